@@ -21,6 +21,31 @@ pub struct Podcast {
     /// requests when the feed server doesn't provide ETags.
     #[serde(skip, default)]
     pub feed_last_modified: Option<String>,
+
+    /// Update interval the feed declared via `<ttl>` or `sy:updatePeriod`
+    /// on its last full fetch, kept so 304 responses can still honour it.
+    #[serde(skip, default)]
+    pub feed_update_interval_secs: Option<i32>,
+
+    /// When the feed was last fetched, successfully or not.
+    #[serde(default)]
+    pub last_fetch_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub last_success_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Failed fetches since the last success; non-zero means the feed is
+    /// currently broken and `last_error` says why.
+    #[serde(default)]
+    pub consecutive_failures: i32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Earliest time the feed will be fetched again. `None` means it is due
+    /// on the next refresh cycle.
+    #[serde(default)]
+    pub next_fetch_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Stable `podcast:guid` identifying the show across feed URL changes.
+    #[serde(default)]
+    pub podcast_guid: Option<String>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -43,6 +68,22 @@ pub struct PodcastWithEpisodeStats {
     pub feed_etag: Option<String>,
     #[serde(skip, default)]
     pub feed_last_modified: Option<String>,
+    #[serde(skip, default)]
+    pub feed_update_interval_secs: Option<i32>,
+
+    #[serde(default)]
+    pub last_fetch_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub last_success_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub consecutive_failures: i32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub next_fetch_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    #[serde(default)]
+    pub podcast_guid: Option<String>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -62,6 +103,98 @@ pub struct Episode {
     pub thumbnail_url: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_updated: chrono::DateTime<chrono::Utc>,
+
+    /// Set when the episode disappeared from its feed. Deleted episodes are
+    /// hidden from listings and reach clients as sync tombstones instead.
+    #[serde(skip, default)]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Season and episode numbers from `itunes:season`/`itunes:episode`,
+    /// falling back to `podcast:season`/`podcast:episode`.
+    #[serde(default)]
+    pub season: Option<i32>,
+    #[serde(default)]
+    pub episode_number: Option<i32>,
+    /// `itunes:episodeType`: `full`, `trailer` or `bonus`.
+    #[serde(default)]
+    pub episode_type: Option<String>,
+    /// `podcast:chapters` document and its MIME type.
+    #[serde(default)]
+    pub chapters_url: Option<String>,
+    #[serde(default)]
+    pub chapters_type: Option<String>,
+}
+
+impl Episode {
+    pub fn is_trailer(&self) -> bool {
+        self.episode_type.as_deref() == Some("trailer")
+    }
+}
+
+/// A `podcast:transcript` link.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Transcript {
+    pub url: String,
+    pub mime_type: String,
+    pub language: Option<String>,
+    pub rel: Option<String>,
+}
+
+/// A timestamped piece of an episode's transcript, normalised from SRT,
+/// WebVTT, JSON or HTML. Times are in seconds from the start.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TranscriptSegment {
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    pub speaker: Option<String>,
+    pub body: String,
+}
+
+/// A `podcast:person` credited on an episode.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Person {
+    pub name: String,
+    pub role: String,
+    pub group: String,
+    pub img: Option<String>,
+    pub href: Option<String>,
+}
+
+/// A `podcast:funding` link for supporting a show.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Funding {
+    pub url: String,
+    pub message: String,
+}
+
+/// A chapter marker from a `podcast:chapters` JSON document or the audio
+/// file's ID3 `CHAP` frames. Times are in seconds from the start.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Chapter {
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    pub title: String,
+    pub img: Option<String>,
+    pub url: Option<String>,
+}
+
+/// An episode together with its repeatable Podcasting 2.0 metadata.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct EpisodeDetail {
+    #[serde(flatten)]
+    pub episode: Episode,
+
+    pub transcripts: Vec<Transcript>,
+    pub persons: Vec<Person>,
+}
+
+/// A podcast together with its funding links.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct PodcastDetail {
+    #[serde(flatten)]
+    pub podcast: Podcast,
+
+    pub funding: Vec<Funding>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -75,7 +208,7 @@ pub struct EpisodeWithProgress {
 }
 
 /// An episode with progress and parent podcast metadata, used for the
-/// cross-podcast inbox view.
+/// cross-podcast inbox and queue views.
 #[derive(serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct InboxEpisode {
@@ -89,6 +222,234 @@ pub struct InboxEpisode {
     pub podcast_image_link: String,
 }
 
+/// An episode in the user's library matching a search query. Matched terms
+/// in `snippet` are wrapped in `<mark>` tags; `rank` orders results, best
+/// first.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct SearchResult {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "sqlx", sqlx(flatten))]
+    pub episode: InboxEpisode,
+
+    pub snippet: String,
+    pub rank: f32,
+}
+
+/// A show found in a podcast directory. `feed_url` can be passed straight to
+/// `POST /api/v1/podcasts` to subscribe.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DirectoryPodcast {
+    pub title: String,
+    pub author: Option<String>,
+    pub feed_url: String,
+    pub image_link: Option<String>,
+    pub genre: Option<String>,
+    pub episode_count: Option<i64>,
+}
+
+/// A user's settings for one of their subscriptions.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SubscriptionSettings {
+    /// Default playback speed; 1.0 is normal speed.
+    pub playback_speed: f32,
+    /// Seconds skipped at the start and end of every episode.
+    pub skip_intro_secs: i32,
+    pub skip_outro_secs: i32,
+    /// How many of the newest episodes clients keep downloaded; 0 turns
+    /// auto-download off.
+    pub auto_download: i32,
+    /// Keep the show's episodes out of the inbox.
+    pub hide_from_inbox: bool,
+    /// Whether clients should notify about new episodes.
+    pub notifications: bool,
+    /// Title shown instead of the feed's own.
+    pub title_override: Option<String>,
+    pub state: SubscriptionState,
+}
+
+impl Default for SubscriptionSettings {
+    fn default() -> Self {
+        Self {
+            playback_speed: 1.0,
+            skip_intro_secs: 0,
+            skip_outro_secs: 0,
+            auto_download: 0,
+            hide_from_inbox: false,
+            notifications: true,
+            title_override: None,
+            state: SubscriptionState::Active,
+        }
+    }
+}
+
+impl SubscriptionSettings {
+    pub fn apply(&mut self, patch: SubscriptionSettingsPatch) {
+        if let Some(speed) = patch.playback_speed {
+            self.playback_speed = speed;
+        }
+        if let Some(secs) = patch.skip_intro_secs {
+            self.skip_intro_secs = secs;
+        }
+        if let Some(secs) = patch.skip_outro_secs {
+            self.skip_outro_secs = secs;
+        }
+        if let Some(count) = patch.auto_download {
+            self.auto_download = count;
+        }
+        if let Some(hide) = patch.hide_from_inbox {
+            self.hide_from_inbox = hide;
+        }
+        if let Some(notifications) = patch.notifications {
+            self.notifications = notifications;
+        }
+        if let Some(title) = patch.title_override {
+            self.title_override = Some(title).filter(|t| !t.trim().is_empty());
+        }
+        if let Some(state) = patch.state {
+            self.state = state;
+        }
+    }
+
+    /// The patch that turns these settings into `edited`, leaving out the
+    /// fields that are the same.
+    pub fn diff(&self, edited: &SubscriptionSettings) -> SubscriptionSettingsPatch {
+        fn changed<T: PartialEq + Clone>(old: &T, new: &T) -> Option<T> {
+            (old != new).then(|| new.clone())
+        }
+        SubscriptionSettingsPatch {
+            playback_speed: changed(&self.playback_speed, &edited.playback_speed),
+            skip_intro_secs: changed(&self.skip_intro_secs, &edited.skip_intro_secs),
+            skip_outro_secs: changed(&self.skip_outro_secs, &edited.skip_outro_secs),
+            auto_download: changed(&self.auto_download, &edited.auto_download),
+            hide_from_inbox: changed(&self.hide_from_inbox, &edited.hide_from_inbox),
+            notifications: changed(&self.notifications, &edited.notifications),
+            // An empty title clears the override.
+            title_override: changed(&self.title_override, &edited.title_override)
+                .map(Option::unwrap_or_default),
+            state: changed(&self.state, &edited.state),
+        }
+    }
+}
+
+/// Whether a subscription is in regular use. Paused and archived shows are
+/// kept out of the inbox and aren't auto-downloaded; clients may list
+/// archived shows separately.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionState {
+    #[default]
+    Active,
+    Paused,
+    Archived,
+}
+
+impl SubscriptionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Paused => "paused",
+            Self::Archived => "archived",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(Self::Active),
+            "paused" => Some(Self::Paused),
+            "archived" => Some(Self::Archived),
+            _ => None,
+        }
+    }
+}
+
+/// Body of PATCH /api/v1/podcasts/{id}/settings. Fields left out keep their
+/// value; an empty `title_override` clears it.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SubscriptionSettingsPatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playback_speed: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_intro_secs: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_outro_secs: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_download: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hide_from_inbox: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_override: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<SubscriptionState>,
+}
+
+/// A saved smart list: the episodes of the user's subscriptions that match
+/// `filter`, newest first.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Playlist {
+    pub id: String,
+    pub name: String,
+    pub filter: PlaylistFilter,
+}
+
+/// Predicates an episode has to match to appear in a playlist. The default
+/// filter selects the inbox: every unfinished episode.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlaylistFilter {
+    /// Only episodes of these podcasts; empty means every subscription.
+    #[serde(default)]
+    pub podcast_ids: Vec<String>,
+    #[serde(default)]
+    pub min_duration_secs: Option<i32>,
+    #[serde(default)]
+    pub max_duration_secs: Option<i32>,
+    #[serde(default)]
+    pub progress: ProgressFilter,
+    /// Only episodes published in the last this many days.
+    #[serde(default)]
+    pub published_within_days: Option<i32>,
+}
+
+/// Which episodes a playlist includes by listening state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressFilter {
+    /// Not marked done.
+    #[default]
+    Unfinished,
+    /// Never started and not marked done.
+    Unplayed,
+    /// Started but not finished.
+    InProgress,
+    Done,
+    Any,
+}
+
+impl ProgressFilter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unfinished => "unfinished",
+            Self::Unplayed => "unplayed",
+            Self::InProgress => "in_progress",
+            Self::Done => "done",
+            Self::Any => "any",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "unfinished" => Some(Self::Unfinished),
+            "unplayed" => Some(Self::Unplayed),
+            "in_progress" => Some(Self::InProgress),
+            "done" => Some(Self::Done),
+            "any" => Some(Self::Any),
+            _ => None,
+        }
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct ProgressState {
     pub progress: i32,
@@ -131,9 +492,22 @@ pub struct SyncResponse {
 
 /// A single change entry in the sync response.
 ///
-/// TODO: support `op: "delete"` changes. When implemented, `episode` should
-/// become optional (present only for upserts) and an `episode_tombstone`
-/// field should be added for deletes (containing `id` and `deleted_at`).
+/// `type` is `"episode"` for episode changes and `"subscription"` for
+/// changes to the user's subscription list. An `upsert` means the user
+/// subscribed or changed the subscription's `settings` (clients should fetch
+/// the episodes of podcasts they don't have yet) and a `delete` means they
+/// unsubscribed (clients should drop the podcast and its episodes).
+///
+/// Episode upserts carry the full `episode`; episode deletes carry an
+/// `episode_tombstone` instead.
+///
+/// `type` is `"queue"` when the user's Up Next queue changed. Queue changes
+/// carry the whole queue as episode ids in play order, which replaces the
+/// client's copy; their `podcast_id` is empty.
+///
+/// `type` is `"playlists"` when the user's saved playlists changed. These
+/// carry all of the user's playlists, replacing the client's copies, and an
+/// empty `podcast_id` too.
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct SyncChange {
     pub seq: i64,
@@ -141,5 +515,50 @@ pub struct SyncChange {
     pub change_type: String,
     pub op: String,
     pub podcast_id: String,
-    pub episode: Episode,
+    /// Present for episode upserts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode: Option<Episode>,
+    /// Present for episode deletes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode_tombstone: Option<EpisodeTombstone>,
+    /// Present for queue changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<Vec<String>>,
+    /// Present for playlist changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playlists: Option<Vec<Playlist>>,
+    /// Present for subscription upserts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<SubscriptionSettings>,
+}
+
+/// Marker for an episode that was removed from its feed.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct EpisodeTombstone {
+    pub id: String,
+    pub deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+// ==============================================================================
+// OPML import
+// ==============================================================================
+
+/// Report returned by POST /api/v1/podcasts/import.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct OpmlImportReport {
+    pub imported: usize,
+    pub failed: usize,
+    pub results: Vec<OpmlImportResult>,
+}
+
+/// Outcome for a single feed in an OPML import. Exactly one of
+/// `podcast_id` (subscribed) or `error` is set.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct OpmlImportResult {
+    pub feed_url: String,
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub podcast_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
@@ -0,0 +1,14 @@
+-- Per-user log of subscription changes for delta-based sync.
+-- Rows draw from the episode_change sequence so both logs share one
+-- monotonic seq space and can be merged behind a single sync cursor.
+
+CREATE TABLE subscription_change (
+    seq BIGINT PRIMARY KEY DEFAULT nextval('episode_change_seq_seq'),
+    user_id UUID NOT NULL REFERENCES users(id),
+    podcast_id TEXT NOT NULL REFERENCES podcast(id),
+    op TEXT NOT NULL CHECK (op IN ('upsert', 'delete')),
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX subscription_change_user_seq_idx
+    ON subscription_change(user_id, seq);
@@ -0,0 +1,4 @@
+-- Soft-delete marker for episodes the publisher pulled from the feed. Rows
+-- are kept so existing progress and change-log references stay valid, and
+-- clients learn about the removal through a 'delete' change.
+ALTER TABLE episode ADD COLUMN deleted_at TIMESTAMPTZ;
@@ -0,0 +1,9 @@
+-- Single-row bookkeeping for change-log compaction. `pruned_through_seq` is
+-- the highest seq removed by horizon pruning; sync cursors below it can no
+-- longer be served and must re-bootstrap.
+CREATE TABLE sync_log_state (
+    id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+    pruned_through_seq BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO sync_log_state DEFAULT VALUES;
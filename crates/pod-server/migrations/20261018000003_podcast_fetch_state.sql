@@ -0,0 +1,8 @@
+-- Feed fetch bookkeeping. Failed fetches push next_fetch_at out with
+-- exponential backoff; NULL means the podcast is due on the next refresh.
+ALTER TABLE podcast
+    ADD COLUMN last_fetch_at TIMESTAMPTZ,
+    ADD COLUMN last_success_at TIMESTAMPTZ,
+    ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN last_error TEXT,
+    ADD COLUMN next_fetch_at TIMESTAMPTZ;
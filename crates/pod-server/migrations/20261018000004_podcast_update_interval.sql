@@ -0,0 +1,3 @@
+-- Update interval declared by the feed (<ttl> / sy:updatePeriod), in
+-- seconds. Remembered so conditional 304 fetches can still honour it.
+ALTER TABLE podcast ADD COLUMN feed_update_interval_secs INTEGER;
@@ -0,0 +1,16 @@
+-- WebSub (PubSubHubbub) subscriptions to feed hubs, one per podcast.
+-- Rows are 'pending' until the hub verifies intent, after which
+-- lease_expires_at tracks when the subscription must be renewed.
+CREATE TABLE websub_subscription (
+    podcast_id TEXT PRIMARY KEY REFERENCES podcast(id) ON DELETE CASCADE,
+    hub_url TEXT NOT NULL,
+    topic_url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    state TEXT NOT NULL DEFAULT 'pending' CHECK (state IN ('pending', 'active', 'denied')),
+    lease_expires_at TIMESTAMPTZ,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_push_at TIMESTAMPTZ
+);
+
+CREATE INDEX websub_subscription_lease_idx
+    ON websub_subscription(lease_expires_at);
@@ -0,0 +1,41 @@
+-- Podcasting 2.0 and iTunes episode metadata. Single-valued tags live on
+-- the episode and podcast rows; repeatable ones get their own tables,
+-- ordered by their position in the feed.
+ALTER TABLE podcast
+    ADD COLUMN podcast_guid TEXT;
+
+ALTER TABLE episode
+    ADD COLUMN season INTEGER,
+    ADD COLUMN episode_number INTEGER,
+    ADD COLUMN episode_type TEXT,
+    ADD COLUMN chapters_url TEXT,
+    ADD COLUMN chapters_type TEXT;
+
+CREATE TABLE episode_transcript (
+    episode_id TEXT NOT NULL REFERENCES episode(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    language TEXT,
+    rel TEXT,
+    PRIMARY KEY (episode_id, position)
+);
+
+CREATE TABLE episode_person (
+    episode_id TEXT NOT NULL REFERENCES episode(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    role TEXT NOT NULL,
+    person_group TEXT NOT NULL,
+    img TEXT,
+    href TEXT,
+    PRIMARY KEY (episode_id, position)
+);
+
+CREATE TABLE podcast_funding (
+    podcast_id TEXT NOT NULL REFERENCES podcast(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    message TEXT NOT NULL,
+    PRIMARY KEY (podcast_id, position)
+);
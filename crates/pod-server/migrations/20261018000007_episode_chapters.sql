@@ -0,0 +1,21 @@
+-- Chapters fetched on demand from an episode's podcast:chapters document or
+-- the ID3 CHAP frames of its audio. episode_chapter_source records where and
+-- when they were fetched so the cache can be invalidated when the source
+-- changes and failed fetches retried.
+CREATE TABLE episode_chapter (
+    episode_id TEXT NOT NULL REFERENCES episode(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    start_time DOUBLE PRECISION NOT NULL,
+    end_time DOUBLE PRECISION,
+    title TEXT NOT NULL,
+    img TEXT,
+    url TEXT,
+    PRIMARY KEY (episode_id, position)
+);
+
+CREATE TABLE episode_chapter_source (
+    episode_id TEXT PRIMARY KEY REFERENCES episode(id) ON DELETE CASCADE,
+    source_url TEXT NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    error TEXT
+);
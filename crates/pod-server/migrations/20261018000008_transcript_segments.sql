@@ -0,0 +1,24 @@
+-- Transcripts downloaded from an episode's podcast:transcript links and
+-- normalised into timestamped segments. Like chapters they are fetched on
+-- demand; episode_transcript_source records which link was used so the
+-- cache can be invalidated when it changes and failed fetches retried.
+CREATE TABLE episode_transcript_segment (
+    episode_id TEXT NOT NULL REFERENCES episode(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    start_time DOUBLE PRECISION NOT NULL,
+    end_time DOUBLE PRECISION,
+    speaker TEXT,
+    body TEXT NOT NULL,
+    body_tsv TSVECTOR GENERATED ALWAYS AS (to_tsvector('simple', body)) STORED,
+    PRIMARY KEY (episode_id, position)
+);
+
+CREATE INDEX episode_transcript_segment_body_tsv_idx
+    ON episode_transcript_segment USING GIN (body_tsv);
+
+CREATE TABLE episode_transcript_source (
+    episode_id TEXT PRIMARY KEY REFERENCES episode(id) ON DELETE CASCADE,
+    source_url TEXT NOT NULL,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    error TEXT
+);
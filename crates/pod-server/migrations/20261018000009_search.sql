@@ -0,0 +1,25 @@
+-- Full-text search over a user's library. The search documents are built by
+-- immutable functions so the GIN expression indexes and the queries in
+-- Db::search_episodes are guaranteed to use the same expression. Markup in
+-- descriptions is stripped so tag and attribute names don't match. The
+-- 'simple' configuration doesn't stem, which suits multilingual libraries.
+CREATE FUNCTION episode_search_vector(title TEXT, summary TEXT, content_encoded TEXT)
+RETURNS TSVECTOR
+LANGUAGE SQL IMMUTABLE PARALLEL SAFE AS $$
+    SELECT setweight(to_tsvector('simple', coalesce(title, '')), 'A')
+        || setweight(to_tsvector('simple', regexp_replace(coalesce(summary, ''), '<[^>]*>', ' ', 'g')), 'B')
+        || setweight(to_tsvector('simple', regexp_replace(coalesce(content_encoded, ''), '<[^>]*>', ' ', 'g')), 'C')
+$$;
+
+CREATE FUNCTION podcast_search_vector(title TEXT, description TEXT)
+RETURNS TSVECTOR
+LANGUAGE SQL IMMUTABLE PARALLEL SAFE AS $$
+    SELECT setweight(to_tsvector('simple', coalesce(title, '')), 'A')
+        || setweight(to_tsvector('simple', regexp_replace(coalesce(description, ''), '<[^>]*>', ' ', 'g')), 'B')
+$$;
+
+CREATE INDEX episode_search_idx
+    ON episode USING GIN (episode_search_vector(title, summary, content_encoded));
+
+CREATE INDEX podcast_search_idx
+    ON podcast USING GIN (podcast_search_vector(title, description));
@@ -0,0 +1,24 @@
+-- Per-user "Up Next" queue. `position` only orders the items; it is not
+-- kept dense, so moving an item to the front or back touches a single row.
+CREATE TABLE queue_item (
+    user_id UUID NOT NULL REFERENCES users(id),
+    episode_id TEXT NOT NULL REFERENCES episode(id),
+    position INTEGER NOT NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, episode_id)
+);
+
+CREATE INDEX queue_item_user_position_idx
+    ON queue_item(user_id, position);
+
+-- Log of queue changes for delta-based sync, sharing the episode_change
+-- sequence like subscription_change. Rows carry no payload: clients are
+-- sent the user's whole queue, so only the latest row per user matters.
+CREATE TABLE queue_change (
+    seq BIGINT PRIMARY KEY DEFAULT nextval('episode_change_seq_seq'),
+    user_id UUID NOT NULL REFERENCES users(id),
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX queue_change_user_seq_idx
+    ON queue_change(user_id, seq);
@@ -0,0 +1,28 @@
+-- Saved smart filters ("playlists") over a user's subscriptions. Each
+-- column is one predicate; NULL (or an empty podcast list) leaves it out.
+CREATE TABLE playlist (
+    id TEXT PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id),
+    name TEXT NOT NULL,
+    podcast_ids TEXT[] NOT NULL DEFAULT '{}',
+    min_duration_secs INTEGER,
+    max_duration_secs INTEGER,
+    -- One of unfinished, unplayed, in_progress, done or any.
+    progress TEXT NOT NULL DEFAULT 'unfinished',
+    published_within_days INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX playlist_user_idx ON playlist(user_id);
+
+-- Log of playlist changes for delta-based sync. Like queue_change, rows
+-- carry no payload: clients are sent all of the user's playlists.
+CREATE TABLE playlist_change (
+    seq BIGINT PRIMARY KEY DEFAULT nextval('episode_change_seq_seq'),
+    user_id UUID NOT NULL REFERENCES users(id),
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX playlist_change_user_seq_idx
+    ON playlist_change(user_id, seq);
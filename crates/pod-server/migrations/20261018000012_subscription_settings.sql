@@ -0,0 +1,12 @@
+-- Per-subscription settings. Defaults match how shows behaved before they
+-- had settings.
+ALTER TABLE user_subscription
+    ADD COLUMN playback_speed REAL NOT NULL DEFAULT 1.0,
+    ADD COLUMN skip_intro_secs INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN skip_outro_secs INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN auto_download INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN hide_from_inbox BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN notifications BOOLEAN NOT NULL DEFAULT true,
+    ADD COLUMN title_override TEXT,
+    -- One of active, paused or archived.
+    ADD COLUMN state TEXT NOT NULL DEFAULT 'active';
@@ -1,24 +1,113 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use tokio::task::JoinSet;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 use pod_model::{
-    EpisodeWithProgress, Podcast, PodcastWithEpisodeStats, ProgressState, ProgressSyncResponse,
-    SyncChange, SyncResponse,
+    Chapter, DirectoryPodcast, EpisodeDetail, EpisodeTombstone, EpisodeWithProgress,
+    OpmlImportReport, OpmlImportResult, Playlist, PlaylistFilter, Podcast, PodcastDetail,
+    PodcastWithEpisodeStats, ProgressState, ProgressSyncResponse, SearchResult,
+    SubscriptionSettings, SubscriptionSettingsPatch, SyncChange, SyncResponse, TranscriptSegment,
 };
 
-use crate::{db::Db, feed::entry_to_episode, http::errors::AppError};
+use crate::{
+    chapters,
+    db::{Db, SearchCursor},
+    directory::CatalogProvider,
+    feed::{entry_to_episode, FeedExtensions, GetFeedError, RefreshHints},
+    http::errors::AppError,
+    model::WebSubSubscription,
+    opml::OpmlFeed,
+    transcripts,
+    websub::{self, HubLink},
+};
+
+/// Maximum number of feeds fetched concurrently during an OPML import.
+const OPML_IMPORT_CONCURRENCY: usize = 8;
+
+/// First retry delay after a failed feed fetch.
+const FAILURE_BACKOFF_BASE: chrono::Duration = chrono::Duration::minutes(10);
+/// Upper bound on the retry delay for a persistently failing feed.
+const FAILURE_BACKOFF_MAX: chrono::Duration = chrono::Duration::hours(24);
+
+/// How long a failed chapter or transcript fetch is cached before it is
+/// retried.
+const FETCH_RETRY_AFTER: chrono::Duration = chrono::Duration::hours(1);
+
+/// Range of per-subscription playback speeds.
+const MIN_PLAYBACK_SPEED: f32 = 0.25;
+const MAX_PLAYBACK_SPEED: f32 = 4.0;
+
+/// Longest `published_within_days` window a playlist may ask for, about a
+/// century.
+const MAX_PUBLISHED_WITHIN_DAYS: i32 = 36_500;
+
+/// Renew WebSub leases this long before they expire.
+const WEBSUB_RENEW_BEFORE: chrono::Duration = chrono::Duration::days(1);
+/// How long to wait for a hub to verify a request before sending it again.
+const WEBSUB_RETRY_AFTER: chrono::Duration = chrono::Duration::hours(1);
 
 #[derive(Clone)]
 pub struct App {
     db: Arc<Db>,
     http: reqwest::Client,
+    refresh: RefreshPolicy,
+    /// Public base URL for WebSub callbacks; `None` disables WebSub.
+    websub_base_url: Option<String>,
+    catalog: Arc<dyn CatalogProvider>,
 }
 
 impl App {
-    pub fn new(db: Arc<Db>, http: reqwest::Client) -> Self {
-        Self { db, http }
+    pub fn new(
+        db: Arc<Db>,
+        http: reqwest::Client,
+        refresh: RefreshPolicy,
+        websub_base_url: Option<String>,
+        catalog: Arc<dyn CatalogProvider>,
+    ) -> Self {
+        Self {
+            db,
+            http,
+            refresh,
+            websub_base_url,
+            catalog,
+        }
+    }
+}
+
+/// Bounds for per-feed refresh scheduling.
+#[derive(Debug, Clone)]
+pub struct RefreshPolicy {
+    /// Interval for feeds that don't declare one.
+    pub default_interval: chrono::Duration,
+    pub min_interval: chrono::Duration,
+    pub max_interval: chrono::Duration,
+}
+
+impl RefreshPolicy {
+    /// Interval until the next fetch after a successful one. The feed's own
+    /// `<ttl>`/`sy:updatePeriod` replaces the default interval, and
+    /// `Cache-Control: max-age` can only lengthen it; the result is clamped
+    /// to the configured bounds.
+    pub fn next_interval(&self, hints: &RefreshHints) -> chrono::Duration {
+        let interval = hints.feed_interval.unwrap_or(self.default_interval);
+        let interval = interval.max(hints.max_age.unwrap_or_else(chrono::Duration::zero));
+        interval.clamp(self.min_interval, self.max_interval.max(self.min_interval))
+    }
+
+    /// Interval until the next attempt after a failed fetch. A server-sent
+    /// `Retry-After` is honoured up to the maximum interval.
+    pub fn retry_interval(
+        &self,
+        consecutive_failures: i32,
+        retry_after: Option<chrono::Duration>,
+    ) -> chrono::Duration {
+        let backoff = failure_backoff(consecutive_failures);
+        match retry_after {
+            Some(retry_after) => backoff.max(retry_after.min(self.max_interval)),
+            None => backoff,
+        }
     }
 }
 
@@ -45,6 +134,138 @@ impl App {
         Ok(podcast)
     }
 
+    /// A subscribed podcast together with its funding links.
+    pub async fn get_podcast_detail_for_user(
+        &self,
+        username: &str,
+        podcast_id: &str,
+    ) -> Result<Option<PodcastDetail>> {
+        let Some(podcast) = self.db.get_podcast_for_user(username, podcast_id).await? else {
+            return Ok(None);
+        };
+        let funding = self.db.get_podcast_funding(podcast_id).await?;
+        Ok(Some(PodcastDetail { podcast, funding }))
+    }
+
+    /// An episode of a subscribed podcast together with its transcripts and
+    /// credited persons.
+    pub async fn get_episode_detail(
+        &self,
+        username: &str,
+        episode_id: &str,
+    ) -> Result<Option<EpisodeDetail>> {
+        let Some(episode) = self.db.get_episode_for_user(username, episode_id).await? else {
+            return Ok(None);
+        };
+        let transcripts = self.db.get_episode_transcripts(episode_id).await?;
+        let persons = self.db.get_episode_persons(episode_id).await?;
+        Ok(Some(EpisodeDetail {
+            episode,
+            transcripts,
+            persons,
+        }))
+    }
+
+    /// Chapters of an episode of a subscribed podcast. They're fetched on
+    /// first request from the episode's `podcast:chapters` document, or the
+    /// ID3 tag of MP3 audio when there is none, and cached until the source
+    /// changes. Fetch failures yield no chapters rather than an error.
+    pub async fn get_episode_chapters(
+        &self,
+        username: &str,
+        episode_id: &str,
+    ) -> Result<Vec<Chapter>> {
+        let episode = self
+            .db
+            .get_episode_for_user(username, episode_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("episode".to_string(), episode_id.to_string()))?;
+
+        let source_url = episode.chapters_url.as_deref().unwrap_or(&episode.audio_url);
+        if let Some(cached) = self.db.get_chapter_source(episode_id).await? {
+            let retry_at = cached.fetched_at + FETCH_RETRY_AFTER;
+            if cached.source_url == source_url
+                && (cached.error.is_none() || retry_at > chrono::Utc::now())
+            {
+                return Ok(self.db.get_episode_chapters(episode_id).await?);
+            }
+        }
+
+        let result = match &episode.chapters_url {
+            Some(url) => chapters::fetch_json(&self.http, url).await,
+            None if matches!(episode.audio_type.as_str(), "audio/mpeg" | "audio/mp3") => {
+                chapters::fetch_id3(&self.http, &episode.audio_url).await
+            }
+            None => Ok(Vec::new()),
+        };
+        let chapters = match result {
+            Ok(chapters) => {
+                debug!(episode_id, chapters = chapters.len(), "fetched chapters");
+                self.db
+                    .store_episode_chapters(episode_id, source_url, &chapters, None)
+                    .await?;
+                chapters
+            }
+            Err(e) => {
+                warn!(episode_id, source_url, "error fetching chapters: {}", e);
+                self.db
+                    .store_episode_chapters(episode_id, source_url, &[], Some(&e.to_string()))
+                    .await?;
+                Vec::new()
+            }
+        };
+        Ok(chapters)
+    }
+
+    /// Transcript segments of an episode of a subscribed podcast, optionally
+    /// only those matching a full-text `query`. The transcript is downloaded
+    /// on first request from the episode's best supported
+    /// `podcast:transcript` link and cached like chapters.
+    pub async fn get_episode_transcript(
+        &self,
+        username: &str,
+        episode_id: &str,
+        query: Option<&str>,
+    ) -> Result<Vec<TranscriptSegment>> {
+        self.db
+            .get_episode_for_user(username, episode_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("episode".to_string(), episode_id.to_string()))?;
+
+        let links = self.db.get_episode_transcripts(episode_id).await?;
+        let Some((link, format)) = transcripts::preferred(&links) else {
+            return Ok(Vec::new());
+        };
+
+        let cached = self.db.get_transcript_source(episode_id).await?;
+        let fresh = cached.is_some_and(|c| {
+            c.source_url == link.url
+                && (c.error.is_none() || c.fetched_at + FETCH_RETRY_AFTER > chrono::Utc::now())
+        });
+        if !fresh {
+            match transcripts::fetch(&self.http, &link.url, format).await {
+                Ok(segments) => {
+                    debug!(episode_id, segments = segments.len(), "fetched transcript");
+                    self.db
+                        .store_transcript_segments(episode_id, &link.url, &segments, None)
+                        .await?;
+                }
+                Err(e) => {
+                    warn!(episode_id, url = link.url, "error fetching transcript: {}", e);
+                    self.db
+                        .store_transcript_segments(episode_id, &link.url, &[], Some(&e.to_string()))
+                        .await?;
+                }
+            }
+        }
+
+        let segments = match query {
+            Some(query) => self.db.search_transcript_segments(episode_id, query).await?,
+            None => self.db.get_transcript_segments(episode_id).await?,
+        };
+        Ok(segments)
+    }
+
     pub async fn get_podcasts_for_user(
         &self,
         username: &str,
@@ -96,6 +317,13 @@ impl App {
             last_updated: now,
             feed_etag: None,
             feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
         };
         self.db.insert_podcast(&podcast).await?;
 
@@ -109,8 +337,118 @@ impl App {
         Ok(())
     }
 
+    /// Remove the user's subscription to a podcast. The podcast and its
+    /// episodes stay in the database since other users may still follow it.
+    pub async fn unsubscribe_from_podcast(&self, username: &str, podcast_id: &str) -> Result<()> {
+        let removed = self.db.remove_subscription(username, podcast_id).await?;
+        if !removed {
+            return Err(AppError::NotFound(
+                "subscription".to_string(),
+                podcast_id.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn get_subscription_settings(
+        &self,
+        username: &str,
+        podcast_id: &str,
+    ) -> Result<SubscriptionSettings> {
+        self.db
+            .get_subscription_settings(username, podcast_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("subscription".to_string(), podcast_id.to_string()))
+    }
+
+    /// Apply a partial update to a subscription's settings and return the
+    /// result.
+    pub async fn update_subscription_settings(
+        &self,
+        username: &str,
+        podcast_id: &str,
+        patch: SubscriptionSettingsPatch,
+    ) -> Result<SubscriptionSettings> {
+        let mut settings = self.get_subscription_settings(username, podcast_id).await?;
+        settings.apply(patch);
+        validate_subscription_settings(&settings)?;
+        if !self
+            .db
+            .update_subscription_settings(username, podcast_id, &settings)
+            .await?
+        {
+            return Err(AppError::NotFound(
+                "subscription".to_string(),
+                podcast_id.to_string(),
+            ));
+        }
+        Ok(settings)
+    }
+
+    /// Add and subscribe to every feed in an OPML import. Feeds are fetched
+    /// concurrently (bounded by `OPML_IMPORT_CONCURRENCY`); a failing feed is
+    /// reported in its result entry rather than failing the whole import.
+    pub async fn import_opml(&self, username: &str, feeds: Vec<OpmlFeed>) -> OpmlImportReport {
+        let mut seen = HashSet::new();
+        let mut results: Vec<OpmlImportResult> = feeds
+            .into_iter()
+            .filter(|feed| seen.insert(feed.feed_url.clone()))
+            .map(|feed| OpmlImportResult {
+                feed_url: feed.feed_url,
+                title: feed.title,
+                podcast_id: None,
+                error: None,
+            })
+            .collect();
+
+        let mut set = JoinSet::new();
+        let mut task_index = HashMap::new();
+        let mut pending = 0..results.len();
+        loop {
+            while set.len() < OPML_IMPORT_CONCURRENCY {
+                let Some(i) = pending.next() else { break };
+                let app = self.clone();
+                let username = username.to_string();
+                let feed_url = results[i].feed_url.clone();
+                let handle = set.spawn(async move {
+                    let podcast = app.add_podcast(&feed_url).await?;
+                    app.subscribe_to_podcast(&username, &podcast.id).await?;
+                    Ok::<_, AppError>(podcast)
+                });
+                task_index.insert(handle.id(), i);
+            }
+
+            let Some(joined) = set.join_next_with_id().await else {
+                break;
+            };
+            let (i, outcome) = match joined {
+                Ok((id, outcome)) => (task_index[&id], outcome.map_err(|e| e.to_string())),
+                Err(e) => (task_index[&e.id()], Err(format!("import task failed: {}", e))),
+            };
+            match outcome {
+                Ok(podcast) => {
+                    results[i].podcast_id = Some(podcast.id);
+                    results[i].title.get_or_insert(podcast.title);
+                }
+                Err(e) => {
+                    error!("error importing feed {}: {}", results[i].feed_url, e);
+                    results[i].error = Some(e);
+                }
+            }
+        }
+
+        let failed = results.iter().filter(|r| r.error.is_some()).count();
+        OpmlImportReport {
+            imported: results.len() - failed,
+            failed,
+            results,
+        }
+    }
+
+    /// Refresh every podcast that is due. Podcasts backing off after failed
+    /// fetches are skipped until their `next_fetch_at`.
     pub async fn refresh_all_podcasts(&self) -> Result<()> {
-        let podcasts = self.db.list_podcasts().await?;
+        let podcasts = self.db.list_podcasts_due(chrono::Utc::now()).await?;
 
         let mut set = JoinSet::new();
         for podcast in podcasts {
@@ -133,6 +471,9 @@ impl App {
         Ok(())
     }
 
+    /// Fetch a podcast's feed and apply it, recording the outcome in the
+    /// podcast's fetch state. The next fetch is scheduled from the feed's
+    /// refresh hints, or with exponential backoff after a failure.
     pub async fn refresh_podcast(&self, podcast_id: &str) -> Result<()> {
         let podcast = self.db.get_podcast_by_id(podcast_id).await?;
         let Some(podcast) = podcast else {
@@ -142,6 +483,37 @@ impl App {
             ));
         };
 
+        match self.fetch_and_apply_feed(&podcast).await {
+            Ok(hints) => {
+                let next_fetch_at = chrono::Utc::now() + self.refresh.next_interval(&hints);
+                self.db
+                    .record_fetch_success(podcast_id, Some(next_fetch_at))
+                    .await?;
+                Ok(())
+            }
+            Err(e) => {
+                let failures = podcast.consecutive_failures.saturating_add(1);
+                let retry_after = match &e {
+                    AppError::GetFeedError(GetFeedError::Status { retry_after, .. }) => {
+                        *retry_after
+                    }
+                    _ => None,
+                };
+                let next_fetch_at =
+                    chrono::Utc::now() + self.refresh.retry_interval(failures, retry_after);
+                self.db
+                    .record_fetch_failure(podcast_id, &e.to_string(), next_fetch_at)
+                    .await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Fetch the feed and apply new, changed and removed episodes. Returns
+    /// the refresh hints used to schedule the next fetch.
+    async fn fetch_and_apply_feed(&self, podcast: &Podcast) -> Result<RefreshHints> {
+        let podcast_id = podcast.id.as_str();
+
         debug!(
             podcast_id,
             title = podcast.title,
@@ -150,22 +522,27 @@ impl App {
             "refreshing podcast feed"
         );
 
-        let now = chrono::Utc::now();
-
         let cache = crate::feed::FeedCacheHeaders {
             etag: podcast.feed_etag.clone(),
             last_modified: podcast.feed_last_modified.clone(),
         };
         let result = crate::feed::get_feed(&self.http, &podcast.feed_url, &cache).await?;
 
-        let crate::feed::FeedResult::Fetched {
-            feed,
-            etag,
-            last_modified,
-        } = result
-        else {
-            debug!(podcast_id, "feed not modified, skipping refresh");
-            return Ok(());
+        let (feed, extensions, etag, last_modified, hints) = match result {
+            crate::feed::FeedResult::Fetched {
+                feed,
+                extensions,
+                etag,
+                last_modified,
+                hints,
+            } => (feed, extensions, etag, last_modified, hints),
+            crate::feed::FeedResult::NotModified { mut hints } => {
+                debug!(podcast_id, "feed not modified, skipping refresh");
+                hints.feed_interval = podcast
+                    .feed_update_interval_secs
+                    .map(|secs| chrono::Duration::seconds(secs.into()));
+                return Ok(hints);
+            }
         };
 
         debug!(
@@ -190,6 +567,61 @@ impl App {
                 .await?;
         }
 
+        let feed_interval_secs = hints
+            .feed_interval
+            .and_then(|d| i32::try_from(d.num_seconds()).ok());
+        if feed_interval_secs != podcast.feed_update_interval_secs {
+            self.db
+                .update_podcast_feed_interval(podcast_id, feed_interval_secs)
+                .await?;
+        }
+
+        if extensions.podcast_guid != podcast.podcast_guid {
+            self.db
+                .update_podcast_guid(podcast_id, extensions.podcast_guid.as_deref())
+                .await?;
+        }
+        if self.db.get_podcast_funding(podcast_id).await? != extensions.funding {
+            self.db
+                .replace_podcast_funding(podcast_id, &extensions.funding)
+                .await?;
+        }
+
+        // Hubs advertised by the feed let us receive pushes instead of
+        // waiting for the next poll. Polling continues as the fallback, so a
+        // hub failure doesn't fail the refresh.
+        if let Err(e) = self
+            .ensure_websub_subscription(podcast_id, &podcast.feed_url, &feed)
+            .await
+        {
+            warn!(podcast_id, "error subscribing to websub hub: {}", e);
+        }
+
+        self.apply_feed_entries(podcast_id, &feed, &extensions, true)
+            .await?;
+
+        Ok(hints)
+    }
+
+    /// Insert new entries, restore reappeared ones and update changed ones.
+    /// With `prune_missing`, stored episodes absent from the feed are
+    /// deleted; pushes may carry only a subset of entries, so they skip it.
+    async fn apply_feed_entries(
+        &self,
+        podcast_id: &str,
+        feed: &feed_rs::model::Feed,
+        extensions: &FeedExtensions,
+        prune_missing: bool,
+    ) -> Result<()> {
+        let now = chrono::Utc::now();
+
+        let extensions_by_id = feed
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item.id.as_str(), extensions.item(i)))
+            .collect::<HashMap<_, _>>();
+
         let feed_episode_ids = feed
             .entries
             .iter()
@@ -215,7 +647,12 @@ impl App {
         );
 
         for entry in new_episodes {
-            let episode = match entry_to_episode(&podcast.id, entry, now) {
+            let episode = match entry_to_episode(
+                podcast_id,
+                entry,
+                &extensions_by_id[entry.id.as_str()],
+                now,
+            ) {
                 Ok(episode) => episode,
                 Err(e) => {
                     error!("error creating episode: {:?}", e);
@@ -226,6 +663,306 @@ impl App {
             self.db.insert_episode(episode).await?;
         }
 
+        let restored = self
+            .db
+            .restore_episodes(podcast_id, &feed_episode_ids)
+            .await?;
+        if restored > 0 {
+            debug!(podcast_id, restored, "restored episodes that reappeared in feed");
+        }
+
+        // Diff the entries we already know about against the stored rows so
+        // publisher corrections (titles, show notes, enclosures, durations)
+        // reach clients.
+        let entries_by_id = feed
+            .entries
+            .iter()
+            .filter(|item| crate::feed::has_audio(item))
+            .map(|item| (item.id.as_str(), item))
+            .collect::<HashMap<_, _>>();
+        let mut updated = 0;
+        for stored in self.db.get_episodes_for_podcast(podcast_id).await? {
+            if new_episode_ids.contains(&stored.id) {
+                continue;
+            }
+            let Some(entry) = entries_by_id.get(stored.id.as_str()) else {
+                continue;
+            };
+            let mut fresh = match entry_to_episode(
+                podcast_id,
+                entry,
+                &extensions_by_id[entry.id.as_str()],
+                now,
+            ) {
+                Ok(episode) => episode,
+                Err(e) => {
+                    error!("error creating episode: {:?}", e);
+                    continue;
+                }
+            };
+            // Undated entries fall back to `now`, which would otherwise look
+            // like a change on every refresh.
+            if entry.published.is_none() {
+                fresh.publication_date = stored.publication_date;
+            }
+            if crate::feed::episode_metadata_changed(&stored, &fresh) {
+                self.db.update_episode(&fresh).await?;
+                updated += 1;
+            }
+        }
+        if updated > 0 {
+            debug!(podcast_id, updated, "updated changed episodes");
+        }
+
+        // Transcripts and persons aren't part of the synced episode, so
+        // they're diffed separately and don't touch the change log.
+        let mut transcripts = self.db.get_transcripts_for_podcast(podcast_id).await?;
+        let mut persons = self.db.get_persons_for_podcast(podcast_id).await?;
+        for stored in self.db.get_episodes_for_podcast(podcast_id).await? {
+            let Some(ext) = extensions_by_id.get(stored.id.as_str()) else {
+                continue;
+            };
+            let stored_transcripts = transcripts.remove(&stored.id).unwrap_or_default();
+            let stored_persons = persons.remove(&stored.id).unwrap_or_default();
+            if stored_transcripts != ext.transcripts || stored_persons != ext.persons {
+                self.db
+                    .replace_episode_extras(&stored.id, &ext.transcripts, &ext.persons)
+                    .await?;
+            }
+        }
+
+        if !prune_missing {
+            return Ok(());
+        }
+
+        // Episodes missing from the feed were pulled by the publisher. Many
+        // feeds only list their most recent entries, so only consider
+        // episodes published within the range the feed still covers.
+        let oldest_in_feed = feed
+            .entries
+            .iter()
+            .filter(|item| crate::feed::has_audio(item))
+            .filter_map(|item| item.published)
+            .min();
+        if let Some(oldest) = oldest_in_feed {
+            let removed_ids = self
+                .db
+                .find_removed_episode_ids(podcast_id, &feed_episode_ids, oldest)
+                .await?;
+            if !removed_ids.is_empty() {
+                debug!(
+                    podcast_id,
+                    removed = removed_ids.len(),
+                    "deleting episodes dropped from feed"
+                );
+                self.db.delete_episodes(podcast_id, &removed_ids).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Subscribe to the hub the feed advertises, if any. Switching hubs or
+    /// topics starts a fresh subscription; a feed that stops advertising a
+    /// hub drops back to polling only. Either way the old hub is told to stop
+    /// pushing.
+    async fn ensure_websub_subscription(
+        &self,
+        podcast_id: &str,
+        feed_url: &str,
+        feed: &feed_rs::model::Feed,
+    ) -> Result<()> {
+        if self.websub_base_url.is_none() {
+            return Ok(());
+        }
+
+        let existing = self.db.get_websub_subscription(podcast_id).await?;
+        let Some(hub) = websub::discover_hub(feed, feed_url) else {
+            if let Some(sub) = existing {
+                debug!(podcast_id, "feed no longer advertises a websub hub");
+                self.db.delete_websub_subscription(podcast_id).await?;
+                self.cancel_websub_subscription(&sub).await;
+            }
+            return Ok(());
+        };
+
+        let secret = match existing {
+            Some(sub) if sub.hub_url == hub.hub_url && sub.topic_url == hub.topic_url => {
+                // Active subscriptions are renewed by `renew_websub_leases`;
+                // unverified requests are retried once they go stale.
+                let stale = sub.requested_at < chrono::Utc::now() - WEBSUB_RETRY_AFTER;
+                if sub.state == "active" || !stale {
+                    return Ok(());
+                }
+                sub.secret
+            }
+            Some(sub) => {
+                debug!(podcast_id, hub = sub.hub_url, "feed moved to another websub hub or topic");
+                self.cancel_websub_subscription(&sub).await;
+                websub::generate_secret()
+            }
+            None => websub::generate_secret(),
+        };
+
+        self.request_websub_subscription(podcast_id, &hub, &secret)
+            .await
+    }
+
+    /// Record a subscription request and send it to the hub. The row is
+    /// written first since hubs may verify before answering the request.
+    async fn request_websub_subscription(
+        &self,
+        podcast_id: &str,
+        hub: &HubLink,
+        secret: &str,
+    ) -> Result<()> {
+        let Some(base_url) = &self.websub_base_url else {
+            return Ok(());
+        };
+        let callback_url = websub_callback_url(base_url, podcast_id)
+            .ok_or_else(|| AppError::BadRequest(format!("invalid base URL {}", base_url)))?;
+
+        self.db
+            .upsert_websub_subscription(podcast_id, &hub.hub_url, &hub.topic_url, secret)
+            .await?;
+        debug!(podcast_id, hub = hub.hub_url, "requesting websub subscription");
+        websub::send_request(
+            &self.http,
+            websub::Mode::Subscribe,
+            hub,
+            &callback_url,
+            secret,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Ask the hub of a subscription we've given up to stop pushing. Failures
+    /// are only logged: pushes from it no longer verify once the
+    /// subscription is replaced or deleted.
+    async fn cancel_websub_subscription(&self, sub: &WebSubSubscription) {
+        let Some(callback_url) = self
+            .websub_base_url
+            .as_deref()
+            .and_then(|base_url| websub_callback_url(base_url, &sub.podcast_id))
+        else {
+            return;
+        };
+        let hub = HubLink {
+            hub_url: sub.hub_url.clone(),
+            topic_url: sub.topic_url.clone(),
+        };
+        debug!(podcast_id = sub.podcast_id, hub = hub.hub_url, "cancelling websub subscription");
+        if let Err(e) = websub::send_request(
+            &self.http,
+            websub::Mode::Unsubscribe,
+            &hub,
+            &callback_url,
+            &sub.secret,
+        )
+        .await
+        {
+            warn!(podcast_id = sub.podcast_id, "error unsubscribing from websub hub: {}", e);
+        }
+    }
+
+    /// Answer a hub's intent-verification request. Returns whether the
+    /// request matches what we asked for, in which case the caller echoes
+    /// the challenge.
+    pub async fn verify_websub_intent(
+        &self,
+        podcast_id: &str,
+        mode: &str,
+        topic: &str,
+        lease_seconds: Option<i64>,
+    ) -> Result<bool> {
+        let subscription = self
+            .db
+            .get_websub_subscription(podcast_id)
+            .await?
+            .filter(|sub| sub.topic_url == topic);
+
+        match (mode, subscription) {
+            ("subscribe", Some(_)) => {
+                let Some(expires_at) = websub::lease_expiry(lease_seconds, chrono::Utc::now())
+                else {
+                    return Ok(false);
+                };
+                self.db
+                    .activate_websub_subscription(podcast_id, expires_at)
+                    .await?;
+                debug!(podcast_id, "websub subscription verified");
+                Ok(true)
+            }
+            // Only confirm unsubscribes we no longer want: the topic is gone,
+            // or is still waiting on the hub we're moving to.
+            ("unsubscribe", sub) => Ok(sub.is_none_or(|sub| sub.state != "active")),
+            ("denied", Some(_)) => {
+                warn!(podcast_id, "websub hub denied subscription");
+                self.db.deny_websub_subscription(podcast_id).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Apply a content push from a hub. Pushes with a missing or invalid
+    /// signature are acknowledged but ignored, as WebSub requires.
+    pub async fn handle_websub_push(
+        &self,
+        podcast_id: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<()> {
+        let subscription = self
+            .db
+            .get_websub_subscription(podcast_id)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound("websub subscription".to_string(), podcast_id.to_string())
+            })?;
+
+        let verified = signature
+            .map(|sig| websub::verify_signature(&subscription.secret, sig, body))
+            .unwrap_or(false);
+        if !verified {
+            warn!(podcast_id, "ignoring websub push with missing or invalid signature");
+            return Ok(());
+        }
+
+        let (feed, extensions) = crate::feed::parse_feed(body)?;
+        debug!(podcast_id, entries = feed.entries.len(), "received websub push");
+        self.db.record_websub_push(podcast_id).await?;
+        self.apply_feed_entries(podcast_id, &feed, &extensions, false)
+            .await
+    }
+
+    /// Re-subscribe to hubs whose leases are about to expire.
+    pub async fn renew_websub_leases(&self) -> Result<()> {
+        if self.websub_base_url.is_none() {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now();
+        let expiring = self
+            .db
+            .list_websub_subscriptions_expiring(
+                now + WEBSUB_RENEW_BEFORE,
+                now - WEBSUB_RETRY_AFTER,
+            )
+            .await?;
+        for sub in expiring {
+            let hub = HubLink {
+                hub_url: sub.hub_url,
+                topic_url: sub.topic_url,
+            };
+            if let Err(e) = self
+                .request_websub_subscription(&sub.podcast_id, &hub, &sub.secret)
+                .await
+            {
+                warn!(podcast_id = sub.podcast_id, "error renewing websub lease: {}", e);
+            }
+        }
         Ok(())
     }
 
@@ -253,10 +990,31 @@ impl App {
     ) -> Result<Vec<pod_model::InboxEpisode>> {
         Ok(self
             .db
-            .get_inbox_episodes(username, pagination.limit, pagination.cursor)
+            .get_inbox_episodes(
+                username,
+                &PlaylistFilter::default(),
+                pagination.limit,
+                pagination.cursor,
+            )
             .await?)
     }
 
+    pub async fn search_episodes(
+        &self,
+        username: &str,
+        query: &str,
+        limit: i64,
+        cursor: Option<SearchCursor>,
+    ) -> Result<Vec<SearchResult>> {
+        Ok(self.db.search_episodes(username, query, limit, cursor).await?)
+    }
+
+    /// Shows in the podcast directory matching `term`, for discovering feeds
+    /// to pass to [`App::add_podcast`].
+    pub async fn search_directory(&self, term: &str, limit: u32) -> Result<Vec<DirectoryPodcast>> {
+        Ok(self.catalog.search(term, limit).await?)
+    }
+
     pub async fn update_episode_progress(
         &self,
         username: &str,
@@ -287,12 +1045,147 @@ impl App {
         })
     }
 
+    // ==========================================================================
+    // Up Next queue
+    // ==========================================================================
+
+    pub async fn get_queue(&self, username: &str) -> Result<Vec<pod_model::InboxEpisode>> {
+        Ok(self.db.get_queue(username).await?)
+    }
+
+    /// Add an episode to the end of the queue, or move it to the front when
+    /// `front` is set. Returns the updated queue.
+    pub async fn enqueue_episode(
+        &self,
+        username: &str,
+        episode_id: &str,
+        front: bool,
+    ) -> Result<Vec<pod_model::InboxEpisode>> {
+        if self.db.get_episode_for_user(username, episode_id).await?.is_none() {
+            return Err(AppError::NotFound(
+                "episode".to_string(),
+                episode_id.to_string(),
+            ));
+        }
+        self.db.enqueue_episode(username, episode_id, front).await?;
+        self.get_queue(username).await
+    }
+
+    /// Remove an episode from the queue. Returns the updated queue.
+    pub async fn dequeue_episode(
+        &self,
+        username: &str,
+        episode_id: &str,
+    ) -> Result<Vec<pod_model::InboxEpisode>> {
+        if !self.db.dequeue_episode(username, episode_id).await? {
+            return Err(AppError::NotFound(
+                "queued episode".to_string(),
+                episode_id.to_string(),
+            ));
+        }
+        self.get_queue(username).await
+    }
+
+    /// Replace the queue with `episode_ids` in that order; used both to
+    /// reorder it and to push a client's offline edits. Returns the updated
+    /// queue.
+    pub async fn replace_queue(
+        &self,
+        username: &str,
+        episode_ids: &[String],
+    ) -> Result<Vec<pod_model::InboxEpisode>> {
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = episode_ids.iter().find(|id| !seen.insert(id.as_str())) {
+            return Err(AppError::BadRequest(format!(
+                "episode {} is listed more than once",
+                duplicate
+            )));
+        }
+        if !self.db.replace_queue(username, episode_ids).await? {
+            return Err(AppError::BadRequest(
+                "queue may only contain episodes of subscribed podcasts".to_string(),
+            ));
+        }
+        self.get_queue(username).await
+    }
+
+    pub async fn clear_queue(&self, username: &str) -> Result<()> {
+        Ok(self.db.clear_queue(username).await?)
+    }
+
+    // ==========================================================================
+    // Playlists
+    // ==========================================================================
+
+    pub async fn list_playlists(&self, username: &str) -> Result<Vec<Playlist>> {
+        Ok(self.db.list_playlists(username).await?)
+    }
+
+    pub async fn get_playlist(&self, username: &str, id: &str) -> Result<Playlist> {
+        self.db
+            .get_playlist(username, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("playlist".to_string(), id.to_string()))
+    }
+
+    pub async fn create_playlist(
+        &self,
+        username: &str,
+        name: &str,
+        filter: &PlaylistFilter,
+    ) -> Result<Playlist> {
+        validate_playlist(name, filter)?;
+        Ok(self.db.insert_playlist(username, name.trim(), filter).await?)
+    }
+
+    pub async fn update_playlist(
+        &self,
+        username: &str,
+        id: &str,
+        name: &str,
+        filter: &PlaylistFilter,
+    ) -> Result<Playlist> {
+        validate_playlist(name, filter)?;
+        self.db
+            .update_playlist(username, id, name.trim(), filter)
+            .await?
+            .ok_or_else(|| AppError::NotFound("playlist".to_string(), id.to_string()))
+    }
+
+    pub async fn delete_playlist(&self, username: &str, id: &str) -> Result<()> {
+        if !self.db.delete_playlist(username, id).await? {
+            return Err(AppError::NotFound("playlist".to_string(), id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Episodes matching a saved playlist's filter, paginated like the inbox.
+    pub async fn get_playlist_episodes(
+        &self,
+        username: &str,
+        id: &str,
+        pagination: CursorPagination,
+    ) -> Result<Vec<pod_model::InboxEpisode>> {
+        let playlist = self.get_playlist(username, id).await?;
+        Ok(self
+            .db
+            .get_inbox_episodes(
+                username,
+                &playlist.filter,
+                pagination.limit,
+                pagination.cursor,
+            )
+            .await?)
+    }
+
     // ==========================================================================
     // Sync protocol
     // ==========================================================================
 
-    /// Fetch episode changes for the user's subscriptions since `since_seq`,
-    /// hydrate each change row with the full episode, and build the sync
+    /// Fetch episode, subscription, queue and playlist changes for the user
+    /// since `since_seq`, hydrate each episode change with the full episode,
+    /// each subscription upsert with its settings and each queue or playlist
+    /// change with the current queue or playlists, and build the sync
     /// response including the opaque cursor and has_more flag.
     pub async fn get_sync_changes(
         &self,
@@ -300,6 +1193,15 @@ impl App {
         since_seq: i64,
         limit: i64,
     ) -> Result<SyncResponse> {
+        // Cursors below the prune watermark may have missed changes that
+        // compaction removed; the client has to re-bootstrap.
+        let pruned_through = self.db.get_pruned_through_seq().await?;
+        if since_seq < pruned_through {
+            return Err(AppError::Gone(
+                "sync cursor has expired, perform a full resync".into(),
+            ));
+        }
+
         let mut rows = self.db.get_sync_changes(username, since_seq, limit).await?;
 
         // We asked for limit+1 rows; if we got that many there are more pages.
@@ -308,27 +1210,120 @@ impl App {
             rows.truncate(limit as usize);
         }
 
+        // Every queue, playlist and subscription change carries the queue,
+        // playlists or settings as they are now, so each is only loaded once
+        // per page.
+        let mut queue: Option<Vec<String>> = None;
+        let mut playlists: Option<Vec<Playlist>> = None;
+        let mut settings: Option<HashMap<String, SubscriptionSettings>> = None;
+
         let mut changes = Vec::with_capacity(rows.len());
         for row in &rows {
-            // TODO: handle row.op == "delete" by emitting an episode_tombstone
-            // instead of hydrating the full episode.
+            if row.change_type == "queue" {
+                let queue = match &queue {
+                    Some(queue) => queue.clone(),
+                    None => queue
+                        .insert(self.db.get_queue_episode_ids(username).await?)
+                        .clone(),
+                };
+                changes.push(SyncChange {
+                    seq: row.seq,
+                    change_type: row.change_type.clone(),
+                    op: row.op.clone(),
+                    podcast_id: String::new(),
+                    episode: None,
+                    episode_tombstone: None,
+                    queue: Some(queue),
+                    playlists: None,
+                    settings: None,
+                });
+                continue;
+            }
+
+            if row.change_type == "playlists" {
+                let playlists = match &playlists {
+                    Some(playlists) => playlists.clone(),
+                    None => playlists
+                        .insert(self.db.list_playlists(username).await?)
+                        .clone(),
+                };
+                changes.push(SyncChange {
+                    seq: row.seq,
+                    change_type: row.change_type.clone(),
+                    op: row.op.clone(),
+                    podcast_id: String::new(),
+                    episode: None,
+                    episode_tombstone: None,
+                    queue: None,
+                    playlists: Some(playlists),
+                    settings: None,
+                });
+                continue;
+            }
+
+            let Some(episode_id) = &row.episode_id else {
+                // Subscription upserts carry the subscription's settings,
+                // deletes only the podcast id.
+                let settings = if row.op == "upsert" {
+                    let all = match &settings {
+                        Some(all) => all,
+                        None => settings
+                            .insert(self.db.get_subscription_settings_for_user(username).await?),
+                    };
+                    all.get(&row.podcast_id).cloned()
+                } else {
+                    None
+                };
+                changes.push(SyncChange {
+                    seq: row.seq,
+                    change_type: row.change_type.clone(),
+                    op: row.op.clone(),
+                    podcast_id: row.podcast_id.clone(),
+                    episode: None,
+                    episode_tombstone: None,
+                    queue: None,
+                    playlists: None,
+                    settings,
+                });
+                continue;
+            };
 
-            // Hydrate the episode. Since we skip deletions, the episode
-            // should always exist.
+            if row.op == "delete" {
+                changes.push(SyncChange {
+                    seq: row.seq,
+                    change_type: "episode".to_string(),
+                    op: "delete".to_string(),
+                    podcast_id: row.podcast_id.clone(),
+                    episode: None,
+                    episode_tombstone: Some(EpisodeTombstone {
+                        id: episode_id.clone(),
+                        deleted_at: row.changed_at,
+                    }),
+                    queue: None,
+                    playlists: None,
+                    settings: None,
+                });
+                continue;
+            }
+
+            // Hydrate the episode. Deleted episodes are only soft-deleted,
+            // so the row always exists.
             let episode = self
                 .db
-                .find_episode_by_id(&row.episode_id)
+                .find_episode_by_id(episode_id)
                 .await?
-                .ok_or_else(|| {
-                    AppError::NotFound("episode".to_string(), row.episode_id.clone())
-                })?;
+                .ok_or_else(|| AppError::NotFound("episode".to_string(), episode_id.clone()))?;
 
             changes.push(SyncChange {
                 seq: row.seq,
                 change_type: "episode".to_string(),
                 op: "upsert".to_string(),
                 podcast_id: row.podcast_id.clone(),
-                episode,
+                episode: Some(episode),
+                episode_tombstone: None,
+                queue: None,
+                playlists: None,
+                settings: None,
             });
         }
 
@@ -349,6 +1344,83 @@ impl App {
         let seq = self.db.get_latest_seq_for_user(username).await?;
         Ok(seq)
     }
+
+    /// Compact the sync change log, pruning rows older than `retention`.
+    /// Retentions reaching past the start of time leave the log as it is.
+    pub async fn compact_change_log(&self, retention: chrono::Duration) -> Result<()> {
+        let Some(horizon) = chrono::Utc::now().checked_sub_signed(retention) else {
+            return Ok(());
+        };
+        let stats = self.db.compact_change_log(horizon).await?;
+        debug!(
+            "compacted change log: {} superseded, {} pruned",
+            stats.superseded, stats.pruned
+        );
+        Ok(())
+    }
+}
+
+/// Build the callback URL a hub uses for a podcast's subscription. The
+/// podcast id is percent-encoded since feed ids may contain slashes.
+fn websub_callback_url(base_url: &str, podcast_id: &str) -> Option<String> {
+    let mut url = reqwest::Url::parse(base_url).ok()?;
+    url.path_segments_mut()
+        .ok()?
+        .pop_if_empty()
+        .push("websub")
+        .push(podcast_id);
+    Some(url.to_string())
+}
+
+/// Delay before retrying a feed after `consecutive_failures` failed fetches:
+/// doubles from `FAILURE_BACKOFF_BASE` up to `FAILURE_BACKOFF_MAX`.
+fn failure_backoff(consecutive_failures: i32) -> chrono::Duration {
+    let exponent = consecutive_failures.saturating_sub(1).clamp(0, 16) as u32;
+    let backoff = FAILURE_BACKOFF_BASE * 2i32.pow(exponent);
+    backoff.min(FAILURE_BACKOFF_MAX)
+}
+
+/// Reject playlists without a name or whose filter can never match.
+fn validate_playlist(name: &str, filter: &PlaylistFilter) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(AppError::BadRequest("playlist name must not be empty".into()));
+    }
+    let durations = [filter.min_duration_secs, filter.max_duration_secs];
+    if durations.iter().flatten().any(|secs| *secs < 0) {
+        return Err(AppError::BadRequest("durations must not be negative".into()));
+    }
+    if let [Some(min), Some(max)] = durations {
+        if min > max {
+            return Err(AppError::BadRequest(
+                "min_duration_secs must not exceed max_duration_secs".into(),
+            ));
+        }
+    }
+    if filter
+        .published_within_days
+        .is_some_and(|days| !(1..=MAX_PUBLISHED_WITHIN_DAYS).contains(&days))
+    {
+        return Err(AppError::BadRequest(format!(
+            "published_within_days must be between 1 and {MAX_PUBLISHED_WITHIN_DAYS}"
+        )));
+    }
+    Ok(())
+}
+
+/// Reject settings players can't honour.
+fn validate_subscription_settings(settings: &SubscriptionSettings) -> Result<()> {
+    if !(MIN_PLAYBACK_SPEED..=MAX_PLAYBACK_SPEED).contains(&settings.playback_speed) {
+        return Err(AppError::BadRequest(format!(
+            "playback_speed must be between {MIN_PLAYBACK_SPEED} and {MAX_PLAYBACK_SPEED}"
+        )));
+    }
+    if settings.skip_intro_secs < 0 || settings.skip_outro_secs < 0 {
+        return Err(AppError::BadRequest("skip seconds must not be negative".into()));
+    }
+    if settings.auto_download < 0 {
+        return Err(AppError::BadRequest("auto_download must not be negative".into()));
+    }
+    Ok(())
 }
 
 // ==============================================================================
@@ -369,3 +1441,124 @@ pub fn decode_sync_cursor(cursor: &str) -> std::result::Result<i64, AppError> {
     s.parse::<i64>()
         .map_err(|_| AppError::BadRequest("invalid sync cursor".into()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_backoff_doubles_up_to_cap() {
+        assert_eq!(failure_backoff(1), chrono::Duration::minutes(10));
+        assert_eq!(failure_backoff(2), chrono::Duration::minutes(20));
+        assert_eq!(failure_backoff(4), chrono::Duration::minutes(80));
+        assert_eq!(failure_backoff(9), FAILURE_BACKOFF_MAX);
+        assert_eq!(failure_backoff(i32::MAX), FAILURE_BACKOFF_MAX);
+    }
+
+    #[test]
+    fn validate_playlist_rejects_impossible_filters() {
+        let filter = |min, max, days| PlaylistFilter {
+            min_duration_secs: min,
+            max_duration_secs: max,
+            published_within_days: days,
+            ..Default::default()
+        };
+
+        assert!(validate_playlist("Short ones", &filter(None, Some(1800), Some(7))).is_ok());
+        assert!(validate_playlist("  ", &filter(None, None, None)).is_err());
+        assert!(validate_playlist("Range", &filter(Some(600), Some(300), None)).is_err());
+        assert!(validate_playlist("Negative", &filter(Some(-1), None, None)).is_err());
+        assert!(validate_playlist("Today", &filter(None, None, Some(0))).is_err());
+        assert!(validate_playlist("Ever", &filter(None, None, Some(36_500))).is_ok());
+        assert!(validate_playlist("Forever", &filter(None, None, Some(i32::MAX))).is_err());
+    }
+
+    #[test]
+    fn subscription_settings_patch_is_validated() {
+        let patched = |patch: SubscriptionSettingsPatch| {
+            let mut settings = SubscriptionSettings::default();
+            settings.apply(patch);
+            validate_subscription_settings(&settings).map(|_| settings)
+        };
+
+        let settings = patched(SubscriptionSettingsPatch {
+            playback_speed: Some(1.5),
+            skip_intro_secs: Some(30),
+            title_override: Some("Show".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(settings.playback_speed, 1.5);
+        assert_eq!(settings.skip_intro_secs, 30);
+        assert_eq!(settings.skip_outro_secs, 0);
+        assert_eq!(settings.title_override.as_deref(), Some("Show"));
+
+        let cleared = patched(SubscriptionSettingsPatch {
+            title_override: Some(" ".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(cleared.title_override, None);
+
+        let speed = |speed| SubscriptionSettingsPatch {
+            playback_speed: Some(speed),
+            ..Default::default()
+        };
+        assert!(patched(speed(0.0)).is_err());
+        assert!(patched(speed(f32::NAN)).is_err());
+        assert!(patched(speed(4.5)).is_err());
+        assert!(patched(SubscriptionSettingsPatch {
+            skip_outro_secs: Some(-5),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn refresh_policy_honours_hints_within_bounds() {
+        let policy = RefreshPolicy {
+            default_interval: chrono::Duration::minutes(10),
+            min_interval: chrono::Duration::minutes(5),
+            max_interval: chrono::Duration::days(1),
+        };
+        let hints = |feed_interval, max_age| RefreshHints {
+            feed_interval,
+            max_age,
+        };
+
+        assert_eq!(
+            policy.next_interval(&hints(None, None)),
+            chrono::Duration::minutes(10)
+        );
+        // A daily show is polled daily; a weekly one is capped at the max.
+        assert_eq!(
+            policy.next_interval(&hints(Some(chrono::Duration::days(1)), None)),
+            chrono::Duration::days(1)
+        );
+        assert_eq!(
+            policy.next_interval(&hints(Some(chrono::Duration::weeks(1)), None)),
+            chrono::Duration::days(1)
+        );
+        // max-age only lengthens the interval, and the minimum still applies.
+        assert_eq!(
+            policy.next_interval(&hints(None, Some(chrono::Duration::hours(2)))),
+            chrono::Duration::hours(2)
+        );
+        assert_eq!(
+            policy.next_interval(&hints(
+                Some(chrono::Duration::minutes(1)),
+                Some(chrono::Duration::zero())
+            )),
+            chrono::Duration::minutes(5)
+        );
+        // Retry-After beats the backoff but not the maximum interval.
+        assert_eq!(
+            policy.retry_interval(1, Some(chrono::Duration::hours(3))),
+            chrono::Duration::hours(3)
+        );
+        assert_eq!(
+            policy.retry_interval(1, Some(chrono::Duration::weeks(2))),
+            chrono::Duration::days(1)
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use pod_model::Chapter;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Upper bound on the ID3 tag we're willing to read from the start of an
+/// audio file. Tags with embedded chapter artwork can run to a few MB.
+const MAX_ID3_TAG_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ChaptersError {
+    #[error("reqwest error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("chapters server returned {0}")]
+    Status(reqwest::StatusCode),
+    #[error("invalid chapters JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid ID3 tag: {0}")]
+    Id3(#[from] id3::Error),
+    #[error("ID3 tag of {0} bytes is too large")]
+    TooLarge(usize),
+}
+
+/// A `podcast:chapters` JSON document (`application/json+chapters`).
+#[derive(Deserialize)]
+struct ChaptersDocument {
+    chapters: Vec<JsonChapter>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonChapter {
+    start_time: f64,
+    end_time: Option<f64>,
+    #[serde(default)]
+    title: String,
+    img: Option<String>,
+    url: Option<String>,
+    /// `false` marks chapters that only carry artwork or links and shouldn't
+    /// appear in a table of contents.
+    toc: Option<bool>,
+}
+
+/// Fetch and parse a `podcast:chapters` JSON document.
+pub async fn fetch_json(http: &reqwest::Client, url: &str) -> Result<Vec<Chapter>, ChaptersError> {
+    let res = http.get(url).send().await?;
+    if !res.status().is_success() {
+        return Err(ChaptersError::Status(res.status()));
+    }
+    parse_json(&res.bytes().await?)
+}
+
+/// Read `CHAP` frames from the ID3 tag at the start of an MP3. Only the tag
+/// is downloaded; the response is dropped once it has been read.
+pub async fn fetch_id3(
+    http: &reqwest::Client,
+    audio_url: &str,
+) -> Result<Vec<Chapter>, ChaptersError> {
+    let mut res = http.get(audio_url).send().await?;
+    if !res.status().is_success() {
+        return Err(ChaptersError::Status(res.status()));
+    }
+
+    let mut buf = Vec::new();
+    let mut tag_len = None;
+    while let Some(chunk) = res.chunk().await? {
+        buf.extend_from_slice(&chunk);
+        if tag_len.is_none() && buf.len() >= 10 {
+            match id3_tag_len(&buf[..10]) {
+                Some(len) if len > MAX_ID3_TAG_BYTES => return Err(ChaptersError::TooLarge(len)),
+                Some(len) => tag_len = Some(len),
+                None => return Ok(Vec::new()),
+            }
+        }
+        if tag_len.is_some_and(|len| buf.len() >= len) {
+            break;
+        }
+    }
+
+    match tag_len {
+        Some(len) if buf.len() >= len => parse_id3(&buf[..len]),
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn parse_json(body: &[u8]) -> Result<Vec<Chapter>, ChaptersError> {
+    let doc: ChaptersDocument = serde_json::from_slice(body)?;
+    let mut chapters: Vec<Chapter> = doc
+        .chapters
+        .into_iter()
+        .filter(|c| c.toc != Some(false) && c.start_time >= 0.0)
+        .map(|c| Chapter {
+            start_time: c.start_time,
+            end_time: c.end_time,
+            title: c.title,
+            img: c.img,
+            url: c.url,
+        })
+        .collect();
+    chapters.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    Ok(chapters)
+}
+
+/// Total length of an ID3v2 tag (header, body and optional footer) given
+/// its 10-byte header, or `None` if the file doesn't start with one.
+fn id3_tag_len(header: &[u8]) -> Option<usize> {
+    if header.len() < 10 || &header[..3] != b"ID3" {
+        return None;
+    }
+    // The body size is a 28-bit "syncsafe" integer: 7 bits per byte.
+    let size = header[6..10]
+        .iter()
+        .fold(0usize, |acc, b| (acc << 7) | (*b & 0x7f) as usize);
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + size + footer)
+}
+
+fn parse_id3(tag: &[u8]) -> Result<Vec<Chapter>, ChaptersError> {
+    let tag = id3::Tag::read_from2(std::io::Cursor::new(tag))?;
+    let mut chapters: Vec<Chapter> = tag
+        .chapters()
+        .map(|c| {
+            let title = c
+                .frames
+                .iter()
+                .find(|f| f.id() == "TIT2")
+                .and_then(|f| f.content().text())
+                .unwrap_or(&c.element_id)
+                .to_string();
+            let url = c
+                .frames
+                .iter()
+                .find_map(|f| f.content().extended_link())
+                .map(|l| l.link.clone());
+            Chapter {
+                start_time: f64::from(c.start_time) / 1000.0,
+                end_time: Some(f64::from(c.end_time) / 1000.0),
+                title,
+                img: None,
+                url,
+            }
+        })
+        .collect();
+    chapters.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    Ok(chapters)
+}
+
+#[cfg(test)]
+mod tests {
+    use id3::{frame, Frame, TagLike, Version};
+
+    use super::*;
+
+    #[test]
+    fn json_chapters_sorted_without_hidden() {
+        let doc = br#"{
+  "version": "1.2.0",
+  "chapters": [
+    {"startTime": 120.5, "title": "Main topic", "url": "https://example.com"},
+    {"startTime": 0, "title": "Intro", "img": "https://example.com/intro.jpg"},
+    {"startTime": 60, "title": "Sponsor art", "toc": false}
+  ]
+}"#;
+        let chapters = parse_json(doc).unwrap();
+        assert_eq!(
+            chapters.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(),
+            vec!["Intro", "Main topic"]
+        );
+        assert_eq!(chapters[1].start_time, 120.5);
+        assert_eq!(chapters[1].url.as_deref(), Some("https://example.com"));
+    }
+
+    /// An ID3 tag with two chapters, written out of order.
+    fn tagged_bytes() -> Vec<u8> {
+        let mut tag = id3::Tag::new();
+        for (id, start, end, title) in [("ch1", 90_000, 180_000, "Second"), ("ch0", 0, 90_000, "First")] {
+            tag.add_frame(frame::Chapter {
+                element_id: id.to_string(),
+                start_time: start,
+                end_time: end,
+                start_offset: 0xffff_ffff,
+                end_offset: 0xffff_ffff,
+                frames: vec![Frame::text("TIT2", title)],
+            });
+        }
+        let mut bytes = Vec::new();
+        tag.write_to(&mut bytes, Version::Id3v24).unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn fetch_id3_from_audio_url() {
+        let mut audio = tagged_bytes();
+        audio.resize(audio.len() + 1024 * 1024, 0);
+        let app = axum::Router::new().route("/ep.mp3", axum::routing::get(move || async move { audio }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let chapters = fetch_id3(&reqwest::Client::new(), &format!("http://{}/ep.mp3", addr))
+            .await
+            .unwrap();
+        assert_eq!(
+            chapters.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(),
+            vec!["First", "Second"]
+        );
+    }
+
+    #[test]
+    fn id3_chap_frames() {
+        let mut bytes = tagged_bytes();
+        // Audio data follows the tag in a real file.
+        let len = id3_tag_len(&bytes).unwrap();
+        assert_eq!(len, bytes.len());
+        bytes.extend_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+
+        let chapters = parse_id3(&bytes[..len]).unwrap();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title, "First");
+        assert_eq!(chapters[1].start_time, 90.0);
+        assert_eq!(chapters[1].end_time, Some(180.0));
+
+        assert_eq!(id3_tag_len(&[0xff, 0xfb, 0x90, 0x00, 0, 0, 0, 0, 0, 0]), None);
+    }
+}
@@ -3,20 +3,51 @@ use std::path::Path;
 use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 
+use crate::directory::ITUNES_BASE_URL;
+
+/// Longest feed refresh interval the config accepts, a year. Larger values
+/// are clamped so scheduling can't overflow.
+const MAX_REFRESH_INTERVAL_SECS: u64 = 365 * 24 * 60 * 60;
+/// Longest change-log retention the config accepts, about a century.
+const MAX_CHANGE_LOG_RETENTION_DAYS: u64 = 36_500;
+
 #[derive(Debug, Default, Deserialize)]
 struct ConfigFile {
     database_url: Option<String>,
+    base_url: Option<String>,
     port: Option<u16>,
     refresh_interval_secs: Option<u64>,
+    refresh_min_interval_secs: Option<u64>,
+    refresh_max_interval_secs: Option<u64>,
     allow_registration: Option<bool>,
+    change_log_retention_days: Option<u64>,
+    compaction_interval_secs: Option<u64>,
+    catalog_base_url: Option<String>,
 }
 
 #[derive(Debug)]
 pub struct Config {
     pub database_url: String,
+    /// Public URL of this server. Required for WebSub, since hubs call back
+    /// to `{base_url}/websub/{podcast_id}`; without it only polling is used.
+    pub base_url: Option<String>,
     pub port: u16,
+    /// Refresh interval for feeds that don't declare one via `<ttl>` or
+    /// `sy:updatePeriod`.
     pub refresh_interval_secs: u64,
+    /// Bounds applied to every per-feed refresh interval. All three are
+    /// capped at a year.
+    pub refresh_min_interval_secs: u64,
+    pub refresh_max_interval_secs: u64,
     pub allow_registration: bool,
+    /// How long sync change-log rows are kept. Clients whose cursor is older
+    /// than this get 410 Gone and must re-bootstrap. Capped at 36,500 days.
+    pub change_log_retention_days: u64,
+    /// Seconds between compaction runs, at least one.
+    pub compaction_interval_secs: u64,
+    /// Base URL of the iTunes Search API–compatible directory used for
+    /// podcast discovery.
+    pub catalog_base_url: String,
 }
 
 /// Resolve a config value: env var takes priority, then file value.
@@ -56,14 +87,43 @@ impl Config {
 
         Ok(Config {
             database_url: database_url.unwrap(),
+            base_url: resolve("BASE_URL", file.base_url)
+                .map(|url| url.trim_end_matches('/').to_string()),
             port: resolve_parsed("PORT", file.port).unwrap_or(3000),
             refresh_interval_secs: resolve_parsed(
                 "REFRESH_INTERVAL_SECS",
                 file.refresh_interval_secs,
             )
-            .unwrap_or(600),
+            .unwrap_or(600)
+            .min(MAX_REFRESH_INTERVAL_SECS),
+            refresh_min_interval_secs: resolve_parsed(
+                "REFRESH_MIN_INTERVAL_SECS",
+                file.refresh_min_interval_secs,
+            )
+            .unwrap_or(300)
+            .min(MAX_REFRESH_INTERVAL_SECS),
+            refresh_max_interval_secs: resolve_parsed(
+                "REFRESH_MAX_INTERVAL_SECS",
+                file.refresh_max_interval_secs,
+            )
+            .unwrap_or(86400)
+            .min(MAX_REFRESH_INTERVAL_SECS),
             allow_registration: resolve_parsed("ALLOW_REGISTRATION", file.allow_registration)
                 .unwrap_or(true),
+            change_log_retention_days: resolve_parsed(
+                "CHANGE_LOG_RETENTION_DAYS",
+                file.change_log_retention_days,
+            )
+            .unwrap_or(90)
+            .min(MAX_CHANGE_LOG_RETENTION_DAYS),
+            compaction_interval_secs: resolve_parsed(
+                "COMPACTION_INTERVAL_SECS",
+                file.compaction_interval_secs,
+            )
+            .unwrap_or(86400)
+            .max(1),
+            catalog_base_url: resolve("CATALOG_BASE_URL", file.catalog_base_url)
+                .unwrap_or_else(|| ITUNES_BASE_URL.to_string()),
         })
     }
 }
@@ -1,9 +1,33 @@
-use pod_model::{Episode, EpisodeWithProgress, Podcast, PodcastWithEpisodeStats, ProgressChange};
+use std::collections::HashMap;
 
-use crate::model::{EpisodeChangeRow, Session, User, UserEpisode, UserSubscription};
+use pod_model::{
+    Chapter, Episode, EpisodeWithProgress, Funding, Person, Playlist, PlaylistFilter, Podcast,
+    PodcastWithEpisodeStats, ProgressChange, SubscriptionSettings, Transcript, TranscriptSegment,
+};
+
+use crate::model::{
+    ChapterSource, PlaylistRow, Session, SubscriptionSettingsRow, SyncChangeRow, TranscriptSource,
+    User, UserEpisode, UserSubscription, WebSubSubscription,
+};
 
 type Result<T> = std::result::Result<T, sqlx::Error>;
 
+/// Number of change-log rows removed by a compaction run.
+#[derive(Debug)]
+pub struct CompactionStats {
+    /// Rows dropped because a newer row exists for the same episode,
+    /// subscription or queue.
+    pub superseded: u64,
+    /// Rows dropped for being older than the retention horizon.
+    pub pruned: u64,
+}
+
+/// Compound `(publication_date, id)` cursor for keyset-paginated episode lists.
+pub type EpisodeCursor = (chrono::DateTime<chrono::Utc>, String);
+
+/// Position in search results: the rank and id of the last result seen.
+pub type SearchCursor = (f32, String);
+
 pub struct Db {
     pool: sqlx::PgPool,
 }
@@ -34,6 +58,70 @@ impl Db {
         Ok(podcasts)
     }
 
+    /// List podcasts whose next scheduled fetch is at or before `now`.
+    pub async fn list_podcasts_due(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Podcast>> {
+        let podcasts = sqlx::query_as!(
+            Podcast,
+            r#"SELECT * FROM podcast WHERE next_fetch_at IS NULL OR next_fetch_at <= $1"#,
+            now,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(podcasts)
+    }
+
+    /// Record a successful feed fetch, clearing any failure state.
+    pub async fn record_fetch_success(
+        &self,
+        id: &str,
+        next_fetch_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE podcast
+            SET last_fetch_at = now(),
+                last_success_at = now(),
+                consecutive_failures = 0,
+                last_error = NULL,
+                next_fetch_at = $2
+            WHERE id = $1
+            "#,
+            id,
+            next_fetch_at,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record a failed feed fetch and when to try again.
+    pub async fn record_fetch_failure(
+        &self,
+        id: &str,
+        error: &str,
+        next_fetch_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE podcast
+            SET last_fetch_at = now(),
+                consecutive_failures = consecutive_failures + 1,
+                last_error = $2,
+                next_fetch_at = $3
+            WHERE id = $1
+            "#,
+            id,
+            error,
+            next_fetch_at,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
     pub async fn insert_podcast(&self, podcast: &Podcast) -> Result<Podcast> {
         // Use a no-op DO UPDATE so RETURNING always yields the row,
         // whether it was freshly inserted or already existed.
@@ -80,8 +168,9 @@ impl Db {
         let episode = sqlx::query_as!(
             Episode,
             r#"
-            INSERT INTO episode (id, podcast_id, title, summary, summary_type, content_encoded, content_encoded_type, publication_date, audio_url, audio_type, audio_duration, thumbnail_url, created_at, last_updated)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            INSERT INTO episode (id, podcast_id, title, summary, summary_type, content_encoded, content_encoded_type, publication_date, audio_url, audio_type, audio_duration, thumbnail_url, created_at, last_updated,
+                                 season, episode_number, episode_type, chapters_url, chapters_type)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
             ON CONFLICT (id) DO UPDATE SET id = EXCLUDED.id
             RETURNING *
             "#,
@@ -99,6 +188,64 @@ impl Db {
             episode.thumbnail_url,
             episode.created_at,
             episode.last_updated,
+            episode.season,
+            episode.episode_number,
+            episode.episode_type,
+            episode.chapters_url,
+            episode.chapters_type,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO episode_change (podcast_id, episode_id, op)
+            VALUES ($1, $2, 'upsert')
+            "#,
+            episode.podcast_id,
+            episode.id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(episode)
+    }
+
+    /// Overwrite an episode's feed-derived metadata and record an upsert
+    /// entry in the change log in the same transaction. Callers should only
+    /// invoke this when something actually changed, since every call reaches
+    /// all synced clients.
+    pub async fn update_episode(&self, episode: &Episode) -> Result<Episode> {
+        let mut tx = self.pool.begin().await?;
+
+        let episode = sqlx::query_as!(
+            Episode,
+            r#"
+            UPDATE episode
+            SET title = $2, summary = $3, summary_type = $4, content_encoded = $5, content_encoded_type = $6,
+                publication_date = $7, audio_url = $8, audio_type = $9, audio_duration = $10, thumbnail_url = $11,
+                season = $12, episode_number = $13, episode_type = $14, chapters_url = $15, chapters_type = $16,
+                last_updated = now()
+            WHERE id = $1
+            RETURNING *
+            "#,
+            episode.id,
+            episode.title,
+            episode.summary,
+            episode.summary_type,
+            episode.content_encoded,
+            episode.content_encoded_type,
+            episode.publication_date,
+            episode.audio_url,
+            episode.audio_type,
+            episode.audio_duration,
+            episode.thumbnail_url,
+            episode.season,
+            episode.episode_number,
+            episode.episode_type,
+            episode.chapters_url,
+            episode.chapters_type,
         )
         .fetch_one(&mut *tx)
         .await?;
@@ -118,6 +265,363 @@ impl Db {
         Ok(episode)
     }
 
+    /// Fetch an episode from a podcast the user is subscribed to.
+    pub async fn get_episode_for_user(
+        &self,
+        username: &str,
+        episode_id: &str,
+    ) -> Result<Option<Episode>> {
+        let episode = sqlx::query_as!(
+            Episode,
+            r#"
+            SELECT e.* FROM episode e
+            JOIN user_subscription us ON us.podcast_id = e.podcast_id
+            JOIN users u ON us.user_id = u.id
+            WHERE e.id = $1 AND u.username = $2 AND e.deleted_at IS NULL
+            "#,
+            episode_id,
+            username
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(episode)
+    }
+
+    pub async fn get_episode_transcripts(&self, episode_id: &str) -> Result<Vec<Transcript>> {
+        let transcripts = sqlx::query_as!(
+            Transcript,
+            r#"
+            SELECT url, mime_type, language, rel FROM episode_transcript
+            WHERE episode_id = $1
+            ORDER BY position
+            "#,
+            episode_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(transcripts)
+    }
+
+    pub async fn get_episode_persons(&self, episode_id: &str) -> Result<Vec<Person>> {
+        let persons = sqlx::query_as!(
+            Person,
+            r#"
+            SELECT name, role, person_group AS "group", img, href FROM episode_person
+            WHERE episode_id = $1
+            ORDER BY position
+            "#,
+            episode_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(persons)
+    }
+
+    /// Transcripts of every episode of a podcast, keyed by episode id.
+    pub async fn get_transcripts_for_podcast(
+        &self,
+        podcast_id: &str,
+    ) -> Result<HashMap<String, Vec<Transcript>>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT t.episode_id, t.url, t.mime_type, t.language, t.rel
+            FROM episode_transcript t
+            JOIN episode e ON e.id = t.episode_id
+            WHERE e.podcast_id = $1
+            ORDER BY t.episode_id, t.position
+            "#,
+            podcast_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut transcripts: HashMap<String, Vec<Transcript>> = HashMap::new();
+        for row in rows {
+            transcripts.entry(row.episode_id).or_default().push(Transcript {
+                url: row.url,
+                mime_type: row.mime_type,
+                language: row.language,
+                rel: row.rel,
+            });
+        }
+        Ok(transcripts)
+    }
+
+    /// Persons credited on every episode of a podcast, keyed by episode id.
+    pub async fn get_persons_for_podcast(
+        &self,
+        podcast_id: &str,
+    ) -> Result<HashMap<String, Vec<Person>>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT p.episode_id, p.name, p.role, p.person_group, p.img, p.href
+            FROM episode_person p
+            JOIN episode e ON e.id = p.episode_id
+            WHERE e.podcast_id = $1
+            ORDER BY p.episode_id, p.position
+            "#,
+            podcast_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut persons: HashMap<String, Vec<Person>> = HashMap::new();
+        for row in rows {
+            persons.entry(row.episode_id).or_default().push(Person {
+                name: row.name,
+                role: row.role,
+                group: row.person_group,
+                img: row.img,
+                href: row.href,
+            });
+        }
+        Ok(persons)
+    }
+
+    /// Replace an episode's transcripts and persons with the given lists.
+    pub async fn replace_episode_extras(
+        &self,
+        episode_id: &str,
+        transcripts: &[Transcript],
+        persons: &[Person],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM episode_transcript WHERE episode_id = $1"#,
+            episode_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        for (position, t) in transcripts.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO episode_transcript (episode_id, position, url, mime_type, language, rel)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+                episode_id,
+                position as i32,
+                t.url,
+                t.mime_type,
+                t.language,
+                t.rel,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            r#"DELETE FROM episode_person WHERE episode_id = $1"#,
+            episode_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        for (position, p) in persons.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO episode_person (episode_id, position, name, role, person_group, img, href)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                episode_id,
+                position as i32,
+                p.name,
+                p.role,
+                p.group,
+                p.img,
+                p.href,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_chapter_source(&self, episode_id: &str) -> Result<Option<ChapterSource>> {
+        let source = sqlx::query_as!(
+            ChapterSource,
+            r#"SELECT * FROM episode_chapter_source WHERE episode_id = $1"#,
+            episode_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(source)
+    }
+
+    pub async fn get_episode_chapters(&self, episode_id: &str) -> Result<Vec<Chapter>> {
+        let chapters = sqlx::query_as!(
+            Chapter,
+            r#"
+            SELECT start_time, end_time, title, img, url FROM episode_chapter
+            WHERE episode_id = $1
+            ORDER BY position
+            "#,
+            episode_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(chapters)
+    }
+
+    /// Replace an episode's cached chapters with the result of a fetch from
+    /// `source_url`. A failed fetch stores its error and no chapters.
+    pub async fn store_episode_chapters(
+        &self,
+        episode_id: &str,
+        source_url: &str,
+        chapters: &[Chapter],
+        error: Option<&str>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM episode_chapter WHERE episode_id = $1"#,
+            episode_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        for (position, c) in chapters.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO episode_chapter (episode_id, position, start_time, end_time, title, img, url)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                episode_id,
+                position as i32,
+                c.start_time,
+                c.end_time,
+                c.title,
+                c.img,
+                c.url,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO episode_chapter_source (episode_id, source_url, fetched_at, error)
+            VALUES ($1, $2, now(), $3)
+            ON CONFLICT (episode_id) DO UPDATE
+            SET source_url = EXCLUDED.source_url, fetched_at = EXCLUDED.fetched_at, error = EXCLUDED.error
+            "#,
+            episode_id,
+            source_url,
+            error,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_transcript_source(&self, episode_id: &str) -> Result<Option<TranscriptSource>> {
+        let source = sqlx::query_as!(
+            TranscriptSource,
+            r#"SELECT * FROM episode_transcript_source WHERE episode_id = $1"#,
+            episode_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(source)
+    }
+
+    pub async fn get_transcript_segments(&self, episode_id: &str) -> Result<Vec<TranscriptSegment>> {
+        let segments = sqlx::query_as!(
+            TranscriptSegment,
+            r#"
+            SELECT start_time, end_time, speaker, body FROM episode_transcript_segment
+            WHERE episode_id = $1
+            ORDER BY position
+            "#,
+            episode_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(segments)
+    }
+
+    /// Transcript segments of an episode matching a web-search style query
+    /// (`"exact phrase" -excluded or`), in transcript order.
+    pub async fn search_transcript_segments(
+        &self,
+        episode_id: &str,
+        query: &str,
+    ) -> Result<Vec<TranscriptSegment>> {
+        let segments = sqlx::query_as!(
+            TranscriptSegment,
+            r#"
+            SELECT start_time, end_time, speaker, body FROM episode_transcript_segment
+            WHERE episode_id = $1 AND body_tsv @@ websearch_to_tsquery('simple', $2)
+            ORDER BY position
+            "#,
+            episode_id,
+            query
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(segments)
+    }
+
+    /// Replace an episode's cached transcript with the segments fetched from
+    /// `source_url`. A failed fetch stores its error and no segments.
+    pub async fn store_transcript_segments(
+        &self,
+        episode_id: &str,
+        source_url: &str,
+        segments: &[TranscriptSegment],
+        error: Option<&str>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM episode_transcript_segment WHERE episode_id = $1"#,
+            episode_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        // Transcripts run to thousands of segments, so insert them in one
+        // statement rather than one round trip each.
+        let positions: Vec<i32> = (0..segments.len() as i32).collect();
+        let start_times: Vec<f64> = segments.iter().map(|s| s.start_time).collect();
+        let end_times: Vec<Option<f64>> = segments.iter().map(|s| s.end_time).collect();
+        let speakers: Vec<Option<String>> = segments.iter().map(|s| s.speaker.clone()).collect();
+        let bodies: Vec<String> = segments.iter().map(|s| s.body.clone()).collect();
+        sqlx::query!(
+            r#"
+            INSERT INTO episode_transcript_segment (episode_id, position, start_time, end_time, speaker, body)
+            SELECT $1, * FROM UNNEST($2::INTEGER[], $3::DOUBLE PRECISION[], $4::DOUBLE PRECISION[], $5::TEXT[], $6::TEXT[])
+            "#,
+            episode_id,
+            &positions,
+            &start_times,
+            &end_times as &[Option<f64>],
+            &speakers as &[Option<String>],
+            &bodies,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO episode_transcript_source (episode_id, source_url, fetched_at, error)
+            VALUES ($1, $2, now(), $3)
+            ON CONFLICT (episode_id) DO UPDATE
+            SET source_url = EXCLUDED.source_url, fetched_at = EXCLUDED.fetched_at, error = EXCLUDED.error
+            "#,
+            episode_id,
+            source_url,
+            error,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
     pub async fn find_user_by_username(&self, username: &str) -> Result<Option<User>> {
         let user = sqlx::query_as!(
             User,
@@ -208,7 +712,7 @@ impl Db {
         let existing = sqlx::query_as!(
             UserSubscription,
             r#"
-            SELECT * FROM user_subscription WHERE user_id = (SELECT id FROM users WHERE username = $1) AND podcast_id = $2
+            SELECT id, user_id, podcast_id, created_at, last_updated FROM user_subscription WHERE user_id = (SELECT id FROM users WHERE username = $1) AND podcast_id = $2
             "#,
             username,
             podcast_id
@@ -217,6 +721,8 @@ impl Db {
         if let Some(existing) = existing {
             Ok(existing)
         } else {
+            let mut tx = self.pool.begin().await?;
+
             let subscription = sqlx::query_as!(
                 UserSubscription,
                 r#"
@@ -227,24 +733,92 @@ impl Db {
                 username,
                 podcast_id
             )
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            sqlx::query!(
+                r#"
+                INSERT INTO subscription_change (user_id, podcast_id, op)
+                VALUES ($1, $2, 'upsert')
+                "#,
+                subscription.user_id,
+                subscription.podcast_id,
+            )
+            .execute(&mut *tx)
             .await?;
+
+            tx.commit().await?;
             Ok(subscription)
         }
     }
 
-    pub async fn get_subscribed_podcasts_for_user(
-        &self,
-        username: &str,
-    ) -> Result<Vec<PodcastWithEpisodeStats>> {
-        let podcasts = sqlx::query_as!(
-            PodcastWithEpisodeStats,
-            r#"
-            SELECT p.*, (SELECT MAX(e.publication_date) FROM episode e WHERE e.podcast_id = p.id) as last_publication_date FROM podcast p
-            JOIN user_subscription us ON p.id = us.podcast_id
+    /// Remove a user's subscription and record a delete entry in the
+    /// subscription change log so clients drop the podcast on their next
+    /// sync. Returns false if the user wasn't subscribed.
+    pub async fn remove_subscription(&self, username: &str, podcast_id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let removed = sqlx::query_as!(
+            UserSubscription,
+            r#"
+            DELETE FROM user_subscription
+            WHERE user_id = (SELECT id FROM users WHERE username = $1) AND podcast_id = $2
+            RETURNING id, user_id, podcast_id, created_at, last_updated
+            "#,
+            username,
+            podcast_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let Some(subscription) = removed.first() else {
+            return Ok(false);
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO subscription_change (user_id, podcast_id, op)
+            VALUES ($1, $2, 'delete')
+            "#,
+            subscription.user_id,
+            subscription.podcast_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Queued episodes of the podcast go with the subscription.
+        let dequeued = sqlx::query!(
+            r#"
+            DELETE FROM queue_item q
+            USING episode e
+            WHERE q.user_id = $1 AND q.episode_id = e.id AND e.podcast_id = $2
+            "#,
+            subscription.user_id,
+            subscription.podcast_id,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if dequeued > 0 {
+            record_queue_change(&mut tx, subscription.user_id).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn get_subscribed_podcasts_for_user(
+        &self,
+        username: &str,
+    ) -> Result<Vec<PodcastWithEpisodeStats>> {
+        let podcasts = sqlx::query_as!(
+            PodcastWithEpisodeStats,
+            r#"
+            SELECT p.*, (SELECT MAX(e.publication_date) FROM episode e WHERE e.podcast_id = p.id AND e.deleted_at IS NULL) as last_publication_date FROM podcast p
+            JOIN user_subscription us ON p.id = us.podcast_id
             JOIN users u ON us.user_id = u.id
             WHERE u.username = $1
-            ORDER BY (SELECT MAX(e.publication_date) FROM episode e WHERE e.podcast_id = p.id) DESC
+            ORDER BY (SELECT MAX(e.publication_date) FROM episode e WHERE e.podcast_id = p.id AND e.deleted_at IS NULL) DESC
             "#,
             username
         )
@@ -253,6 +827,109 @@ impl Db {
         Ok(podcasts)
     }
 
+    /// The user's settings for a subscription, or `None` if they aren't
+    /// subscribed to the podcast.
+    pub async fn get_subscription_settings(
+        &self,
+        username: &str,
+        podcast_id: &str,
+    ) -> Result<Option<SubscriptionSettings>> {
+        let row = sqlx::query_as!(
+            SubscriptionSettingsRow,
+            r#"
+            SELECT us.podcast_id, us.playback_speed, us.skip_intro_secs, us.skip_outro_secs,
+                   us.auto_download, us.hide_from_inbox, us.notifications, us.title_override,
+                   us.state
+            FROM user_subscription us
+            JOIN users u ON u.id = us.user_id
+            WHERE u.username = $1 AND us.podcast_id = $2
+            "#,
+            username,
+            podcast_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(SubscriptionSettings::from))
+    }
+
+    /// Settings of all the user's subscriptions, keyed by podcast id.
+    pub async fn get_subscription_settings_for_user(
+        &self,
+        username: &str,
+    ) -> Result<HashMap<String, SubscriptionSettings>> {
+        let rows = sqlx::query_as!(
+            SubscriptionSettingsRow,
+            r#"
+            SELECT us.podcast_id, us.playback_speed, us.skip_intro_secs, us.skip_outro_secs,
+                   us.auto_download, us.hide_from_inbox, us.notifications, us.title_override,
+                   us.state
+            FROM user_subscription us
+            JOIN users u ON u.id = us.user_id
+            WHERE u.username = $1
+            "#,
+            username
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.podcast_id.clone(), SubscriptionSettings::from(row)))
+            .collect())
+    }
+
+    /// Store a subscription's settings and record an upsert in the
+    /// subscription change log so other clients pick them up. Returns false
+    /// if the user isn't subscribed to the podcast.
+    pub async fn update_subscription_settings(
+        &self,
+        username: &str,
+        podcast_id: &str,
+        settings: &SubscriptionSettings,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE user_subscription
+            SET playback_speed = $3, skip_intro_secs = $4, skip_outro_secs = $5,
+                auto_download = $6, hide_from_inbox = $7, notifications = $8,
+                title_override = $9, state = $10, last_updated = now()
+            WHERE user_id = (SELECT id FROM users WHERE username = $1) AND podcast_id = $2
+            RETURNING user_id
+            "#,
+            username,
+            podcast_id,
+            settings.playback_speed,
+            settings.skip_intro_secs,
+            settings.skip_outro_secs,
+            settings.auto_download,
+            settings.hide_from_inbox,
+            settings.notifications,
+            settings.title_override.as_deref(),
+            settings.state.as_str(),
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(updated) = updated else {
+            return Ok(false);
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO subscription_change (user_id, podcast_id, op)
+            VALUES ($1, $2, 'upsert')
+            "#,
+            updated.user_id,
+            podcast_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
     pub async fn update_podcast_cache_headers(
         &self,
         id: &str,
@@ -270,6 +947,77 @@ impl Db {
         Ok(())
     }
 
+    pub async fn update_podcast_feed_interval(
+        &self,
+        id: &str,
+        interval_secs: Option<i32>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE podcast SET feed_update_interval_secs = $2 WHERE id = $1"#,
+            id,
+            interval_secs,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_podcast_guid(&self, id: &str, guid: Option<&str>) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE podcast SET podcast_guid = $2 WHERE id = $1"#,
+            id,
+            guid,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_podcast_funding(&self, podcast_id: &str) -> Result<Vec<Funding>> {
+        let funding = sqlx::query_as!(
+            Funding,
+            r#"
+            SELECT url, message FROM podcast_funding
+            WHERE podcast_id = $1
+            ORDER BY position
+            "#,
+            podcast_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(funding)
+    }
+
+    pub async fn replace_podcast_funding(
+        &self,
+        podcast_id: &str,
+        funding: &[Funding],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            r#"DELETE FROM podcast_funding WHERE podcast_id = $1"#,
+            podcast_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        for (position, f) in funding.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO podcast_funding (podcast_id, position, url, message)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                podcast_id,
+                position as i32,
+                f.url,
+                f.message,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
     pub async fn get_podcast_by_id(&self, id: &str) -> Result<Option<Podcast>> {
         let podcast = sqlx::query_as!(
             Podcast,
@@ -308,7 +1056,7 @@ impl Db {
         let episodes = sqlx::query_as!(
             Episode,
             r#"
-            SELECT * FROM episode WHERE podcast_id = $1
+            SELECT * FROM episode WHERE podcast_id = $1 AND deleted_at IS NULL
             "#,
             id
         )
@@ -321,7 +1069,7 @@ impl Db {
         &self,
         username: &str,
         id: &str,
-        pagination: Option<(i64, Option<(chrono::DateTime<chrono::Utc>, String)>)>,
+        pagination: Option<(i64, Option<EpisodeCursor>)>,
     ) -> Result<Vec<EpisodeWithProgress>> {
         let episodes: Vec<EpisodeWithProgress> = if let Some((limit, cursor)) = pagination {
             let (cursor_date, cursor_id) = match cursor {
@@ -337,6 +1085,7 @@ impl Db {
                     FROM episode e
                     LEFT JOIN user_episode ue ON e.id = ue.episode_id AND ue.user_id = (SELECT id FROM users WHERE username = $1)
                     WHERE e.podcast_id = $2
+                      AND e.deleted_at IS NULL
                       AND (
                         $3::timestamptz IS NULL
                         OR e.publication_date < $3
@@ -361,6 +1110,7 @@ impl Db {
                     FROM episode e
                     LEFT JOIN user_episode ue ON e.id = ue.episode_id AND ue.user_id = (SELECT id FROM users WHERE username = $1)
                     WHERE e.podcast_id = $2
+                      AND e.deleted_at IS NULL
                     ORDER BY e.publication_date DESC, e.id DESC
                 "#,
             )
@@ -372,24 +1122,30 @@ impl Db {
         Ok(episodes)
     }
 
-    /// Fetch episodes across all subscribed podcasts for the inbox view,
-    /// excluding completed episodes, sorted by publication date descending.
+    /// Fetch episodes across all subscribed podcasts matching `filter`,
+    /// sorted by publication date descending. The default filter gives the
+    /// inbox view; saved playlists pass their own.
     pub async fn get_inbox_episodes(
         &self,
         username: &str,
+        filter: &PlaylistFilter,
         limit: i64,
-        cursor: Option<(chrono::DateTime<chrono::Utc>, String)>,
+        cursor: Option<EpisodeCursor>,
     ) -> Result<Vec<pod_model::InboxEpisode>> {
         let (cursor_date, cursor_id) = match cursor {
             Some((date, id)) => (Some(date), Some(id)),
             None => (None, None),
         };
+        let published_after = filter
+            .published_within_days
+            .and_then(|days| chrono::TimeDelta::try_days(days.into()))
+            .and_then(|window| chrono::Utc::now().checked_sub_signed(window));
         let episodes = sqlx::query_as(
             r#"
                 SELECT e.*
                      , ue.progress
                      , COALESCE(ue.done, false) AS done
-                     , p.title  AS podcast_title
+                     , COALESCE(us.title_override, p.title) AS podcast_title
                      , p.image_link AS podcast_image_link
                 FROM episode e
                 JOIN user_subscription us
@@ -399,7 +1155,23 @@ impl Db {
                 LEFT JOIN user_episode ue
                   ON ue.episode_id = e.id
                  AND ue.user_id = us.user_id
-                WHERE COALESCE(ue.done, false) = false
+                WHERE e.deleted_at IS NULL
+                  AND CASE $5
+                    WHEN 'unfinished' THEN NOT COALESCE(ue.done, false)
+                    WHEN 'unplayed' THEN NOT COALESCE(ue.done, false)
+                                     AND COALESCE(ue.progress, 0) = 0
+                    WHEN 'in_progress' THEN NOT COALESCE(ue.done, false)
+                                        AND COALESCE(ue.progress, 0) > 0
+                    WHEN 'done' THEN COALESCE(ue.done, false)
+                    ELSE true
+                  END
+                  AND (cardinality($6::text[]) = 0 OR e.podcast_id = ANY($6))
+                  -- Hidden, paused and archived shows only show up in
+                  -- playlists that name them.
+                  AND ((NOT us.hide_from_inbox AND us.state = 'active') OR e.podcast_id = ANY($6))
+                  AND ($7::int IS NULL OR e.audio_duration >= $7)
+                  AND ($8::int IS NULL OR e.audio_duration <= $8)
+                  AND ($9::timestamptz IS NULL OR e.publication_date >= $9)
                   AND (
                     $2::timestamptz IS NULL
                     OR e.publication_date < $2
@@ -413,11 +1185,84 @@ impl Db {
         .bind(cursor_date)
         .bind(cursor_id)
         .bind(limit)
+        .bind(filter.progress.as_str())
+        .bind(&filter.podcast_ids)
+        .bind(filter.min_duration_secs)
+        .bind(filter.max_duration_secs)
+        .bind(published_after)
         .fetch_all(&self.pool)
         .await?;
         Ok(episodes)
     }
 
+    /// Episodes of a user's subscriptions whose own text, or whose
+    /// podcast's, matches a web-search style query (`"exact phrase" -excluded
+    /// or`). Best matches first; ties and pages are ordered by episode id.
+    pub async fn search_episodes(
+        &self,
+        username: &str,
+        query: &str,
+        limit: i64,
+        cursor: Option<SearchCursor>,
+    ) -> Result<Vec<pod_model::SearchResult>> {
+        let (cursor_rank, cursor_id) = match cursor {
+            Some((rank, id)) => (Some(rank), Some(id)),
+            None => (None, None),
+        };
+        // The snippet is computed in the outer query so it's only built for
+        // the rows on the page.
+        let results = sqlx::query_as(
+            r#"
+                SELECT r.*
+                     , ts_headline(
+                           'simple',
+                           regexp_replace(
+                               regexp_replace(COALESCE(NULLIF(r.content_encoded, ''), r.summary), '<[^>]*>', ' ', 'g'),
+                               '\s+', ' ', 'g'
+                           ),
+                           websearch_to_tsquery('simple', $2),
+                           'StartSel=<mark>, StopSel=</mark>, MinWords=10, MaxWords=30, MaxFragments=2'
+                       ) AS snippet
+                FROM (
+                    SELECT e.*
+                         , ue.progress
+                         , COALESCE(ue.done, false) AS done
+                         , COALESCE(us.title_override, p.title) AS podcast_title
+                         , p.image_link AS podcast_image_link
+                         , (ts_rank(episode_search_vector(e.title, e.summary, e.content_encoded), q.query)
+                            + 0.5 * ts_rank(podcast_search_vector(p.title, p.description), q.query))::REAL AS rank
+                    FROM websearch_to_tsquery('simple', $2) AS q(query)
+                    CROSS JOIN episode e
+                    JOIN user_subscription us
+                      ON us.podcast_id = e.podcast_id
+                     AND us.user_id = (SELECT id FROM users WHERE username = $1)
+                    JOIN podcast p ON p.id = e.podcast_id
+                    LEFT JOIN user_episode ue
+                      ON ue.episode_id = e.id
+                     AND ue.user_id = us.user_id
+                    WHERE e.deleted_at IS NULL
+                      AND (
+                        episode_search_vector(e.title, e.summary, e.content_encoded) @@ q.query
+                        OR podcast_search_vector(p.title, p.description) @@ q.query
+                      )
+                ) r
+                WHERE $3::REAL IS NULL
+                   OR r.rank < $3
+                   OR (r.rank = $3 AND r.id < $4)
+                ORDER BY r.rank DESC, r.id DESC
+                LIMIT $5
+            "#,
+        )
+        .bind(username)
+        .bind(query)
+        .bind(cursor_rank)
+        .bind(cursor_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(results)
+    }
+
     pub async fn update_progress(
         &self,
         username: &str,
@@ -497,31 +1342,474 @@ impl Db {
         Ok(new_episode_ids)
     }
 
+    /// Return live episodes of a podcast that are missing from `episode_ids`
+    /// and were published at or after `since`.
+    pub async fn find_removed_episode_ids(
+        &self,
+        podcast_id: &str,
+        episode_ids: &[String],
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<String>> {
+        struct EpisodeId {
+            id: String,
+        }
+
+        let removed = sqlx::query_as!(
+            EpisodeId,
+            r#"
+            SELECT e.id FROM episode e
+            WHERE e.podcast_id = $1
+              AND e.deleted_at IS NULL
+              AND e.publication_date >= $3
+              AND NOT (e.id = ANY($2::text[]))
+            "#,
+            podcast_id,
+            episode_ids,
+            since,
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|e| e.id)
+        .collect();
+        Ok(removed)
+    }
+
+    /// Soft-delete episodes and record a delete entry in the change log for
+    /// each one. Already-deleted episodes are left untouched.
+    pub async fn delete_episodes(&self, podcast_id: &str, episode_ids: &[String]) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            WITH deleted AS (
+                UPDATE episode SET deleted_at = now(), last_updated = now()
+                WHERE podcast_id = $1 AND id = ANY($2::text[]) AND deleted_at IS NULL
+                RETURNING id, podcast_id
+            )
+            INSERT INTO episode_change (podcast_id, episode_id, op)
+            SELECT podcast_id, id, 'delete' FROM deleted
+            "#,
+            podcast_id,
+            episode_ids,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Undo a soft delete for episodes that reappeared in the feed, recording
+    /// an upsert entry in the change log for each restored episode.
+    pub async fn restore_episodes(&self, podcast_id: &str, episode_ids: &[String]) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            WITH restored AS (
+                UPDATE episode SET deleted_at = NULL, last_updated = now()
+                WHERE podcast_id = $1 AND id = ANY($2::text[]) AND deleted_at IS NOT NULL
+                RETURNING id, podcast_id
+            )
+            INSERT INTO episode_change (podcast_id, episode_id, op)
+            SELECT podcast_id, id, 'upsert' FROM restored
+            "#,
+            podcast_id,
+            episode_ids,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    // ==========================================================================
+    // Up Next queue
+    // ==========================================================================
+
+    /// The user's queue in play order, with progress and podcast metadata.
+    /// Episodes that have been removed from their feed are left out.
+    pub async fn get_queue(&self, username: &str) -> Result<Vec<pod_model::InboxEpisode>> {
+        let episodes = sqlx::query_as(
+            r#"
+                SELECT e.*
+                     , ue.progress
+                     , COALESCE(ue.done, false) AS done
+                     , COALESCE(us.title_override, p.title) AS podcast_title
+                     , p.image_link AS podcast_image_link
+                FROM queue_item q
+                JOIN episode e ON e.id = q.episode_id
+                JOIN podcast p ON p.id = e.podcast_id
+                LEFT JOIN user_subscription us
+                  ON us.podcast_id = e.podcast_id
+                 AND us.user_id = q.user_id
+                LEFT JOIN user_episode ue
+                  ON ue.episode_id = e.id
+                 AND ue.user_id = q.user_id
+                WHERE q.user_id = (SELECT id FROM users WHERE username = $1)
+                  AND e.deleted_at IS NULL
+                ORDER BY q.position, q.added_at
+            "#,
+        )
+        .bind(username)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(episodes)
+    }
+
+    /// Ids of the episodes in the user's queue, in play order.
+    pub async fn get_queue_episode_ids(&self, username: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT q.episode_id
+            FROM queue_item q
+            JOIN users u ON u.id = q.user_id
+            JOIN episode e ON e.id = q.episode_id
+            WHERE u.username = $1 AND e.deleted_at IS NULL
+            ORDER BY q.position, q.added_at
+            "#,
+            username
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|r| r.episode_id).collect())
+    }
+
+    /// Add an episode to the end of the user's queue, or move it to the
+    /// front when `front` is set. Appending an episode that is already queued
+    /// leaves it where it is. Returns whether the queue changed.
+    pub async fn enqueue_episode(
+        &self,
+        username: &str,
+        episode_id: &str,
+        front: bool,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = lock_user(&mut tx, username).await?;
+
+        let changed = if front {
+            let first = sqlx::query!(
+                r#"
+                SELECT episode_id FROM queue_item
+                WHERE user_id = $1
+                ORDER BY position, added_at
+                LIMIT 1
+                "#,
+                user_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+            if first.is_some_and(|f| f.episode_id == episode_id) {
+                false
+            } else {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO queue_item (user_id, episode_id, position)
+                    SELECT $1, $2, COALESCE(MIN(position) - 1, 0)
+                    FROM queue_item WHERE user_id = $1
+                    ON CONFLICT (user_id, episode_id) DO UPDATE SET position = EXCLUDED.position
+                    "#,
+                    user_id,
+                    episode_id
+                )
+                .execute(&mut *tx)
+                .await?;
+                true
+            }
+        } else {
+            sqlx::query!(
+                r#"
+                INSERT INTO queue_item (user_id, episode_id, position)
+                SELECT $1, $2, COALESCE(MAX(position) + 1, 0)
+                FROM queue_item WHERE user_id = $1
+                ON CONFLICT (user_id, episode_id) DO NOTHING
+                "#,
+                user_id,
+                episode_id
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                > 0
+        };
+
+        if changed {
+            record_queue_change(&mut tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Remove an episode from the user's queue. Returns false if it wasn't
+    /// queued.
+    pub async fn dequeue_episode(&self, username: &str, episode_id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = lock_user(&mut tx, username).await?;
+
+        let removed = sqlx::query!(
+            r#"
+            DELETE FROM queue_item WHERE user_id = $1 AND episode_id = $2
+            "#,
+            user_id,
+            episode_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if removed > 0 {
+            record_queue_change(&mut tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(removed > 0)
+    }
+
+    /// Replace the user's queue with `episode_ids`, in that order. The ids
+    /// must be distinct. Returns false, leaving the queue untouched, if any
+    /// of them isn't an episode of the user's subscriptions.
+    pub async fn replace_queue(&self, username: &str, episode_ids: &[String]) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = lock_user(&mut tx, username).await?;
+
+        let current = sqlx::query!(
+            r#"
+            SELECT episode_id FROM queue_item
+            WHERE user_id = $1
+            ORDER BY position, added_at
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if current.iter().map(|r| &r.episode_id).eq(episode_ids.iter()) {
+            return Ok(true);
+        }
+
+        sqlx::query!(r#"DELETE FROM queue_item WHERE user_id = $1"#, user_id)
+            .execute(&mut *tx)
+            .await?;
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO queue_item (user_id, episode_id, position)
+            SELECT $1, e.id, q.position::int
+            FROM UNNEST($2::text[]) WITH ORDINALITY AS q(episode_id, position)
+            JOIN episode e ON e.id = q.episode_id AND e.deleted_at IS NULL
+            JOIN user_subscription us ON us.podcast_id = e.podcast_id AND us.user_id = $1
+            "#,
+            user_id,
+            episode_ids
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted != episode_ids.len() as u64 {
+            return Ok(false);
+        }
+
+        record_queue_change(&mut tx, user_id).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Empty the user's queue.
+    pub async fn clear_queue(&self, username: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = lock_user(&mut tx, username).await?;
+
+        let removed = sqlx::query!(r#"DELETE FROM queue_item WHERE user_id = $1"#, user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        if removed > 0 {
+            record_queue_change(&mut tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // ==========================================================================
+    // Playlists
+    // ==========================================================================
+
+    /// The user's saved playlists, oldest first.
+    pub async fn list_playlists(&self, username: &str) -> Result<Vec<Playlist>> {
+        let rows = sqlx::query_as!(
+            PlaylistRow,
+            r#"
+            SELECT pl.id, pl.name, pl.podcast_ids, pl.min_duration_secs, pl.max_duration_secs,
+                   pl.progress, pl.published_within_days
+            FROM playlist pl
+            JOIN users u ON u.id = pl.user_id
+            WHERE u.username = $1
+            ORDER BY pl.created_at, pl.id
+            "#,
+            username
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Playlist::from).collect())
+    }
+
+    pub async fn get_playlist(&self, username: &str, id: &str) -> Result<Option<Playlist>> {
+        let row = sqlx::query_as!(
+            PlaylistRow,
+            r#"
+            SELECT pl.id, pl.name, pl.podcast_ids, pl.min_duration_secs, pl.max_duration_secs,
+                   pl.progress, pl.published_within_days
+            FROM playlist pl
+            JOIN users u ON u.id = pl.user_id
+            WHERE u.username = $1 AND pl.id = $2
+            "#,
+            username,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(Playlist::from))
+    }
+
+    pub async fn insert_playlist(
+        &self,
+        username: &str,
+        name: &str,
+        filter: &PlaylistFilter,
+    ) -> Result<Playlist> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = lock_user(&mut tx, username).await?;
+
+        let row = sqlx::query_as!(
+            PlaylistRow,
+            r#"
+            INSERT INTO playlist (user_id, name, podcast_ids, min_duration_secs,
+                                  max_duration_secs, progress, published_within_days)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, name, podcast_ids, min_duration_secs, max_duration_secs,
+                      progress, published_within_days
+            "#,
+            user_id,
+            name,
+            &filter.podcast_ids,
+            filter.min_duration_secs,
+            filter.max_duration_secs,
+            filter.progress.as_str(),
+            filter.published_within_days,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        record_playlist_change(&mut tx, user_id).await?;
+        tx.commit().await?;
+        Ok(row.into())
+    }
+
+    /// Rename a playlist and replace its filter. Returns `None` if the user
+    /// has no such playlist.
+    pub async fn update_playlist(
+        &self,
+        username: &str,
+        id: &str,
+        name: &str,
+        filter: &PlaylistFilter,
+    ) -> Result<Option<Playlist>> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = lock_user(&mut tx, username).await?;
+
+        let row = sqlx::query_as!(
+            PlaylistRow,
+            r#"
+            UPDATE playlist
+            SET name = $3, podcast_ids = $4, min_duration_secs = $5, max_duration_secs = $6,
+                progress = $7, published_within_days = $8, last_updated = now()
+            WHERE user_id = $1 AND id = $2
+            RETURNING id, name, podcast_ids, min_duration_secs, max_duration_secs,
+                      progress, published_within_days
+            "#,
+            user_id,
+            id,
+            name,
+            &filter.podcast_ids,
+            filter.min_duration_secs,
+            filter.max_duration_secs,
+            filter.progress.as_str(),
+            filter.published_within_days,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if row.is_some() {
+            record_playlist_change(&mut tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(row.map(Playlist::from))
+    }
+
+    /// Delete a playlist. Returns false if the user has no such playlist.
+    pub async fn delete_playlist(&self, username: &str, id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = lock_user(&mut tx, username).await?;
+
+        let removed = sqlx::query!(
+            r#"DELETE FROM playlist WHERE user_id = $1 AND id = $2"#,
+            user_id,
+            id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if removed > 0 {
+            record_playlist_change(&mut tx, user_id).await?;
+        }
+        tx.commit().await?;
+        Ok(removed > 0)
+    }
+
     // ==========================================================================
     // Sync protocol
     // ==========================================================================
 
-    /// Return episode changes for a user's subscriptions since the given
-    /// sequence number, ordered by seq ascending.  Fetches `limit + 1` rows
-    /// so the caller can detect whether more pages remain.
+    /// Return episode, subscription, queue and playlist changes for a user
+    /// since the given sequence number, ordered by seq ascending.  The logs
+    /// share one sequence, so they merge into a single stream.  Fetches
+    /// `limit + 1` rows so the caller can detect whether more pages remain.
     pub async fn get_sync_changes(
         &self,
         username: &str,
         since_seq: i64,
         limit: i64,
-    ) -> Result<Vec<EpisodeChangeRow>> {
+    ) -> Result<Vec<SyncChangeRow>> {
         let rows = sqlx::query_as!(
-            EpisodeChangeRow,
-            r#"
-            SELECT ec.seq, ec.podcast_id, ec.episode_id, ec.op
-            FROM episode_change ec
-            JOIN user_subscription us
-              ON us.podcast_id = ec.podcast_id
-            JOIN users u
-              ON u.id = us.user_id
-            WHERE u.username = $1
-              AND ec.seq > $2
-            ORDER BY ec.seq ASC
+            SyncChangeRow,
+            r#"
+            SELECT c.seq as "seq!", c.change_type as "change_type!", c.podcast_id as "podcast_id!",
+                   c.episode_id, c.op as "op!", c.changed_at as "changed_at!"
+            FROM (
+                SELECT ec.seq, 'episode' AS change_type, ec.podcast_id, ec.episode_id, ec.op, ec.changed_at
+                FROM episode_change ec
+                JOIN user_subscription us
+                  ON us.podcast_id = ec.podcast_id
+                JOIN users u
+                  ON u.id = us.user_id
+                WHERE u.username = $1
+                  AND ec.seq > $2
+                UNION ALL
+                SELECT sc.seq, 'subscription' AS change_type, sc.podcast_id, NULL AS episode_id, sc.op, sc.changed_at
+                FROM subscription_change sc
+                JOIN users u
+                  ON u.id = sc.user_id
+                WHERE u.username = $1
+                  AND sc.seq > $2
+                UNION ALL
+                SELECT qc.seq, 'queue' AS change_type, '' AS podcast_id, NULL AS episode_id, 'upsert' AS op, qc.changed_at
+                FROM queue_change qc
+                JOIN users u
+                  ON u.id = qc.user_id
+                WHERE u.username = $1
+                  AND qc.seq > $2
+                UNION ALL
+                SELECT pc.seq, 'playlists' AS change_type, '' AS podcast_id, NULL AS episode_id, 'upsert' AS op, pc.changed_at
+                FROM playlist_change pc
+                JOIN users u
+                  ON u.id = pc.user_id
+                WHERE u.username = $1
+                  AND pc.seq > $2
+            ) c
+            ORDER BY c.seq ASC
             LIMIT $3
             "#,
             username,
@@ -541,13 +1829,41 @@ impl Db {
         let row = sqlx::query_as!(
             SeqRow,
             r#"
-            SELECT MAX(ec.seq) as seq
-            FROM episode_change ec
-            JOIN user_subscription us
-              ON us.podcast_id = ec.podcast_id
-            JOIN users u
-              ON u.id = us.user_id
-            WHERE u.username = $1
+            SELECT GREATEST(
+                (
+                    SELECT MAX(ec.seq)
+                    FROM episode_change ec
+                    JOIN user_subscription us
+                      ON us.podcast_id = ec.podcast_id
+                    JOIN users u
+                      ON u.id = us.user_id
+                    WHERE u.username = $1
+                ),
+                (
+                    SELECT MAX(sc.seq)
+                    FROM subscription_change sc
+                    JOIN users u
+                      ON u.id = sc.user_id
+                    WHERE u.username = $1
+                ),
+                (
+                    SELECT MAX(qc.seq)
+                    FROM queue_change qc
+                    JOIN users u
+                      ON u.id = qc.user_id
+                    WHERE u.username = $1
+                ),
+                (
+                    SELECT MAX(pc.seq)
+                    FROM playlist_change pc
+                    JOIN users u
+                      ON u.id = pc.user_id
+                    WHERE u.username = $1
+                ),
+                -- Never report a head below the prune watermark, or a
+                -- freshly bootstrapped client would be handed a stale cursor.
+                (SELECT pruned_through_seq FROM sync_log_state)
+            ) as seq
             "#,
             username,
         )
@@ -555,4 +1871,297 @@ impl Db {
         .await?;
         Ok(row.seq)
     }
+
+    /// Return the highest seq removed by horizon pruning. Cursors below this
+    /// may have missed changes.
+    pub async fn get_pruned_through_seq(&self) -> Result<i64> {
+        let row = sqlx::query!(r#"SELECT pruned_through_seq FROM sync_log_state"#)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.pruned_through_seq)
+    }
+
+    /// Compact the episode, subscription, queue and playlist change logs:
+    /// keep only the latest row per episode (and per user subscription, queue
+    /// or playlist set), then
+    /// drop rows older than `horizon`, advancing the prune watermark past
+    /// them.
+    pub async fn compact_change_log(
+        &self,
+        horizon: chrono::DateTime<chrono::Utc>,
+    ) -> Result<CompactionStats> {
+        let mut tx = self.pool.begin().await?;
+
+        let superseded_episodes = sqlx::query!(
+            r#"
+            DELETE FROM episode_change ec
+            WHERE EXISTS (
+                SELECT 1 FROM episode_change newer
+                WHERE newer.episode_id = ec.episode_id AND newer.seq > ec.seq
+            )
+            "#
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let superseded_subscriptions = sqlx::query!(
+            r#"
+            DELETE FROM subscription_change sc
+            WHERE EXISTS (
+                SELECT 1 FROM subscription_change newer
+                WHERE newer.user_id = sc.user_id
+                  AND newer.podcast_id = sc.podcast_id
+                  AND newer.seq > sc.seq
+            )
+            "#
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let superseded_queues = sqlx::query!(
+            r#"
+            DELETE FROM queue_change qc
+            WHERE EXISTS (
+                SELECT 1 FROM queue_change newer
+                WHERE newer.user_id = qc.user_id AND newer.seq > qc.seq
+            )
+            "#
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let superseded_playlists = sqlx::query!(
+            r#"
+            DELETE FROM playlist_change pc
+            WHERE EXISTS (
+                SELECT 1 FROM playlist_change newer
+                WHERE newer.user_id = pc.user_id AND newer.seq > pc.seq
+            )
+            "#
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        struct PrunedRow {
+            count: Option<i64>,
+            max_seq: Option<i64>,
+        }
+        let pruned = sqlx::query_as!(
+            PrunedRow,
+            r#"
+            WITH pruned_episodes AS (
+                DELETE FROM episode_change WHERE changed_at < $1 RETURNING seq
+            ), pruned_subscriptions AS (
+                DELETE FROM subscription_change WHERE changed_at < $1 RETURNING seq
+            ), pruned_queues AS (
+                DELETE FROM queue_change WHERE changed_at < $1 RETURNING seq
+            ), pruned_playlists AS (
+                DELETE FROM playlist_change WHERE changed_at < $1 RETURNING seq
+            ), pruned AS (
+                SELECT seq FROM pruned_episodes
+                UNION ALL
+                SELECT seq FROM pruned_subscriptions
+                UNION ALL
+                SELECT seq FROM pruned_queues
+                UNION ALL
+                SELECT seq FROM pruned_playlists
+            )
+            SELECT COUNT(*) as count, MAX(seq) as max_seq FROM pruned
+            "#,
+            horizon,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(max_seq) = pruned.max_seq {
+            sqlx::query!(
+                r#"
+                UPDATE sync_log_state
+                SET pruned_through_seq = GREATEST(pruned_through_seq, $1)
+                "#,
+                max_seq,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(CompactionStats {
+            superseded: superseded_episodes
+                + superseded_subscriptions
+                + superseded_queues
+                + superseded_playlists,
+            pruned: pruned.count.unwrap_or(0) as u64,
+        })
+    }
+
+    pub async fn get_websub_subscription(
+        &self,
+        podcast_id: &str,
+    ) -> Result<Option<WebSubSubscription>> {
+        let subscription = sqlx::query_as!(
+            WebSubSubscription,
+            r#"SELECT * FROM websub_subscription WHERE podcast_id = $1"#,
+            podcast_id,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(subscription)
+    }
+
+    /// Record a subscription request sent to a hub. Re-requesting the same
+    /// hub and topic keeps the current state and lease so pushes keep
+    /// flowing during renewal; a new hub or topic starts over as pending.
+    pub async fn upsert_websub_subscription(
+        &self,
+        podcast_id: &str,
+        hub_url: &str,
+        topic_url: &str,
+        secret: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO websub_subscription (podcast_id, hub_url, topic_url, secret)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (podcast_id) DO UPDATE SET
+                state = CASE
+                    WHEN websub_subscription.hub_url = EXCLUDED.hub_url
+                     AND websub_subscription.topic_url = EXCLUDED.topic_url
+                    THEN websub_subscription.state
+                    ELSE 'pending'
+                END,
+                lease_expires_at = CASE
+                    WHEN websub_subscription.hub_url = EXCLUDED.hub_url
+                     AND websub_subscription.topic_url = EXCLUDED.topic_url
+                    THEN websub_subscription.lease_expires_at
+                END,
+                hub_url = EXCLUDED.hub_url,
+                topic_url = EXCLUDED.topic_url,
+                secret = EXCLUDED.secret,
+                requested_at = now()
+            "#,
+            podcast_id,
+            hub_url,
+            topic_url,
+            secret,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Mark a subscription verified by its hub with the granted lease.
+    pub async fn activate_websub_subscription(
+        &self,
+        podcast_id: &str,
+        lease_expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE websub_subscription
+            SET state = 'active', lease_expires_at = $2
+            WHERE podcast_id = $1
+            "#,
+            podcast_id,
+            lease_expires_at,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn deny_websub_subscription(&self, podcast_id: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE websub_subscription
+            SET state = 'denied', lease_expires_at = NULL
+            WHERE podcast_id = $1
+            "#,
+            podcast_id,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_websub_subscription(&self, podcast_id: &str) -> Result<()> {
+        sqlx::query!(
+            r#"DELETE FROM websub_subscription WHERE podcast_id = $1"#,
+            podcast_id,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_websub_push(&self, podcast_id: &str) -> Result<()> {
+        sqlx::query!(
+            r#"UPDATE websub_subscription SET last_push_at = now() WHERE podcast_id = $1"#,
+            podcast_id,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// List active subscriptions whose lease expires before `before` and
+    /// that weren't already re-requested after `requested_before`.
+    pub async fn list_websub_subscriptions_expiring(
+        &self,
+        before: chrono::DateTime<chrono::Utc>,
+        requested_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<WebSubSubscription>> {
+        let subscriptions = sqlx::query_as!(
+            WebSubSubscription,
+            r#"
+            SELECT * FROM websub_subscription
+            WHERE state = 'active' AND lease_expires_at < $1 AND requested_at < $2
+            "#,
+            before,
+            requested_before,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(subscriptions)
+    }
+}
+
+/// Lock the user's row for the rest of the transaction so concurrent queue
+/// or playlist edits apply one after the other. Returns the user's id.
+async fn lock_user(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    username: &str,
+) -> Result<uuid::Uuid> {
+    let user = sqlx::query!(r#"SELECT id FROM users WHERE username = $1 FOR UPDATE"#, username)
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(user.id)
+}
+
+/// Record that the user's queue changed so other clients pick it up on
+/// their next sync.
+async fn record_queue_change(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: uuid::Uuid,
+) -> Result<()> {
+    sqlx::query!(r#"INSERT INTO queue_change (user_id) VALUES ($1)"#, user_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Record that the user's playlists changed so other clients pick them up
+/// on their next sync.
+async fn record_playlist_change(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: uuid::Uuid,
+) -> Result<()> {
+    sqlx::query!(r#"INSERT INTO playlist_change (user_id) VALUES ($1)"#, user_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
 }
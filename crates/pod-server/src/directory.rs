@@ -0,0 +1,155 @@
+use pod_model::DirectoryPodcast;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Public iTunes Search API endpoint, used unless `catalog_base_url` is set.
+pub const ITUNES_BASE_URL: &str = "https://itunes.apple.com";
+
+#[derive(Debug, Error)]
+pub enum DirectoryError {
+    #[error("reqwest error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("directory returned {0}")]
+    Status(reqwest::StatusCode),
+}
+
+/// A podcast directory that can be searched for shows to subscribe to.
+#[axum::async_trait]
+pub trait CatalogProvider: Send + Sync {
+    /// Shows matching `term`, best match first, at most `limit` of them.
+    async fn search(
+        &self,
+        term: &str,
+        limit: u32,
+    ) -> Result<Vec<DirectoryPodcast>, DirectoryError>;
+}
+
+/// A directory speaking the iTunes Search API (`/search?media=podcast`).
+pub struct ITunesCatalog {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl ITunesCatalog {
+    pub fn new(http: reqwest::Client, base_url: &str) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    results: Vec<SearchResult>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResult {
+    collection_name: Option<String>,
+    artist_name: Option<String>,
+    feed_url: Option<String>,
+    artwork_url600: Option<String>,
+    artwork_url100: Option<String>,
+    primary_genre_name: Option<String>,
+    track_count: Option<i64>,
+}
+
+impl SearchResult {
+    /// Entries without a feed URL (e.g. Apple-exclusive shows) can't be
+    /// subscribed to and are dropped.
+    fn into_podcast(self) -> Option<DirectoryPodcast> {
+        Some(DirectoryPodcast {
+            title: self.collection_name?,
+            author: self.artist_name,
+            feed_url: self.feed_url?,
+            image_link: self.artwork_url600.or(self.artwork_url100),
+            genre: self.primary_genre_name,
+            episode_count: self.track_count,
+        })
+    }
+}
+
+#[axum::async_trait]
+impl CatalogProvider for ITunesCatalog {
+    async fn search(
+        &self,
+        term: &str,
+        limit: u32,
+    ) -> Result<Vec<DirectoryPodcast>, DirectoryError> {
+        let res = self
+            .http
+            .get(format!("{}/search", self.base_url))
+            .query(&[
+                ("media", "podcast"),
+                ("entity", "podcast"),
+                ("term", term),
+                ("limit", &limit.to_string()),
+            ])
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(DirectoryError::Status(res.status()));
+        }
+        let body: SearchResponse = res.json().await?;
+        Ok(body
+            .results
+            .into_iter()
+            .filter_map(SearchResult::into_podcast)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{extract::Query, routing::get, Json};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn itunes_search_maps_results() {
+        let stub = axum::Router::new().route(
+            "/search",
+            get(|Query(params): Query<HashMap<String, String>>| async move {
+                assert_eq!(params["media"], "podcast");
+                assert_eq!(params["term"], "rust lang");
+                assert_eq!(params["limit"], "5");
+                Json(serde_json::json!({
+                    "resultCount": 2,
+                    "results": [
+                        {
+                            "collectionName": "Rustacean Station",
+                            "artistName": "Rustacean Station",
+                            "feedUrl": "https://rustacean-station.org/podcast.rss",
+                            "artworkUrl100": "https://example.com/100.jpg",
+                            "artworkUrl600": "https://example.com/600.jpg",
+                            "primaryGenreName": "Technology",
+                            "trackCount": 150
+                        },
+                        {"collectionName": "Exclusive", "artistName": "Nobody"}
+                    ]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, stub).await.unwrap() });
+
+        let catalog = ITunesCatalog::new(reqwest::Client::new(), &format!("http://{}/", addr));
+        let results = catalog.search("rust lang", 5).await.unwrap();
+        assert_eq!(
+            results,
+            vec![DirectoryPodcast {
+                title: "Rustacean Station".to_string(),
+                author: Some("Rustacean Station".to_string()),
+                feed_url: "https://rustacean-station.org/podcast.rss".to_string(),
+                image_link: Some("https://example.com/600.jpg".to_string()),
+                genre: Some("Technology".to_string()),
+                episode_count: Some(150),
+            }]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use reqwest::header;
 use thiserror::Error;
 
-use pod_model::Episode;
+use pod_model::{Episode, Funding, Person, Transcript};
 
 #[derive(Debug, Clone)]
 pub struct FeedRef {
@@ -15,17 +15,40 @@ pub enum GetFeedError {
     Reqwest(#[from] reqwest::Error),
     #[error("feed_rs error: {0}")]
     FeedRs(#[from] feed_rs::parser::ParseFeedError),
+    /// Non-success HTTP status. `retry_after` is set when the server sent a
+    /// `Retry-After` header (typically with 429 or 503).
+    #[error("feed server returned {status}")]
+    Status {
+        status: reqwest::StatusCode,
+        retry_after: Option<chrono::Duration>,
+    },
 }
 
 /// Result of a conditional feed fetch. `NotModified` means the server
 /// returned 304 and there is no new content to process.
 pub enum FeedResult {
     Fetched {
-        feed: feed_rs::model::Feed,
+        feed: Box<feed_rs::model::Feed>,
+        extensions: FeedExtensions,
         etag: Option<String>,
         last_modified: Option<String>,
+        hints: RefreshHints,
+    },
+    NotModified {
+        hints: RefreshHints,
     },
-    NotModified,
+}
+
+/// Signals from a feed and its HTTP response about how often it should be
+/// polled.
+#[derive(Debug, Clone, Default)]
+pub struct RefreshHints {
+    /// Update interval declared by the feed itself via `<ttl>` or
+    /// `sy:updatePeriod`/`sy:updateFrequency`. Only known when the feed body
+    /// was fetched.
+    pub feed_interval: Option<chrono::Duration>,
+    /// `Cache-Control: max-age` of the response.
+    pub max_age: Option<chrono::Duration>,
 }
 
 /// Cached conditional-request headers from a previous fetch.
@@ -56,8 +79,31 @@ pub async fn get_feed(
 
     let res = c.execute(req.build()?).await?;
 
+    let max_age = res
+        .headers()
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_cache_control_max_age);
+
     if res.status() == reqwest::StatusCode::NOT_MODIFIED {
-        return Ok(FeedResult::NotModified);
+        return Ok(FeedResult::NotModified {
+            hints: RefreshHints {
+                feed_interval: None,
+                max_age,
+            },
+        });
+    }
+
+    if !res.status().is_success() {
+        let retry_after = res
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, chrono::Utc::now()));
+        return Err(GetFeedError::Status {
+            status: res.status(),
+            retry_after,
+        });
     }
 
     let response_etag = res
@@ -73,15 +119,352 @@ pub async fn get_feed(
         .map(|s| s.to_string());
 
     let text = res.text().await?;
-    let feed = feed_rs::parser::parse(text.as_bytes())?;
+    let (feed, extensions) = parse_feed(text.as_bytes())?;
+
+    // `<ttl>` is in minutes. When both it and the syndication module are
+    // present, honour the longer of the two.
+    let ttl = feed.ttl.map(|m| chrono::Duration::minutes(m.into()));
+    let feed_interval = ttl.into_iter().chain(syndication_interval(&text)).max();
 
     Ok(FeedResult::Fetched {
-        feed,
+        feed: Box::new(feed),
+        extensions,
         etag: response_etag,
         last_modified: response_last_modified,
+        hints: RefreshHints {
+            feed_interval,
+            max_age,
+        },
     })
 }
 
+/// Parse a feed document with feed-rs, along with the namespaced tags
+/// feed-rs doesn't surface.
+pub fn parse_feed(
+    body: &[u8],
+) -> Result<(feed_rs::model::Feed, FeedExtensions), GetFeedError> {
+    let feed = feed_rs::parser::parse(body)?;
+    let mut extensions = parse_extensions(body);
+    // Items are matched to entries by position, which only holds when both
+    // parsers saw the same items.
+    if extensions.items.len() != feed.entries.len() {
+        extensions.items.clear();
+    }
+    Ok((feed, extensions))
+}
+
+/// Longest delay a feed server's caching headers can ask for, a year. The
+/// refresh policy's own maximum applies on top of this.
+const MAX_HINT_SECS: i64 = 365 * 24 * 60 * 60;
+
+/// Turn a header's delay into a duration, clamped to `0..=MAX_HINT_SECS`.
+fn hint_duration(secs: i64) -> Option<chrono::Duration> {
+    chrono::TimeDelta::try_seconds(secs.clamp(0, MAX_HINT_SECS))
+}
+
+/// Extract `max-age` from a `Cache-Control` header value.
+fn parse_cache_control_max_age(value: &str) -> Option<chrono::Duration> {
+    value
+        .split(',')
+        .map(|directive| directive.trim().to_ascii_lowercase())
+        .find_map(|directive| {
+            let secs = directive.strip_prefix("max-age=")?;
+            secs.trim_matches('"').parse::<i64>().ok()
+        })
+        .and_then(hint_duration)
+}
+
+/// Parse a `Retry-After` header, which is either delta-seconds or an
+/// HTTP date.
+fn parse_retry_after(
+    value: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<chrono::Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<i64>() {
+        return hint_duration(secs);
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    hint_duration((at.with_timezone(&chrono::Utc) - now).num_seconds())
+}
+
+/// Read the RSS syndication module's `sy:updatePeriod` and
+/// `sy:updateFrequency` from the channel, returning the implied interval
+/// between updates. feed-rs doesn't surface these, so scan the raw XML up
+/// to the first item.
+fn syndication_interval(xml: &str) -> Option<chrono::Duration> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut current: Option<Vec<u8>> = None;
+    let mut period = None;
+    let mut frequency = None;
+
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) => {
+                let name = e.local_name().as_ref().to_vec();
+                if name == b"item" || name == b"entry" {
+                    break;
+                }
+                current = Some(name);
+            }
+            Event::Text(t) => {
+                let text = t.unescape().ok()?.trim().to_string();
+                match current.as_deref() {
+                    Some(b"updatePeriod") => period = Some(text),
+                    Some(b"updateFrequency") => frequency = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(_) => current = None,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let period = match period?.to_ascii_lowercase().as_str() {
+        "hourly" => chrono::Duration::hours(1),
+        "daily" => chrono::Duration::days(1),
+        "weekly" => chrono::Duration::weeks(1),
+        "monthly" => chrono::Duration::days(30),
+        "yearly" => chrono::Duration::days(365),
+        _ => return None,
+    };
+    // The frequency is the number of updates per period.
+    let frequency = frequency
+        .and_then(|f| f.parse::<i32>().ok())
+        .filter(|f| *f > 0)
+        .unwrap_or(1);
+    Some(period / frequency)
+}
+
+const PODCAST_NS: &[u8] = b"https://podcastindex.org/namespace/1.0";
+const ITUNES_NS: &[u8] = b"http://www.itunes.com/dtds/podcast-1.0.dtd";
+
+/// Podcasting 2.0 and iTunes tags from a feed, which feed-rs doesn't parse.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedExtensions {
+    /// `podcast:guid` of the channel.
+    pub podcast_guid: Option<String>,
+    pub funding: Vec<Funding>,
+    /// One entry per `<item>`/`<entry>`, in document order, so index `i`
+    /// belongs to `feed.entries[i]`.
+    pub items: Vec<ItemExtensions>,
+}
+
+impl FeedExtensions {
+    /// Extensions of the entry at `index`, or empty ones when the items
+    /// couldn't be matched to entries.
+    pub fn item(&self, index: usize) -> ItemExtensions {
+        self.items.get(index).cloned().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemExtensions {
+    pub itunes_season: Option<i32>,
+    pub itunes_episode: Option<i32>,
+    pub itunes_episode_type: Option<String>,
+    pub podcast_season: Option<i32>,
+    /// `podcast:episode` allows decimals (e.g. `1.5`); only whole numbers
+    /// are kept.
+    pub podcast_episode: Option<i32>,
+    pub chapters_url: Option<String>,
+    pub chapters_type: Option<String>,
+    pub transcripts: Vec<Transcript>,
+    pub persons: Vec<Person>,
+}
+
+impl ItemExtensions {
+    pub fn season(&self) -> Option<i32> {
+        self.itunes_season.or(self.podcast_season)
+    }
+
+    pub fn episode_number(&self) -> Option<i32> {
+        self.itunes_episode.or(self.podcast_episode)
+    }
+}
+
+/// The namespaced element whose text is currently being read.
+#[derive(Clone, Copy)]
+enum ExtensionText {
+    PodcastGuid,
+    Funding,
+    Person,
+    PodcastSeason,
+    PodcastEpisode,
+    ItunesSeason,
+    ItunesEpisode,
+    ItunesEpisodeType,
+}
+
+/// Scan a feed for Podcasting 2.0 and iTunes tags. Malformed documents
+/// yield whatever was read before the error; feed-rs reports the error
+/// itself.
+fn parse_extensions(xml: &[u8]) -> FeedExtensions {
+    use quick_xml::events::{BytesStart, Event};
+    use quick_xml::name::{Namespace, ResolveResult};
+
+    fn attr(reader: &quick_xml::NsReader<&[u8]>, e: &BytesStart, name: &[u8]) -> Option<String> {
+        e.attributes()
+            .flatten()
+            .find(|a| a.key.local_name().as_ref() == name)
+            .and_then(|a| a.decode_and_unescape_value(reader).ok())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    }
+
+    // RSS items are un-namespaced (or in the RSS 1.0 namespace), Atom
+    // entries are in the Atom namespace.
+    fn is_item(ns: Option<&[u8]>, local: &[u8]) -> bool {
+        ns != Some(PODCAST_NS) && ns != Some(ITUNES_NS) && (local == b"item" || local == b"entry")
+    }
+
+    fn int(text: &str) -> Option<i32> {
+        text.parse::<i32>()
+            .ok()
+            .or_else(|| {
+                let f = text.parse::<f64>().ok()?;
+                (f.fract() == 0.0).then_some(f as i32)
+            })
+            .filter(|n| *n >= 0)
+    }
+
+    let mut reader = quick_xml::NsReader::from_reader(xml);
+    let mut extensions = FeedExtensions::default();
+    let mut item: Option<ItemExtensions> = None;
+    let mut reading: Option<ExtensionText> = None;
+    let mut text = String::new();
+    let mut funding_url = None;
+    let mut person = None;
+
+    while let Ok((ns, event)) = reader.read_resolved_event() {
+        let ns = match ns {
+            ResolveResult::Bound(Namespace(ns)) => Some(ns),
+            _ => None,
+        };
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let empty = matches!(event, Event::Empty(_));
+                let local = e.local_name();
+                let local = local.as_ref();
+                if is_item(ns, local) {
+                    if empty {
+                        extensions.items.push(ItemExtensions::default());
+                    } else {
+                        item = Some(ItemExtensions::default());
+                    }
+                    continue;
+                }
+
+                let target = match (ns, local, item.as_mut()) {
+                    (Some(PODCAST_NS), b"guid", None) => Some(ExtensionText::PodcastGuid),
+                    (Some(PODCAST_NS), b"funding", None) => {
+                        funding_url = attr(&reader, e, b"url");
+                        Some(ExtensionText::Funding)
+                    }
+                    (Some(PODCAST_NS), b"chapters", Some(item)) => {
+                        item.chapters_url = attr(&reader, e, b"url");
+                        item.chapters_type = attr(&reader, e, b"type");
+                        None
+                    }
+                    (Some(PODCAST_NS), b"transcript", Some(item)) => {
+                        if let (Some(url), Some(mime_type)) =
+                            (attr(&reader, e, b"url"), attr(&reader, e, b"type"))
+                        {
+                            item.transcripts.push(Transcript {
+                                url,
+                                mime_type,
+                                language: attr(&reader, e, b"language"),
+                                rel: attr(&reader, e, b"rel"),
+                            });
+                        }
+                        None
+                    }
+                    (Some(PODCAST_NS), b"person", Some(_)) => {
+                        person = Some(Person {
+                            name: String::new(),
+                            role: attr(&reader, e, b"role")
+                                .unwrap_or_else(|| "host".to_string())
+                                .to_lowercase(),
+                            group: attr(&reader, e, b"group")
+                                .unwrap_or_else(|| "cast".to_string())
+                                .to_lowercase(),
+                            img: attr(&reader, e, b"img"),
+                            href: attr(&reader, e, b"href"),
+                        });
+                        Some(ExtensionText::Person)
+                    }
+                    (Some(PODCAST_NS), b"season", Some(_)) => Some(ExtensionText::PodcastSeason),
+                    (Some(PODCAST_NS), b"episode", Some(_)) => Some(ExtensionText::PodcastEpisode),
+                    (Some(ITUNES_NS), b"season", Some(_)) => Some(ExtensionText::ItunesSeason),
+                    (Some(ITUNES_NS), b"episode", Some(_)) => Some(ExtensionText::ItunesEpisode),
+                    (Some(ITUNES_NS), b"episodeType", Some(_)) => {
+                        Some(ExtensionText::ItunesEpisodeType)
+                    }
+                    _ => None,
+                };
+                if !empty {
+                    reading = target;
+                    text.clear();
+                }
+            }
+            Event::Text(t) if reading.is_some() => {
+                if let Ok(t) = t.unescape() {
+                    text.push_str(&t);
+                }
+            }
+            Event::CData(t) if reading.is_some() => {
+                text.push_str(&String::from_utf8_lossy(&t));
+            }
+            Event::End(ref e) => {
+                if is_item(ns, e.local_name().as_ref()) {
+                    extensions.items.extend(item.take());
+                    continue;
+                }
+                let Some(target) = reading.take() else {
+                    continue;
+                };
+                let value = text.trim().to_string();
+                match (target, item.as_mut()) {
+                    (ExtensionText::PodcastGuid, _) if !value.is_empty() => {
+                        extensions.podcast_guid = Some(value);
+                    }
+                    (ExtensionText::Funding, _) => {
+                        if let Some(url) = funding_url.take() {
+                            extensions.funding.push(Funding {
+                                url,
+                                message: value,
+                            });
+                        }
+                    }
+                    (ExtensionText::Person, Some(item)) => {
+                        if let Some(mut p) = person.take().filter(|_| !value.is_empty()) {
+                            p.name = value;
+                            item.persons.push(p);
+                        }
+                    }
+                    (ExtensionText::PodcastSeason, Some(item)) => item.podcast_season = int(&value),
+                    (ExtensionText::PodcastEpisode, Some(item)) => {
+                        item.podcast_episode = int(&value)
+                    }
+                    (ExtensionText::ItunesSeason, Some(item)) => item.itunes_season = int(&value),
+                    (ExtensionText::ItunesEpisode, Some(item)) => item.itunes_episode = int(&value),
+                    (ExtensionText::ItunesEpisodeType, Some(item)) if !value.is_empty() => {
+                        item.itunes_episode_type = Some(value.to_lowercase());
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    extensions
+}
+
 fn is_audio_mime(mt: &mime::Mime) -> bool {
     matches!(
         mt.essence_str(),
@@ -99,7 +482,7 @@ pub fn has_audio(e: &feed_rs::model::Entry) -> bool {
     let audio_content = e.media.iter().flat_map(|m| m.content.iter()).find(|e| {
         e.content_type
             .as_ref()
-            .map(|mt| is_audio_mime(mt))
+            .map(is_audio_mime)
             .unwrap_or(false)
     });
 
@@ -109,13 +492,14 @@ pub fn has_audio(e: &feed_rs::model::Entry) -> bool {
 pub fn entry_to_episode(
     podcast_id: &str,
     entry: &feed_rs::model::Entry,
+    extensions: &ItemExtensions,
     now: chrono::DateTime<chrono::Utc>,
 ) -> anyhow::Result<Episode> {
     let media = entry.media.iter().find(|m| {
         m.content.iter().any(|c| {
             c.content_type
                 .as_ref()
-                .map(|mt| is_audio_mime(mt))
+                .map(is_audio_mime)
                 .unwrap_or(false)
         })
     });
@@ -127,7 +511,7 @@ pub fn entry_to_episode(
             .find(|c| {
                 c.content_type
                     .as_ref()
-                    .map(|mt| is_audio_mime(mt))
+                    .map(is_audio_mime)
                     .unwrap_or(false)
             })
             .expect("audio content is required");
@@ -186,8 +570,165 @@ pub fn entry_to_episode(
 
             created_at: now,
             last_updated: now,
+            deleted_at: None,
+
+            season: extensions.season(),
+            episode_number: extensions.episode_number(),
+            episode_type: extensions.itunes_episode_type.clone(),
+            chapters_url: extensions.chapters_url.clone(),
+            chapters_type: extensions.chapters_type.clone(),
         })
     } else {
         anyhow::bail!("no audio content found");
     }
 }
+
+/// Whether any feed-derived field differs between a stored episode and one
+/// freshly built from the feed. Bookkeeping fields (`created_at`,
+/// `last_updated`, `deleted_at`) are ignored.
+pub fn episode_metadata_changed(stored: &Episode, fresh: &Episode) -> bool {
+    stored.title != fresh.title
+        || stored.summary != fresh.summary
+        || stored.summary_type != fresh.summary_type
+        || stored.content_encoded != fresh.content_encoded
+        || stored.content_encoded_type != fresh.content_encoded_type
+        || stored.publication_date != fresh.publication_date
+        || stored.audio_url != fresh.audio_url
+        || stored.audio_type != fresh.audio_type
+        || stored.audio_duration != fresh.audio_duration
+        || stored.thumbnail_url != fresh.thumbnail_url
+        || stored.season != fresh.season
+        || stored.episode_number != fresh.episode_number
+        || stored.episode_type != fresh.episode_type
+        || stored.chapters_url != fresh.chapters_url
+        || stored.chapters_type != fresh.chapters_type
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_control_max_age() {
+        assert_eq!(
+            parse_cache_control_max_age("public, Max-Age=3600"),
+            Some(chrono::Duration::hours(1))
+        );
+        assert_eq!(parse_cache_control_max_age("no-cache"), None);
+        assert_eq!(
+            parse_cache_control_max_age("max-age=10000000000000000"),
+            Some(chrono::Duration::days(365))
+        );
+    }
+
+    #[test]
+    fn retry_after_seconds_and_date() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(chrono::Duration::seconds(120))
+        );
+        assert_eq!(
+            parse_retry_after("Mon, 01 Jan 2024 01:00:00 GMT", now),
+            Some(chrono::Duration::hours(1))
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(
+            parse_retry_after("9223372036854775807", now),
+            Some(chrono::Duration::days(365))
+        );
+        assert_eq!(
+            parse_retry_after("Fri, 31 Dec 9999 23:59:59 GMT", now),
+            Some(chrono::Duration::days(365))
+        );
+    }
+
+    #[test]
+    fn syndication_period_and_frequency() {
+        let xml = r#"<rss xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+<channel>
+  <sy:updatePeriod>daily</sy:updatePeriod>
+  <sy:updateFrequency>2</sy:updateFrequency>
+  <item><title>ignored</title></item>
+</channel>
+</rss>"#;
+        assert_eq!(syndication_interval(xml), Some(chrono::Duration::hours(12)));
+        assert_eq!(syndication_interval("<rss><channel/></rss>"), None);
+    }
+
+    #[test]
+    fn podcasting20_and_itunes_extensions() {
+        let xml = r#"<rss version="2.0"
+     xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"
+     xmlns:podcast="https://podcastindex.org/namespace/1.0">
+<channel>
+  <title>Show</title>
+  <podcast:guid>917393e3-1b1e-5cef-ace4-edaa54e1f810</podcast:guid>
+  <podcast:funding url="https://example.com/donate">Support &amp; thanks</podcast:funding>
+  <item>
+    <title>Trailer</title>
+    <itunes:episodeType>Trailer</itunes:episodeType>
+  </item>
+  <item>
+    <title>Five</title>
+    <itunes:season>2</itunes:season>
+    <itunes:episode>5</itunes:episode>
+    <podcast:episode>5.5</podcast:episode>
+    <podcast:chapters url="https://example.com/5.json" type="application/json+chapters"/>
+    <podcast:transcript url="https://example.com/5.vtt" type="text/vtt" language="en"/>
+    <podcast:transcript type="text/srt"/>
+    <podcast:person role="Guest" img="https://example.com/a.jpg">Ada</podcast:person>
+    <podcast:person>Host Name</podcast:person>
+  </item>
+  <item>
+    <title>Six</title>
+    <podcast:season name="Finale">3</podcast:season>
+    <podcast:episode display="Six">6</podcast:episode>
+  </item>
+</channel>
+</rss>"#;
+        let ext = parse_extensions(xml.as_bytes());
+
+        assert_eq!(
+            ext.podcast_guid.as_deref(),
+            Some("917393e3-1b1e-5cef-ace4-edaa54e1f810")
+        );
+        assert_eq!(
+            ext.funding,
+            vec![Funding {
+                url: "https://example.com/donate".into(),
+                message: "Support & thanks".into(),
+            }]
+        );
+        assert_eq!(ext.items.len(), 3);
+
+        assert_eq!(ext.items[0].itunes_episode_type.as_deref(), Some("trailer"));
+
+        let five = &ext.items[1];
+        assert_eq!((five.season(), five.episode_number()), (Some(2), Some(5)));
+        assert_eq!(five.podcast_episode, None);
+        assert_eq!(five.chapters_url.as_deref(), Some("https://example.com/5.json"));
+        assert_eq!(
+            five.transcripts,
+            vec![Transcript {
+                url: "https://example.com/5.vtt".into(),
+                mime_type: "text/vtt".into(),
+                language: Some("en".into()),
+                rel: None,
+            }]
+        );
+        assert_eq!(five.persons.len(), 2);
+        assert_eq!(five.persons[0].name, "Ada");
+        assert_eq!(five.persons[0].role, "guest");
+        assert_eq!(five.persons[1].role, "host");
+        assert_eq!(five.persons[1].group, "cast");
+
+        let six = &ext.items[2];
+        assert_eq!((six.season(), six.episode_number()), (Some(3), Some(6)));
+
+        let (feed, parsed) = parse_feed(xml.as_bytes()).unwrap();
+        assert_eq!(feed.entries.len(), parsed.items.len());
+    }
+}
@@ -0,0 +1,39 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use pod_model::DirectoryPodcast;
+
+use crate::http::{
+    auth::ApiUser,
+    errors::{AppError, JsonAppError},
+    AppState,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/search", get(search))
+}
+
+#[derive(Deserialize)]
+struct DirectorySearchParams {
+    q: String,
+    limit: Option<u32>,
+}
+
+/// Search the podcast directory for shows to subscribe to.
+async fn search(
+    _user: ApiUser,
+    State(state): State<AppState>,
+    Query(params): Query<DirectorySearchParams>,
+) -> Result<Json<Vec<DirectoryPodcast>>, JsonAppError> {
+    let term = params.q.trim();
+    if term.is_empty() {
+        return Err(AppError::BadRequest("q must not be empty".to_string()).into());
+    }
+    let limit = params.limit.unwrap_or(25).clamp(1, 200);
+    let results = state.app.search_directory(term, limit).await?;
+    Ok(Json(results))
+}
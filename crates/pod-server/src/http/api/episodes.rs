@@ -7,7 +7,7 @@ use base64::prelude::*;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use pod_model::{InboxEpisode, ProgressState};
+use pod_model::{Chapter, EpisodeDetail, InboxEpisode, ProgressState, TranscriptSegment};
 
 use crate::{
     app::CursorPagination,
@@ -21,9 +21,53 @@ use crate::{
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/inbox", get(inbox))
+        .route("/:id", get(get_episode))
+        .route("/:id/chapters", get(get_chapters))
+        .route("/:id/transcript", get(get_transcript))
         .route("/:id/progress", post(report_progress))
 }
 
+async fn get_episode(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<EpisodeDetail>, JsonAppError> {
+    let episode = state
+        .app
+        .get_episode_detail(&user.username, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("episode".to_string(), id))?;
+    Ok(Json(episode))
+}
+
+async fn get_chapters(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Chapter>>, JsonAppError> {
+    let chapters = state.app.get_episode_chapters(&user.username, &id).await?;
+    Ok(Json(chapters))
+}
+
+#[derive(Deserialize)]
+struct TranscriptParams {
+    q: Option<String>,
+}
+
+async fn get_transcript(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<TranscriptParams>,
+) -> Result<Json<Vec<TranscriptSegment>>, JsonAppError> {
+    let query = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let segments = state
+        .app
+        .get_episode_transcript(&user.username, &id, query)
+        .await?;
+    Ok(Json(segments))
+}
+
 #[derive(Deserialize)]
 struct ProgressReport {
     progress: i32,
@@ -3,14 +3,22 @@ use axum::Router;
 use crate::http::AppState;
 
 mod auth;
+mod directory;
 mod episodes;
+mod playlists;
 mod podcasts;
+mod queue;
+mod search;
 mod sync;
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .nest("/auth", auth::router())
         .nest("/podcasts", podcasts::router())
+        .nest("/directory", directory::router())
         .nest("/episodes", episodes::router())
+        .nest("/playlists", playlists::router())
+        .nest("/queue", queue::router())
+        .nest("/search", search::router())
         .nest("/sync", sync::router())
 }
@@ -0,0 +1,153 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use pod_model::{InboxEpisode, Playlist, PlaylistFilter};
+
+use crate::{
+    app::CursorPagination,
+    http::{
+        auth::ApiUser,
+        errors::{AppError, JsonAppError},
+        AppState,
+    },
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_playlists).post(create_playlist))
+        .route(
+            "/:id",
+            get(get_playlist).put(update_playlist).delete(delete_playlist),
+        )
+        .route("/:id/episodes", get(playlist_episodes))
+}
+
+#[derive(Deserialize)]
+struct PlaylistRequest {
+    name: String,
+    #[serde(default)]
+    filter: PlaylistFilter,
+}
+
+async fn list_playlists(
+    user: ApiUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Playlist>>, JsonAppError> {
+    let playlists = state.app.list_playlists(&user.username).await?;
+    Ok(Json(playlists))
+}
+
+async fn create_playlist(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Json(req): Json<PlaylistRequest>,
+) -> Result<(StatusCode, Json<Playlist>), JsonAppError> {
+    let playlist = state
+        .app
+        .create_playlist(&user.username, &req.name, &req.filter)
+        .await?;
+    Ok((StatusCode::CREATED, Json(playlist)))
+}
+
+async fn get_playlist(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Playlist>, JsonAppError> {
+    let playlist = state.app.get_playlist(&user.username, &id).await?;
+    Ok(Json(playlist))
+}
+
+async fn update_playlist(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<PlaylistRequest>,
+) -> Result<Json<Playlist>, JsonAppError> {
+    let playlist = state
+        .app
+        .update_playlist(&user.username, &id, &req.name, &req.filter)
+        .await?;
+    Ok(Json(playlist))
+}
+
+async fn delete_playlist(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, JsonAppError> {
+    state.app.delete_playlist(&user.username, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ==============================================================================
+// Playlist episodes — paginated like the inbox
+// ==============================================================================
+
+#[derive(Deserialize)]
+struct PageParams {
+    per_page: Option<u32>,
+    page_token: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PlaylistPage {
+    items: Vec<InboxEpisode>,
+    next_page_token: Option<String>,
+}
+
+async fn playlist_episodes(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<PageParams>,
+) -> Result<Json<PlaylistPage>, JsonAppError> {
+    let per_page = params.per_page.unwrap_or(30).clamp(1, 100) as i64;
+    let cursor = match &params.page_token {
+        Some(token) => Some(decode_page_token(token)?),
+        None => None,
+    };
+
+    let episodes = state
+        .app
+        .get_playlist_episodes(&user.username, &id, CursorPagination { limit: per_page, cursor })
+        .await?;
+
+    let next_page_token = if episodes.len() as i64 == per_page {
+        episodes
+            .last()
+            .map(|ep| encode_page_token(ep.episode.publication_date, &ep.episode.id))
+    } else {
+        None
+    };
+
+    Ok(Json(PlaylistPage {
+        items: episodes,
+        next_page_token,
+    }))
+}
+
+/// Decode a compound page token of the form `{rfc3339}\n{episode_id}`, base64-encoded.
+fn decode_page_token(token: &str) -> Result<(DateTime<Utc>, String), JsonAppError> {
+    let decoded = BASE64_STANDARD
+        .decode(token.as_bytes())
+        .map_err(|_| AppError::OptionError)?;
+    let s = String::from_utf8(decoded).map_err(|_| AppError::OptionError)?;
+    let (date_str, id) = s.split_once('\n').ok_or(AppError::OptionError)?;
+    let dt = DateTime::parse_from_rfc3339(date_str)
+        .map_err(|_| AppError::OptionError)?
+        .with_timezone(&Utc);
+    Ok((dt, id.to_string()))
+}
+
+fn encode_page_token(dt: DateTime<Utc>, id: &str) -> String {
+    let payload = format!("{}\n{}", dt.to_rfc3339(), id);
+    BASE64_STANDARD.encode(payload.as_bytes())
+}
@@ -1,6 +1,8 @@
 use axum::{
     extract::{Path, Query, State},
-    routing::get,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
     Json, Router,
 };
 use base64::prelude::*;
@@ -8,10 +10,14 @@ use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde::Serialize;
 
-use pod_model::{EpisodeWithProgress, Podcast, PodcastWithEpisodeStats};
+use pod_model::{
+    EpisodeWithProgress, OpmlImportReport, Podcast, PodcastDetail, PodcastWithEpisodeStats,
+    SubscriptionSettings, SubscriptionSettingsPatch,
+};
 
 use crate::{
     app::CursorPagination,
+    opml::{self, OpmlFeed},
     http::{
         auth::ApiUser,
         errors::{AppError, JsonAppError},
@@ -22,8 +28,11 @@ use crate::{
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/", get(list_podcasts).post(add_podcast))
-        .route("/:id", get(get_podcast))
+        .route("/import", post(import_opml))
+        .route("/export.opml", get(export_opml))
+        .route("/:id", get(get_podcast).delete(unsubscribe))
         .route("/:id/episodes", get(list_episodes))
+        .route("/:id/settings", get(get_settings).patch(update_settings))
 }
 
 async fn list_podcasts(
@@ -38,10 +47,10 @@ async fn get_podcast(
     user: ApiUser,
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<Podcast>, JsonAppError> {
+) -> Result<Json<PodcastDetail>, JsonAppError> {
     let podcast = state
         .app
-        .get_podcast_for_user(&user.username, &id)
+        .get_podcast_detail_for_user(&user.username, &id)
         .await?
         .ok_or_else(|| AppError::NotFound("podcast".to_string(), id))?;
     Ok(Json(podcast))
@@ -65,6 +74,82 @@ async fn add_podcast(
     Ok(Json(podcast))
 }
 
+/// Accepts an OPML document as the request body and subscribes to every
+/// feed in it.
+async fn import_opml(
+    user: ApiUser,
+    State(state): State<AppState>,
+    body: String,
+) -> Result<Json<OpmlImportReport>, JsonAppError> {
+    let feeds = opml::parse_feeds(&body)?;
+    if feeds.is_empty() {
+        return Err(
+            AppError::BadRequest("OPML document contains no feeds".to_string()).into(),
+        );
+    }
+    let report = state.app.import_opml(&user.username, feeds).await;
+    Ok(Json(report))
+}
+
+async fn export_opml(
+    user: ApiUser,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, JsonAppError> {
+    let podcasts = state.app.get_podcasts_for_user(&user.username).await?;
+    let feeds: Vec<OpmlFeed> = podcasts
+        .into_iter()
+        .map(|p| OpmlFeed {
+            feed_url: p.feed_url,
+            title: Some(p.title),
+        })
+        .collect();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/x-opml; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"pod-subscriptions.opml\"",
+            ),
+        ],
+        opml::render("pod subscriptions", &feeds),
+    ))
+}
+
+async fn unsubscribe(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, JsonAppError> {
+    state.app.unsubscribe_from_podcast(&user.username, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_settings(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SubscriptionSettings>, JsonAppError> {
+    let settings = state
+        .app
+        .get_subscription_settings(&user.username, &id)
+        .await?;
+    Ok(Json(settings))
+}
+
+async fn update_settings(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(patch): Json<SubscriptionSettingsPatch>,
+) -> Result<Json<SubscriptionSettings>, JsonAppError> {
+    let settings = state
+        .app
+        .update_subscription_settings(&user.username, &id, patch)
+        .await?;
+    Ok(Json(settings))
+}
+
 async fn list_episodes(
     user: ApiUser,
     State(state): State<AppState>,
@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use pod_model::InboxEpisode;
+
+use crate::http::{auth::ApiUser, errors::JsonAppError, AppState};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            get(get_queue)
+                .post(enqueue)
+                .put(replace_queue)
+                .delete(clear_queue),
+        )
+        .route("/:episode_id", delete(dequeue))
+        .route("/:episode_id/front", post(move_to_front))
+}
+
+async fn get_queue(
+    user: ApiUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<InboxEpisode>>, JsonAppError> {
+    let queue = state.app.get_queue(&user.username).await?;
+    Ok(Json(queue))
+}
+
+#[derive(Deserialize)]
+struct EnqueueRequest {
+    episode_id: String,
+}
+
+/// Append an episode to the queue. Episodes already queued keep their place.
+async fn enqueue(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Json(req): Json<EnqueueRequest>,
+) -> Result<Json<Vec<InboxEpisode>>, JsonAppError> {
+    let queue = state
+        .app
+        .enqueue_episode(&user.username, &req.episode_id, false)
+        .await?;
+    Ok(Json(queue))
+}
+
+/// Queue an episode to play next, moving it up if it is already queued.
+async fn move_to_front(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(episode_id): Path<String>,
+) -> Result<Json<Vec<InboxEpisode>>, JsonAppError> {
+    let queue = state
+        .app
+        .enqueue_episode(&user.username, &episode_id, true)
+        .await?;
+    Ok(Json(queue))
+}
+
+#[derive(Deserialize)]
+struct ReplaceQueueRequest {
+    episode_ids: Vec<String>,
+}
+
+/// Replace the whole queue, e.g. to reorder it.
+async fn replace_queue(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Json(req): Json<ReplaceQueueRequest>,
+) -> Result<Json<Vec<InboxEpisode>>, JsonAppError> {
+    let queue = state
+        .app
+        .replace_queue(&user.username, &req.episode_ids)
+        .await?;
+    Ok(Json(queue))
+}
+
+async fn dequeue(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Path(episode_id): Path<String>,
+) -> Result<Json<Vec<InboxEpisode>>, JsonAppError> {
+    let queue = state.app.dequeue_episode(&user.username, &episode_id).await?;
+    Ok(Json(queue))
+}
+
+async fn clear_queue(
+    user: ApiUser,
+    State(state): State<AppState>,
+) -> Result<StatusCode, JsonAppError> {
+    state.app.clear_queue(&user.username).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use pod_model::SearchResult;
+
+use crate::{
+    db::SearchCursor,
+    http::{
+        auth::ApiUser,
+        errors::{AppError, JsonAppError},
+        AppState,
+    },
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(search))
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    q: String,
+    per_page: Option<u32>,
+    page_token: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SearchPage {
+    items: Vec<SearchResult>,
+    next_page_token: Option<String>,
+}
+
+/// Full-text search over the episodes of the user's subscriptions.
+async fn search(
+    user: ApiUser,
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<SearchPage>, JsonAppError> {
+    let query = params.q.trim();
+    if query.is_empty() {
+        return Err(AppError::BadRequest("q must not be empty".to_string()).into());
+    }
+    let per_page = params.per_page.unwrap_or(30).clamp(1, 100) as i64;
+    let cursor = match &params.page_token {
+        Some(token) => Some(decode_page_token(token)?),
+        None => None,
+    };
+
+    let results = state
+        .app
+        .search_episodes(&user.username, query, per_page, cursor)
+        .await?;
+
+    let next_page_token = if results.len() as i64 == per_page {
+        results
+            .last()
+            .map(|r| encode_page_token(r.rank, &r.episode.episode.id))
+    } else {
+        None
+    };
+
+    Ok(Json(SearchPage {
+        items: results,
+        next_page_token,
+    }))
+}
+
+/// Decode a compound page token of the form `{rank}\n{episode_id}`, base64-encoded.
+fn decode_page_token(token: &str) -> Result<SearchCursor, JsonAppError> {
+    let invalid = || AppError::BadRequest("invalid page_token".to_string());
+    let decoded = BASE64_STANDARD
+        .decode(token.as_bytes())
+        .map_err(|_| invalid())?;
+    let s = String::from_utf8(decoded).map_err(|_| invalid())?;
+    let (rank, id) = s.split_once('\n').ok_or_else(invalid)?;
+    let rank = rank.parse().map_err(|_| invalid())?;
+    Ok((rank, id.to_string()))
+}
+
+/// `f32`'s `Display` output is the shortest string that parses back to the
+/// same value, so the cursor compares equal to the rank it came from.
+fn encode_page_token(rank: f32, id: &str) -> String {
+    let payload = format!("{}\n{}", rank, id);
+    BASE64_STANDARD.encode(payload.as_bytes())
+}
@@ -50,6 +50,8 @@ struct SyncParams {
     limit: Option<i64>,
 }
 
+/// Returns changes after `since`. Responds 410 Gone when the cursor predates
+/// the compacted change log, in which case the client must re-bootstrap.
 async fn sync_changes(
     user: ApiUser,
     State(state): State<AppState>,
@@ -81,9 +83,6 @@ async fn sync_changes(
         }
     }
 
-    // TODO: return 410 Gone when the cursor is too old (expired / pruned
-    // from the change log) so the client knows to perform a full resync.
-
     let response = state
         .app
         .get_sync_changes(&user.username, since_seq, limit)
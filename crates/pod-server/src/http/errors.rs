@@ -3,7 +3,10 @@ use axum::response::IntoResponse;
 use axum::Json;
 use thiserror::Error;
 
+use crate::directory::DirectoryError;
 use crate::feed::GetFeedError;
+use crate::opml::OpmlError;
+use crate::websub::WebSubError;
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -25,6 +28,14 @@ pub enum AppError {
     NotFound(String, String),
     #[error("Bad request: {0}")]
     BadRequest(String),
+    #[error("Gone: {0}")]
+    Gone(String),
+    #[error("invalid OPML: {0}")]
+    Opml(#[from] OpmlError),
+    #[error("websub error: {0}")]
+    WebSub(#[from] WebSubError),
+    #[error("podcast directory error: {0}")]
+    Directory(#[from] DirectoryError),
 }
 
 impl AppError {
@@ -33,6 +44,9 @@ impl AppError {
             Self::Unauthorized => StatusCode::UNAUTHORIZED,
             Self::NotFound(_, _) => StatusCode::NOT_FOUND,
             Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Gone(_) => StatusCode::GONE,
+            Self::Opml(_) => StatusCode::BAD_REQUEST,
+            Self::Directory(_) => StatusCode::BAD_GATEWAY,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -8,6 +8,7 @@ pub mod api;
 
 pub mod auth;
 pub mod errors;
+pub mod websub;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -0,0 +1,63 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::http::{errors::JsonAppError, AppState};
+
+/// WebSub callback routes. Hubs call these directly, so they live outside
+/// the authenticated JSON API.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/:podcast_id", get(verify_intent).post(receive_push))
+}
+
+#[derive(Deserialize)]
+struct VerificationParams {
+    #[serde(rename = "hub.mode")]
+    mode: String,
+    #[serde(rename = "hub.topic")]
+    topic: String,
+    #[serde(rename = "hub.challenge")]
+    challenge: Option<String>,
+    #[serde(rename = "hub.lease_seconds")]
+    lease_seconds: Option<i64>,
+}
+
+/// Intent verification: echo the challenge for requests we made, 404
+/// otherwise.
+async fn verify_intent(
+    State(state): State<AppState>,
+    Path(podcast_id): Path<String>,
+    Query(params): Query<VerificationParams>,
+) -> Result<impl IntoResponse, JsonAppError> {
+    let confirmed = state
+        .app
+        .verify_websub_intent(&podcast_id, &params.mode, &params.topic, params.lease_seconds)
+        .await?;
+
+    if !confirmed {
+        return Ok((StatusCode::NOT_FOUND, String::new()));
+    }
+    Ok((StatusCode::OK, params.challenge.unwrap_or_default()))
+}
+
+async fn receive_push(
+    State(state): State<AppState>,
+    Path(podcast_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, JsonAppError> {
+    let signature = headers
+        .get("x-hub-signature")
+        .and_then(|v| v.to_str().ok());
+    state
+        .app
+        .handle_websub_push(&podcast_id, signature, &body)
+        .await?;
+    Ok(StatusCode::ACCEPTED)
+}
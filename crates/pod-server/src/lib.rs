@@ -1,8 +1,13 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 pub mod app;
+pub mod chapters;
 pub mod config;
 pub mod db;
+pub mod directory;
 pub mod feed;
 pub mod http;
 pub mod model;
+pub mod opml;
+pub mod transcripts;
+pub mod websub;
@@ -9,7 +9,13 @@ use axum::{
     Router,
 };
 use dotenv::dotenv;
-use pod_server::{app::App, config::Config, db::Db, http::AppState};
+use pod_server::{
+    app::{App, RefreshPolicy},
+    config::Config,
+    db::Db,
+    directory::ITunesCatalog,
+    http::AppState,
+};
 use reqwest::Client as ReqwestClient;
 use sqlx::PgPool;
 use tower_http::cors::{AllowOrigin, CorsLayer};
@@ -20,6 +26,9 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use pod_server::http::api;
 
+/// Upper bound on how often the refresh scheduler checks for due podcasts.
+const SCHEDULER_TICK_SECS: u64 = 60;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
@@ -39,7 +48,21 @@ async fn main() -> Result<()> {
     let db = PgPool::connect(&config.database_url).await?;
     let db: Arc<Db> = pod_server::db::Db::init(db).await?.into();
 
-    let app = Arc::new(App::new(db.clone(), http.clone()));
+    let refresh = RefreshPolicy {
+        default_interval: chrono::Duration::seconds(config.refresh_interval_secs as i64),
+        min_interval: chrono::Duration::seconds(config.refresh_min_interval_secs as i64),
+        max_interval: chrono::Duration::seconds(config.refresh_max_interval_secs as i64),
+    };
+    if config.base_url.is_none() {
+        info!("BASE_URL not set, websub push subscriptions disabled");
+    }
+    let app = Arc::new(App::new(
+        db.clone(),
+        http.clone(),
+        refresh,
+        config.base_url.clone(),
+        Arc::new(ITunesCatalog::new(http.clone(), &config.catalog_base_url)),
+    ));
     let state = AppState {
         db: db.clone(),
         http: http.clone(),
@@ -50,32 +73,55 @@ async fn main() -> Result<()> {
     let cors = CorsLayer::new()
         // Mirror the request origin so browser clients can call from their own host.
         .allow_origin(AllowOrigin::mirror_request())
-        .allow_methods([Method::GET, Method::POST])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
         .allow_headers([AUTHORIZATION, CONTENT_TYPE])
         // Allow credentials for authenticated calls.
         .allow_credentials(true);
 
     let router = Router::new()
         .nest("/api/v1", api::router().layer(cors))
+        .nest("/websub", pod_server::http::websub::router())
         .fallback_service(ServeDir::new("frontend"))
         .layer(TraceLayer::new_for_http())
         .with_state(state);
 
-    let refresh_interval_secs = config.refresh_interval_secs;
+    let compaction_app = app.clone();
+    // Each podcast carries its own next_fetch_at; the scheduler only needs
+    // to wake often enough to pick up the ones that are due.
+    let scheduler_tick_secs = SCHEDULER_TICK_SECS
+        .min(config.refresh_min_interval_secs)
+        .max(1);
     let jh = tokio::spawn(async move {
         let app = app.clone();
 
         let mut interval =
-            tokio::time::interval(std::time::Duration::from_secs(refresh_interval_secs));
+            tokio::time::interval(std::time::Duration::from_secs(scheduler_tick_secs));
         loop {
             match app.refresh_all_podcasts().await {
                 Ok(_) => {}
                 Err(e) => warn!("error refreshing podcasts: {:?}", e),
             }
+            if let Err(e) = app.renew_websub_leases().await {
+                warn!("error renewing websub leases: {:?}", e);
+            }
             interval.tick().await;
         }
     });
 
+    let retention = chrono::TimeDelta::try_days(config.change_log_retention_days as i64)
+        .unwrap_or(chrono::TimeDelta::MAX);
+    let compaction_interval_secs = config.compaction_interval_secs;
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(compaction_interval_secs));
+        loop {
+            interval.tick().await;
+            if let Err(e) = compaction_app.compact_change_log(retention).await {
+                warn!("error compacting change log: {:?}", e);
+            }
+        }
+    });
+
     let bind_addr = format!("0.0.0.0:{}", config.port);
     let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
     info!("listening on {}", listener.local_addr()?);
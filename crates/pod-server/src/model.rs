@@ -21,6 +21,34 @@ pub struct UserSubscription {
     pub last_updated: chrono::DateTime<chrono::Utc>,
 }
 
+/// The settings columns of a `user_subscription` row.
+pub struct SubscriptionSettingsRow {
+    pub podcast_id: String,
+    pub playback_speed: f32,
+    pub skip_intro_secs: i32,
+    pub skip_outro_secs: i32,
+    pub auto_download: i32,
+    pub hide_from_inbox: bool,
+    pub notifications: bool,
+    pub title_override: Option<String>,
+    pub state: String,
+}
+
+impl From<SubscriptionSettingsRow> for pod_model::SubscriptionSettings {
+    fn from(row: SubscriptionSettingsRow) -> Self {
+        Self {
+            playback_speed: row.playback_speed,
+            skip_intro_secs: row.skip_intro_secs,
+            skip_outro_secs: row.skip_outro_secs,
+            auto_download: row.auto_download,
+            hide_from_inbox: row.hide_from_inbox,
+            notifications: row.notifications,
+            title_override: row.title_override,
+            state: pod_model::SubscriptionState::parse(&row.state).unwrap_or_default(),
+        }
+    }
+}
+
 pub struct UserEpisode {
     pub id: String,
     pub user_id: uuid::Uuid,
@@ -32,12 +60,75 @@ pub struct UserEpisode {
     pub progress: i32,
 }
 
-/// A row from the episode_change table, joined with episode data for the
-/// sync response.
+/// A saved playlist as stored, with its filter spread over columns.
+pub struct PlaylistRow {
+    pub id: String,
+    pub name: String,
+    pub podcast_ids: Vec<String>,
+    pub min_duration_secs: Option<i32>,
+    pub max_duration_secs: Option<i32>,
+    pub progress: String,
+    pub published_within_days: Option<i32>,
+}
+
+impl From<PlaylistRow> for pod_model::Playlist {
+    fn from(row: PlaylistRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            filter: pod_model::PlaylistFilter {
+                podcast_ids: row.podcast_ids,
+                min_duration_secs: row.min_duration_secs,
+                max_duration_secs: row.max_duration_secs,
+                progress: pod_model::ProgressFilter::parse(&row.progress).unwrap_or_default(),
+                published_within_days: row.published_within_days,
+            },
+        }
+    }
+}
+
+/// A row from the merged episode_change / subscription_change logs, used to
+/// build the sync response. `episode_id` is only set for episode changes.
 #[derive(Debug)]
-pub struct EpisodeChangeRow {
+pub struct SyncChangeRow {
     pub seq: i64,
+    pub change_type: String,
     pub podcast_id: String,
-    pub episode_id: String,
+    pub episode_id: Option<String>,
     pub op: String,
+    pub changed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A WebSub subscription to a podcast's hub. `state` is `pending` until the
+/// hub verifies intent, then `active` (or `denied`).
+#[derive(Debug)]
+pub struct WebSubSubscription {
+    pub podcast_id: String,
+    pub hub_url: String,
+    pub topic_url: String,
+    pub secret: String,
+    pub state: String,
+    pub lease_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub requested_at: chrono::DateTime<chrono::Utc>,
+    pub last_push_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Where an episode's cached chapters came from. `error` is set when the
+/// last fetch failed, in which case no chapters are stored.
+#[derive(Debug)]
+pub struct ChapterSource {
+    pub episode_id: String,
+    pub source_url: String,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    pub error: Option<String>,
+}
+
+/// Where an episode's cached transcript segments came from, with the same
+/// semantics as [`ChapterSource`].
+#[derive(Debug)]
+pub struct TranscriptSource {
+    pub episode_id: String,
+    pub source_url: String,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    pub error: Option<String>,
 }
@@ -0,0 +1,152 @@
+use quick_xml::{escape::escape, events::Event, Reader};
+use thiserror::Error;
+
+/// A feed outline from an OPML document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpmlFeed {
+    pub feed_url: String,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum OpmlError {
+    #[error("xml error: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("xml attribute error: {0}")]
+    Attr(#[from] quick_xml::events::attributes::AttrError),
+    #[error("document is not OPML")]
+    NotOpml,
+}
+
+/// Extract every feed outline (an `<outline>` with an `xmlUrl` attribute)
+/// from an OPML document. Category outlines are walked but not returned.
+pub fn parse_feeds(doc: &str) -> Result<Vec<OpmlFeed>, OpmlError> {
+    let mut reader = Reader::from_str(doc);
+    let mut feeds = Vec::new();
+    let mut seen_root = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => {
+                let name = e.local_name();
+                if name.as_ref().eq_ignore_ascii_case(b"opml") {
+                    seen_root = true;
+                    continue;
+                }
+                if !name.as_ref().eq_ignore_ascii_case(b"outline") {
+                    continue;
+                }
+
+                let mut feed_url = None;
+                let mut title = None;
+                let mut text = None;
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    let value = attr.decode_and_unescape_value(&reader)?.trim().to_string();
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let key = attr.key.local_name();
+                    // Exporters disagree on attribute casing (`xmlUrl` vs
+                    // `xmlurl`), so match case-insensitively.
+                    if key.as_ref().eq_ignore_ascii_case(b"xmlUrl") {
+                        feed_url = Some(value);
+                    } else if key.as_ref().eq_ignore_ascii_case(b"title") {
+                        title = Some(value);
+                    } else if key.as_ref().eq_ignore_ascii_case(b"text") {
+                        text = Some(value);
+                    }
+                }
+
+                if let Some(feed_url) = feed_url {
+                    feeds.push(OpmlFeed {
+                        feed_url,
+                        title: title.or(text),
+                    });
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !seen_root {
+        return Err(OpmlError::NotOpml);
+    }
+    Ok(feeds)
+}
+
+/// Render an OPML 2.0 document listing the given feeds.
+pub fn render(title: &str, feeds: &[OpmlFeed]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<opml version=\"2.0\">\n");
+    out.push_str("  <head>\n");
+    out.push_str(&format!("    <title>{}</title>\n", escape(title)));
+    out.push_str("  </head>\n");
+    out.push_str("  <body>\n");
+    for feed in feeds {
+        let text = escape(feed.title.as_deref().unwrap_or(&feed.feed_url)).into_owned();
+        out.push_str(&format!(
+            "    <outline type=\"rss\" text=\"{}\" title=\"{}\" xmlUrl=\"{}\"/>\n",
+            text,
+            text,
+            escape(&feed.feed_url)
+        ));
+    }
+    out.push_str("  </body>\n");
+    out.push_str("</opml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nested_outlines() {
+        let doc = r#"<?xml version="1.0"?>
+<opml version="1.0">
+  <head><title>Subs</title></head>
+  <body>
+    <outline text="Tech">
+      <outline type="rss" text="Show &amp; Tell" xmlUrl="https://example.com/a.xml"/>
+    </outline>
+    <outline type="rss" title="B" text="ignored" xmlurl=" https://example.com/b.xml "/>
+  </body>
+</opml>"#;
+
+        let feeds = parse_feeds(doc).unwrap();
+        assert_eq!(
+            feeds,
+            vec![
+                OpmlFeed {
+                    feed_url: "https://example.com/a.xml".into(),
+                    title: Some("Show & Tell".into()),
+                },
+                OpmlFeed {
+                    feed_url: "https://example.com/b.xml".into(),
+                    title: Some("B".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_non_opml() {
+        assert!(matches!(
+            parse_feeds("<rss><channel/></rss>"),
+            Err(OpmlError::NotOpml)
+        ));
+    }
+
+    #[test]
+    fn render_round_trips() {
+        let feeds = vec![OpmlFeed {
+            feed_url: "https://example.com/feed?a=1&b=2".into(),
+            title: Some("Q&A \"Live\"".into()),
+        }];
+        let doc = render("pod subscriptions", &feeds);
+        assert_eq!(parse_feeds(&doc).unwrap(), feeds);
+    }
+}
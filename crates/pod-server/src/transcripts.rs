@@ -0,0 +1,409 @@
+use pod_model::{Transcript, TranscriptSegment};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TranscriptError {
+    #[error("reqwest error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("transcript server returned {0}")]
+    Status(reqwest::StatusCode),
+    #[error("invalid transcript JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Transcript formats that can be normalised into segments, in order of
+/// preference: JSON and the caption formats carry timings for every
+/// segment, HTML usually only for some.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Format {
+    Json,
+    Vtt,
+    Srt,
+    Html,
+}
+
+impl Format {
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        let essence = mime_type.split(';').next().unwrap_or("").trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "application/json" => Some(Self::Json),
+            "text/vtt" => Some(Self::Vtt),
+            "application/x-subrip" | "application/srt" | "text/srt" | "text/x-subrip" => {
+                Some(Self::Srt)
+            }
+            "text/html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+/// The transcript to ingest out of an episode's `podcast:transcript` links:
+/// the first one in the most preferred supported format.
+pub fn preferred(transcripts: &[Transcript]) -> Option<(&Transcript, Format)> {
+    transcripts
+        .iter()
+        .filter_map(|t| Format::from_mime_type(&t.mime_type).map(|f| (t, f)))
+        .min_by_key(|(_, format)| *format)
+}
+
+/// Fetch a transcript and normalise it into segments ordered by start time.
+pub async fn fetch(
+    http: &reqwest::Client,
+    url: &str,
+    format: Format,
+) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let res = http.get(url).send().await?;
+    if !res.status().is_success() {
+        return Err(TranscriptError::Status(res.status()));
+    }
+    parse(format, &res.text().await?)
+}
+
+pub fn parse(format: Format, text: &str) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let mut segments = match format {
+        Format::Json => parse_json(text)?,
+        Format::Vtt | Format::Srt => parse_cues(text),
+        Format::Html => parse_html(text),
+    };
+    segments.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    Ok(segments)
+}
+
+/// A Podcast Index JSON transcript (`application/json`).
+#[derive(Deserialize)]
+struct JsonTranscript {
+    segments: Vec<JsonSegment>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonSegment {
+    start_time: f64,
+    end_time: Option<f64>,
+    speaker: Option<String>,
+    #[serde(default)]
+    body: String,
+}
+
+/// JSON transcripts are often word-by-word. Consecutive segments from the
+/// same speaker are joined until a sentence ends, so each segment reads
+/// like a caption.
+fn parse_json(text: &str) -> Result<Vec<TranscriptSegment>, TranscriptError> {
+    let doc: JsonTranscript = serde_json::from_str(text)?;
+    let mut segments: Vec<TranscriptSegment> = Vec::new();
+    for s in doc.segments {
+        let body = collapse_whitespace(&s.body);
+        if body.is_empty() {
+            continue;
+        }
+        let speaker = s.speaker.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        if let Some(last) = segments.last_mut() {
+            if last.speaker == speaker && !ends_sentence(&last.body) {
+                last.body.push(' ');
+                last.body.push_str(&body);
+                last.end_time = s.end_time.or(last.end_time);
+                continue;
+            }
+        }
+        segments.push(TranscriptSegment {
+            start_time: s.start_time,
+            end_time: s.end_time,
+            speaker,
+            body,
+        });
+    }
+    Ok(segments)
+}
+
+fn ends_sentence(body: &str) -> bool {
+    body.ends_with(['.', '!', '?', '…'])
+}
+
+/// Parse SubRip or WebVTT cues. Both are blank-line separated blocks with a
+/// `start --> end` timing line followed by the cue text; anything without
+/// a timing line (the WebVTT header, NOTE and STYLE blocks) is skipped.
+fn parse_cues(text: &str) -> Vec<TranscriptSegment> {
+    let text = text.replace("\r\n", "\n");
+    let mut segments = Vec::new();
+    for block in text.split("\n\n") {
+        let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+        let Some((start, end)) = lines.next().and_then(|l| l.split_once("-->")) else {
+            continue;
+        };
+        let Some(start_time) = parse_timestamp(start.trim()) else {
+            continue;
+        };
+        // WebVTT cue settings may follow the end time.
+        let end_time = end.split_whitespace().next().and_then(parse_timestamp);
+
+        let (speaker, body) = cue_text(&lines.collect::<Vec<_>>().join(" "));
+        if body.is_empty() {
+            continue;
+        }
+        segments.push(TranscriptSegment {
+            start_time,
+            end_time,
+            speaker,
+            body,
+        });
+    }
+    segments
+}
+
+/// Strip markup from cue text, taking the speaker from a WebVTT voice span
+/// (`<v Alice>` or `<v.loud Alice>`).
+fn cue_text(raw: &str) -> (Option<String>, String) {
+    let mut speaker = None;
+    let mut body = String::new();
+    let mut rest = raw;
+    while let Some(open) = rest.find('<') {
+        body.push_str(&rest[..open]);
+        let Some(len) = rest[open..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[open + 1..open + len];
+        if speaker.is_none() {
+            if let Some(voice) = tag.strip_prefix('v').filter(|v| v.starts_with([' ', '.'])) {
+                speaker = voice
+                    .split_once(' ')
+                    .map(|(_, name)| name.trim().to_string())
+                    .filter(|name| !name.is_empty());
+            }
+        }
+        rest = &rest[open + len + 1..];
+    }
+    body.push_str(rest);
+    (speaker, collapse_whitespace(&decode_entities(&body)))
+}
+
+/// Parse an HTML transcript in the Podcast Index style, where each `<p>`
+/// is a segment optionally preceded by a `<cite>` speaker and a `<time>`
+/// timestamp. Segments without their own timestamp inherit the last one.
+/// Documents without paragraphs become a single segment.
+fn parse_html(html: &str) -> Vec<TranscriptSegment> {
+    let mut segments = Vec::new();
+    let mut speaker: Option<String> = None;
+    let mut start_time = 0.0;
+    // The element whose text is being collected, and that text.
+    let mut capture: Option<(String, String)> = None;
+    let mut all_text = String::new();
+
+    let mut rest = html;
+    while let Some(open) = rest.find('<') {
+        let text = &rest[..open];
+        all_text.push_str(text);
+        if let Some((_, ref mut captured)) = capture {
+            captured.push_str(text);
+        }
+        let Some(len) = rest[open..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[open + 1..open + len].trim();
+        rest = &rest[open + len + 1..];
+
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if !matches!(name.as_str(), "cite" | "time" | "p") {
+            // Keep words on either side of a <br> apart.
+            all_text.push(' ');
+            if let Some((_, ref mut captured)) = capture {
+                captured.push(' ');
+            }
+            continue;
+        }
+
+        if !closing {
+            if capture.is_none() {
+                capture = Some((name, String::new()));
+            }
+            continue;
+        }
+        if capture.as_ref().is_none_or(|(open_name, _)| *open_name != name) {
+            continue;
+        }
+        let (_, captured) = capture.take().unwrap();
+        let text = collapse_whitespace(&decode_entities(&captured));
+        match name.as_str() {
+            "cite" => {
+                let name = text.trim_end_matches(':').trim();
+                speaker = (!name.is_empty()).then(|| name.to_string());
+            }
+            "time" => {
+                if let Some(t) = parse_timestamp(&text) {
+                    start_time = t;
+                }
+            }
+            _ if !text.is_empty() => segments.push(TranscriptSegment {
+                start_time,
+                end_time: None,
+                speaker: speaker.clone(),
+                body: text,
+            }),
+            _ => {}
+        }
+    }
+    all_text.push_str(rest);
+
+    if segments.is_empty() {
+        let body = collapse_whitespace(&decode_entities(&all_text));
+        if !body.is_empty() {
+            segments.push(TranscriptSegment {
+                start_time: 0.0,
+                end_time: None,
+                speaker: None,
+                body,
+            });
+        }
+    }
+    segments
+}
+
+/// Parse `[hh:]mm:ss[.fff]` (SRT uses a comma for the fraction) into
+/// seconds.
+fn parse_timestamp(s: &str) -> Option<f64> {
+    let s = s.replace(',', ".");
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut secs = 0.0;
+    for part in parts {
+        let value: f64 = part.trim().parse().ok()?;
+        if value < 0.0 {
+            return None;
+        }
+        secs = secs * 60.0 + value;
+    }
+    Some(secs)
+}
+
+fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bodies(segments: &[TranscriptSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.body.as_str()).collect()
+    }
+
+    #[test]
+    fn srt_and_vtt_cues() {
+        let srt = "1\r\n00:00:00,500 --> 00:00:02,000\r\nHello &amp; welcome\r\nto the show.\r\n\r\n2\r\n00:00:02,000 --> 00:00:04,250\r\n<i>Thanks</i>\r\n";
+        let segments = parse(Format::Srt, srt).unwrap();
+        assert_eq!(bodies(&segments), vec!["Hello & welcome to the show.", "Thanks"]);
+        assert_eq!(segments[0].start_time, 0.5);
+        assert_eq!(segments[1].end_time, Some(4.25));
+
+        let vtt = "WEBVTT\n\nNOTE written by hand\n\nintro\n01:02.000 --> 01:04.000 align:start\n<v Alice>Hi there</v>\n\n1:00:00.000 --> 1:00:01.000\nBye\n";
+        let segments = parse(Format::Vtt, vtt).unwrap();
+        assert_eq!(bodies(&segments), vec!["Hi there", "Bye"]);
+        assert_eq!(segments[0].speaker.as_deref(), Some("Alice"));
+        assert_eq!(segments[0].start_time, 62.0);
+        assert_eq!(segments[1].start_time, 3600.0);
+    }
+
+    #[test]
+    fn json_words_joined_into_sentences() {
+        let json = r#"{"version": "1.0.0", "segments": [
+  {"speaker": "Alice", "startTime": 0.0, "endTime": 0.4, "body": "Hello"},
+  {"speaker": "Alice", "startTime": 0.4, "endTime": 0.9, "body": "world."},
+  {"speaker": "Alice", "startTime": 1.0, "endTime": 1.5, "body": "Next"},
+  {"speaker": "Bob", "startTime": 1.6, "endTime": 2.0, "body": "Hi"}
+]}"#;
+        let segments = parse(Format::Json, json).unwrap();
+        assert_eq!(bodies(&segments), vec!["Hello world.", "Next", "Hi"]);
+        assert_eq!(segments[0].end_time, Some(0.9));
+        assert_eq!(segments[2].speaker.as_deref(), Some("Bob"));
+    }
+
+    #[test]
+    fn html_paragraphs() {
+        let html = "<cite>Alice:</cite>\n<time>0:00</time>\n<p>Welcome to<br>the show.</p>\n<p>Second &#8220;thought&#8221;</p>\n<cite>Bob:</cite>\n<time>1:05</time>\n<p>Thanks!</p>";
+        let segments = parse(Format::Html, html).unwrap();
+        assert_eq!(
+            bodies(&segments),
+            vec!["Welcome to the show.", "Second \u{201c}thought\u{201d}", "Thanks!"]
+        );
+        assert_eq!(segments[1].speaker.as_deref(), Some("Alice"));
+        assert_eq!(segments[1].start_time, 0.0);
+        assert_eq!(segments[2].speaker.as_deref(), Some("Bob"));
+        assert_eq!(segments[2].start_time, 65.0);
+
+        let plain = parse(Format::Html, "<div>Just some text</div>").unwrap();
+        assert_eq!(bodies(&plain), vec!["Just some text"]);
+    }
+
+    #[test]
+    fn preferred_format() {
+        let link = |url: &str, mime_type: &str| Transcript {
+            url: url.to_string(),
+            mime_type: mime_type.to_string(),
+            language: None,
+            rel: None,
+        };
+        let links = vec![
+            link("a.html", "text/html"),
+            link("a.txt", "text/plain"),
+            link("a.srt", "application/x-subrip"),
+            link("a.vtt", "text/vtt; charset=utf-8"),
+        ];
+        let (transcript, format) = preferred(&links).unwrap();
+        assert_eq!((transcript.url.as_str(), format), ("a.vtt", Format::Vtt));
+        assert!(preferred(&links[1..2]).is_none());
+    }
+}
@@ -0,0 +1,265 @@
+use hmac::{digest::KeyInit, Hmac, Mac};
+use rand::RngCore;
+use thiserror::Error;
+
+/// Lease requested from hubs. Hubs may grant a different one; the granted
+/// lease arrives with the verification request.
+pub const LEASE_SECONDS: i64 = 10 * 24 * 60 * 60;
+
+/// A hub advertised by a feed via `<atom:link rel="hub">`, together with
+/// the topic URL to subscribe to (the feed's `rel="self"` link).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HubLink {
+    pub hub_url: String,
+    pub topic_url: String,
+}
+
+#[derive(Debug, Error)]
+pub enum WebSubError {
+    #[error("reqwest error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("hub rejected {mode} request with {status}")]
+    Rejected {
+        mode: &'static str,
+        status: reqwest::StatusCode,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Subscribe,
+    Unsubscribe,
+}
+
+impl Mode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Subscribe => "subscribe",
+            Self::Unsubscribe => "unsubscribe",
+        }
+    }
+}
+
+/// Find the hub a feed advertises. Feeds without a `rel="self"` link use
+/// the URL we fetch them from as the topic.
+pub fn discover_hub(feed: &feed_rs::model::Feed, feed_url: &str) -> Option<HubLink> {
+    let rel = |rel: &str| {
+        feed.links
+            .iter()
+            .find(|l| l.rel.as_deref() == Some(rel) && !l.href.trim().is_empty())
+            .map(|l| l.href.trim().to_string())
+    };
+
+    Some(HubLink {
+        hub_url: rel("hub")?,
+        topic_url: rel("self").unwrap_or_else(|| feed_url.to_string()),
+    })
+}
+
+/// When a lease granted by a hub runs out. Grants longer than the
+/// [`LEASE_SECONDS`] we ask for are cut back to it, so a hub can't make us
+/// skip renewals; `None` if the expiry can't be represented.
+pub fn lease_expiry(
+    lease_seconds: Option<i64>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let lease_seconds = lease_seconds.unwrap_or(LEASE_SECONDS).clamp(1, LEASE_SECONDS);
+    chrono::TimeDelta::try_seconds(lease_seconds).and_then(|lease| now.checked_add_signed(lease))
+}
+
+/// Generate a per-subscription secret used by the hub to sign pushes.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Send a subscribe or unsubscribe request to a hub. Hubs answer 202 and
+/// confirm asynchronously through an intent-verification request to
+/// `callback_url`.
+pub async fn send_request(
+    http: &reqwest::Client,
+    mode: Mode,
+    hub: &HubLink,
+    callback_url: &str,
+    secret: &str,
+) -> Result<(), WebSubError> {
+    let mut form = vec![
+        ("hub.mode", mode.as_str().to_string()),
+        ("hub.topic", hub.topic_url.clone()),
+        ("hub.callback", callback_url.to_string()),
+    ];
+    if mode == Mode::Subscribe {
+        form.push(("hub.secret", secret.to_string()));
+        form.push(("hub.lease_seconds", LEASE_SECONDS.to_string()));
+    }
+
+    let res = http.post(&hub.hub_url).form(&form).send().await?;
+    if !res.status().is_success() {
+        return Err(WebSubError::Rejected {
+            mode: mode.as_str(),
+            status: res.status(),
+        });
+    }
+    Ok(())
+}
+
+/// Check an `X-Hub-Signature` header (`<algo>=<hex digest>`) against the
+/// HMAC of the push body.
+pub fn verify_signature(secret: &str, signature: &str, body: &[u8]) -> bool {
+    let Some((algo, digest)) = signature.trim().split_once('=') else {
+        return false;
+    };
+    let Ok(digest) = hex::decode(digest) else {
+        return false;
+    };
+
+    match algo.to_ascii_lowercase().as_str() {
+        "sha1" => verify_hmac::<Hmac<sha1::Sha1>>(secret, body, &digest),
+        "sha256" => verify_hmac::<Hmac<sha2::Sha256>>(secret, body, &digest),
+        "sha384" => verify_hmac::<Hmac<sha2::Sha384>>(secret, body, &digest),
+        "sha512" => verify_hmac::<Hmac<sha2::Sha512>>(secret, body, &digest),
+        _ => false,
+    }
+}
+
+fn verify_hmac<M: Mac + KeyInit>(secret: &str, body: &[u8], digest: &[u8]) -> bool {
+    let Ok(mut mac) = <M as KeyInit>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(digest).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, http::StatusCode, routing::post, Form, Router};
+
+    use super::*;
+
+    type Requests = Arc<Mutex<Vec<HashMap<String, String>>>>;
+
+    /// Start a mock hub on an ephemeral port that records every request
+    /// and answers with `status`.
+    async fn mock_hub(status: StatusCode) -> (String, Requests) {
+        let requests: Requests = Arc::default();
+        let app = Router::new()
+            .route(
+                "/hub",
+                post(
+                    move |State(requests): State<Requests>,
+                          Form(form): Form<HashMap<String, String>>| async move {
+                        requests.lock().unwrap().push(form);
+                        status
+                    },
+                ),
+            )
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/hub", addr), requests)
+    }
+
+    #[tokio::test]
+    async fn subscribe_posts_form_to_hub() {
+        let (hub_url, requests) = mock_hub(StatusCode::ACCEPTED).await;
+        let hub = HubLink {
+            hub_url,
+            topic_url: "https://example.com/feed.xml".into(),
+        };
+
+        send_request(
+            &reqwest::Client::new(),
+            Mode::Subscribe,
+            &hub,
+            "https://pod.example/websub/p1",
+            "s3cret",
+        )
+        .await
+        .unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let form = &requests[0];
+        assert_eq!(form["hub.mode"], "subscribe");
+        assert_eq!(form["hub.topic"], "https://example.com/feed.xml");
+        assert_eq!(form["hub.callback"], "https://pod.example/websub/p1");
+        assert_eq!(form["hub.secret"], "s3cret");
+        assert_eq!(form["hub.lease_seconds"], LEASE_SECONDS.to_string());
+    }
+
+    #[tokio::test]
+    async fn rejected_subscription_is_an_error() {
+        let (hub_url, _) = mock_hub(StatusCode::BAD_REQUEST).await;
+        let hub = HubLink {
+            hub_url,
+            topic_url: "https://example.com/feed.xml".into(),
+        };
+
+        let err = send_request(&reqwest::Client::new(), Mode::Unsubscribe, &hub, "cb", "")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            WebSubError::Rejected {
+                mode: "unsubscribe",
+                status: StatusCode::BAD_REQUEST
+            }
+        ));
+    }
+
+    #[test]
+    fn discover_hub_from_feed_links() {
+        let xml = r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+<channel>
+  <title>Show</title>
+  <atom:link rel="hub" href="https://hub.example/"/>
+  <atom:link rel="self" href="https://example.com/feed.xml"/>
+</channel>
+</rss>"#;
+        let feed = feed_rs::parser::parse(xml.as_bytes()).unwrap();
+        assert_eq!(
+            discover_hub(&feed, "http://fetched.example/feed"),
+            Some(HubLink {
+                hub_url: "https://hub.example/".into(),
+                topic_url: "https://example.com/feed.xml".into(),
+            })
+        );
+
+        let xml = r#"<rss version="2.0"><channel><title>Show</title></channel></rss>"#;
+        let feed = feed_rs::parser::parse(xml.as_bytes()).unwrap();
+        assert_eq!(discover_hub(&feed, "http://fetched.example/feed"), None);
+    }
+
+    #[test]
+    fn lease_expiry_is_bounded() {
+        let now = chrono::Utc::now();
+        let lease = |seconds| lease_expiry(seconds, now).map(|at| (at - now).num_seconds());
+        assert_eq!(lease(None), Some(LEASE_SECONDS));
+        assert_eq!(lease(Some(3600)), Some(3600));
+        assert_eq!(lease(Some(i64::MAX)), Some(LEASE_SECONDS));
+        assert_eq!(lease(Some(i64::MIN)), Some(1));
+    }
+
+    #[test]
+    fn verify_signature_algorithms() {
+        let body = b"<rss/>";
+        let mut mac = <Hmac<sha2::Sha256> as KeyInit>::new_from_slice(b"key").unwrap();
+        mac.update(body);
+        let digest = hex::encode(mac.finalize().into_bytes());
+
+        assert!(verify_signature("key", &format!("sha256={}", digest), body));
+        assert!(!verify_signature("other", &format!("sha256={}", digest), body));
+        assert!(!verify_signature("key", &format!("md5={}", digest), body));
+        assert!(!verify_signature("key", "sha256=zz", body));
+
+        let mut mac = <Hmac<sha1::Sha1> as KeyInit>::new_from_slice(b"key").unwrap();
+        mac.update(body);
+        let digest = hex::encode(mac.finalize().into_bytes());
+        assert!(verify_signature("key", &format!("sha1={}", digest), body));
+    }
+}
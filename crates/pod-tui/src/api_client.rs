@@ -3,9 +3,13 @@ use reqwest::header;
 use serde::Deserialize;
 
 use pod_model::{
-    Episode, PodcastWithEpisodeStats, ProgressState, ProgressSyncResponse, SyncResponse,
+    Chapter, Episode, InboxEpisode, Playlist, Podcast, PodcastWithEpisodeStats, ProgressState,
+    ProgressSyncResponse, SubscriptionSettings, SubscriptionSettingsPatch, SyncResponse,
+    TranscriptSegment,
 };
 
+use crate::local_db::LocalDb;
+
 pub struct ApiClient {
     base_url: String,
     token: Option<String>,
@@ -36,6 +40,32 @@ struct SyncHeadResponse {
     since: String,
 }
 
+/// Returned by `sync_changes` when the server answers 410 Gone: the cursor
+/// predates the compacted change log and the client must re-bootstrap.
+#[derive(Debug)]
+pub struct CursorExpired;
+
+impl std::fmt::Display for CursorExpired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sync cursor expired")
+    }
+}
+
+impl std::error::Error for CursorExpired {}
+
+/// Returned by `replace_queue` when the server refuses the queue, e.g.
+/// because it lists an episode of a podcast that was unsubscribed elsewhere.
+#[derive(Debug)]
+pub struct QueueRejected;
+
+impl std::fmt::Display for QueueRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "queue rejected by server")
+    }
+}
+
+impl std::error::Error for QueueRejected {}
+
 impl ApiClient {
     pub fn new(base_url: &str, token: Option<String>) -> Self {
         Self {
@@ -45,6 +75,17 @@ impl ApiClient {
         }
     }
 
+    /// A client for the server the user logged in to.
+    pub fn from_db(db: &LocalDb) -> anyhow::Result<Self> {
+        let server_url = db
+            .get_config("server_url")
+            .ok_or_else(|| anyhow::anyhow!("no server_url configured"))?;
+        let token = db
+            .get_config("auth_token")
+            .ok_or_else(|| anyhow::anyhow!("not logged in"))?;
+        Ok(Self::new(&server_url, Some(token)))
+    }
+
     fn url(&self, path: &str) -> String {
         format!("{}{}", self.base_url, path)
     }
@@ -106,7 +147,38 @@ impl ApiClient {
             .context("send list_podcasts request")?;
 
         let resp = Self::check(resp, "list_podcasts").await?;
-        Ok(resp.json().await.context("parse list_podcasts response")?)
+        resp.json().await.context("parse list_podcasts response")
+    }
+
+    /// Subscribe to the feed at `feed_url`, adding it to the server if it's
+    /// new there.
+    pub async fn add_podcast(&self, feed_url: &str) -> anyhow::Result<Podcast> {
+        let resp = self
+            .auth_request(
+                self.http
+                    .post(self.url("/api/v1/podcasts"))
+                    .json(&serde_json::json!({ "feed_url": feed_url })),
+            )
+            .send()
+            .await
+            .context("send add_podcast request")?;
+
+        let resp = Self::check(resp, "add_podcast").await?;
+        resp.json().await.context("parse add_podcast response")
+    }
+
+    pub async fn unsubscribe(&self, podcast_id: &str) -> anyhow::Result<()> {
+        let resp = self
+            .auth_request(
+                self.http
+                    .delete(self.url(&format!("/api/v1/podcasts/{}", podcast_id))),
+            )
+            .send()
+            .await
+            .context("send unsubscribe request")?;
+
+        Self::check(resp, "unsubscribe").await?;
+        Ok(())
     }
 
     /// Fetch one page of episodes for a podcast.
@@ -140,6 +212,34 @@ impl ApiClient {
         Ok((items, page.next_page_token))
     }
 
+    pub async fn get_chapters(&self, episode_id: &str) -> anyhow::Result<Vec<Chapter>> {
+        let resp = self
+            .auth_request(
+                self.http
+                    .get(self.url(&format!("/api/v1/episodes/{}/chapters", episode_id))),
+            )
+            .send()
+            .await
+            .context("send get_chapters request")?;
+
+        let resp = Self::check(resp, "get_chapters").await?;
+        resp.json().await.context("parse get_chapters response")
+    }
+
+    pub async fn get_transcript(&self, episode_id: &str) -> anyhow::Result<Vec<TranscriptSegment>> {
+        let resp = self
+            .auth_request(
+                self.http
+                    .get(self.url(&format!("/api/v1/episodes/{}/transcript", episode_id))),
+            )
+            .send()
+            .await
+            .context("send get_transcript request")?;
+
+        let resp = Self::check(resp, "get_transcript").await?;
+        resp.json().await.context("parse get_transcript response")
+    }
+
     pub async fn report_progress(
         &self,
         episode_id: &str,
@@ -160,7 +260,94 @@ impl ApiClient {
             .context("send report_progress request")?;
 
         let resp = Self::check(resp, "report_progress").await?;
-        Ok(resp.json().await.context("parse report_progress response")?)
+        resp.json().await.context("parse report_progress response")
+    }
+
+    // ==========================================================================
+    // Queue
+    // ==========================================================================
+
+    pub async fn get_queue(&self) -> anyhow::Result<Vec<InboxEpisode>> {
+        let resp = self
+            .auth_request(self.http.get(self.url("/api/v1/queue")))
+            .send()
+            .await
+            .context("send get_queue request")?;
+
+        let resp = Self::check(resp, "get_queue").await?;
+        resp.json().await.context("parse get_queue response")
+    }
+
+    /// Replace the server's queue with `episode_ids`, returning the queue
+    /// the server stored.
+    pub async fn replace_queue(&self, episode_ids: &[String]) -> anyhow::Result<Vec<InboxEpisode>> {
+        let resp = self
+            .auth_request(
+                self.http
+                    .put(self.url("/api/v1/queue"))
+                    .json(&serde_json::json!({ "episode_ids": episode_ids })),
+            )
+            .send()
+            .await
+            .context("send replace_queue request")?;
+
+        if resp.status() == reqwest::StatusCode::BAD_REQUEST {
+            return Err(QueueRejected.into());
+        }
+        let resp = Self::check(resp, "replace_queue").await?;
+        resp.json().await.context("parse replace_queue response")
+    }
+
+    pub async fn get_podcast_settings(
+        &self,
+        podcast_id: &str,
+    ) -> anyhow::Result<SubscriptionSettings> {
+        let resp = self
+            .auth_request(
+                self.http
+                    .get(self.url(&format!("/api/v1/podcasts/{}/settings", podcast_id))),
+            )
+            .send()
+            .await
+            .context("send get_podcast_settings request")?;
+
+        let resp = Self::check(resp, "get_podcast_settings").await?;
+        resp.json().await.context("parse get_podcast_settings response")
+    }
+
+    /// Change some of a subscription's settings, returning all of them.
+    pub async fn update_podcast_settings(
+        &self,
+        podcast_id: &str,
+        patch: &SubscriptionSettingsPatch,
+    ) -> anyhow::Result<SubscriptionSettings> {
+        let resp = self
+            .auth_request(
+                self.http
+                    .patch(self.url(&format!("/api/v1/podcasts/{}/settings", podcast_id)))
+                    .json(patch),
+            )
+            .send()
+            .await
+            .context("send update_podcast_settings request")?;
+
+        let resp = Self::check(resp, "update_podcast_settings").await?;
+        resp.json().await.context("parse update_podcast_settings response")
+    }
+
+    // ==========================================================================
+    // Playlists
+    // ==========================================================================
+
+    pub async fn list_playlists(&self) -> anyhow::Result<Vec<Playlist>> {
+        let resp = self
+            .auth_request(self.http.get(self.url("/api/v1/playlists")))
+            .send()
+            .await
+            .context("send list_playlists request")?;
+
+        let resp = Self::check(resp, "list_playlists").await?;
+        resp.json().await.context("parse list_playlists response")
     }
 
     // ==========================================================================
@@ -194,8 +381,11 @@ impl ApiClient {
             .await
             .context("send sync_changes request")?;
 
+        if resp.status() == reqwest::StatusCode::GONE {
+            return Err(CursorExpired.into());
+        }
         let resp = Self::check(resp, "sync_changes").await?;
-        Ok(resp.json().await.context("parse sync_changes response")?)
+        resp.json().await.context("parse sync_changes response")
     }
 
     pub async fn sync_progress(
@@ -214,6 +404,6 @@ impl ApiClient {
             .context("send sync_progress request")?;
 
         let resp = Self::check(resp, "sync_progress").await?;
-        Ok(resp.json().await.context("parse sync_progress response")?)
+        resp.json().await.context("parse sync_progress response")
     }
 }
@@ -1,8 +1,14 @@
 use tokio::sync::mpsc;
 
-use pod_model::PodcastWithEpisodeStats;
+use pod_model::{
+    Chapter, Playlist, PlaylistFilter, PodcastWithEpisodeStats, SubscriptionSettings,
+    SubscriptionState, TranscriptSegment,
+};
+use crate::daemon::{DaemonClient, Status};
+use crate::downloads::{self, DownloadManager, DownloadPolicy};
 use crate::local_db::{DownloadStatus, LocalDb};
-use crate::player::PlaybackState;
+use crate::player::{Backend, PlaybackState};
+use crate::sleep_timer::{self, SleepTimer};
 
 // ==============================================================================
 // Actions — everything the app can do in response to events or async results
@@ -22,6 +28,18 @@ pub enum Action {
 
     // Navigation
     ShowInbox,
+    ShowQueue,
+    ShowDownloads,
+    /// Switch the inbox to the next saved playlist, wrapping around to the
+    /// plain inbox.
+    NextPlaylist,
+    PreviousPlaylist,
+
+    // Search
+    ShowSearch,
+    SearchType(char),
+    SearchBackspace,
+    SearchSubmit,
 
     // Podcast list
     ListUp,
@@ -34,6 +52,36 @@ pub enum Action {
     SyncComplete(Result<(), String>),
     PodcastsLoaded(Vec<PodcastWithEpisodeStats>),
 
+    // Podcast management
+    /// Open the prompt for the feed URL of a podcast to subscribe to.
+    ShowAddPodcast,
+    AddPodcastType(char),
+    AddPodcastBackspace,
+    AddPodcastSubmit,
+    /// The title of the podcast subscribed to, or why it failed.
+    PodcastAdded(Result<String, String>),
+    /// Unsubscribe from the selected podcast. The first press only asks for
+    /// confirmation.
+    Unsubscribe,
+    /// The id of the podcast unsubscribed from, or why it failed.
+    Unsubscribed(Result<String, String>),
+    ShowPodcastSettings,
+    /// Step the selected setting up or down, or toggle it.
+    AdjustSetting(i32),
+    /// Start or finish editing the title override.
+    EditSettingText,
+    SettingsType(char),
+    SettingsBackspace,
+    SaveSettings,
+    /// The podcast's settings as the server stored them, or why saving
+    /// failed.
+    SettingsSaved(Result<(String, SubscriptionSettings), String>),
+    /// Send a podcast's playback speed, changed while playing, to the
+    /// server.
+    SavePodcastSpeed { podcast_id: String, speed: f32 },
+    /// Why sending a podcast's speed failed.
+    PodcastSpeedFailed(String),
+
     // Episode list
     SelectEpisode,
     EpisodesLoaded(Vec<EpisodeRow>),
@@ -42,16 +90,55 @@ pub enum Action {
     // Episode detail
     ScrollUp,
     ScrollDown,
+    ToggleTranscript,
+    FollowTranscript,
+    TranscriptLoaded {
+        episode_id: String,
+        segments: Vec<TranscriptSegment>,
+    },
 
     // Playback
     PlayEpisode,
+    /// Play an episode that isn't necessarily on screen, by id.
+    PlayEpisodeById(String),
     TogglePause,
     SeekForward,
     SeekBackward,
+    NextChapter,
+    PreviousChapter,
+    /// Seek to an absolute position in seconds.
+    SeekTo(f64),
+    SpeedUp,
+    SpeedDown,
+    VolumeUp,
+    VolumeDown,
+    ToggleSkipSilence,
+    /// Apply a playback speed, volume or skip-silence setting to the player.
+    SetSpeed(f64),
+    SetVolume(i32),
+    SetSkipSilence(bool),
+    CycleSleepTimer,
+    /// The sleep timer ran out: pause and save the position.
+    SleepTimerFired,
     StopPlayback,
     PlaybackStarted(Result<(), String>),
+    /// Sent by the player whenever the position, duration or pause state
+    /// changes.
     PlaybackUpdate(PlaybackState),
+    /// The daemon's playback state, polled every tick.
+    DaemonStatus(Status),
+    /// The daemon answered a call with an error, or not in time.
+    DaemonError(String),
+    /// Nothing listens on the daemon's socket any more.
+    DaemonLost(String),
+    /// The episode played to the end.
     PlaybackFinished,
+    /// The player stopped because of an error, e.g. a broken stream.
+    PlaybackError(String),
+    ChaptersLoaded {
+        episode_id: String,
+        chapters: Vec<Chapter>,
+    },
 
     // Downloads
     DownloadEpisode,
@@ -60,7 +147,35 @@ pub enum Action {
         downloaded_bytes: u64,
         total_bytes: u64,
     },
-    DownloadComplete(Result<String, String>),
+    DownloadComplete(String),
+    /// A download attempt failed. It is tried again after `retry_in_secs`,
+    /// or given up on when that is `None`.
+    DownloadFailed {
+        episode_id: String,
+        error: String,
+        retry_in_secs: Option<u64>,
+    },
+    /// A download couldn't be started, with why.
+    DownloadNotStarted(String),
+    /// Stop the selected download, keeping what was downloaded so far.
+    CancelDownload,
+    /// Resume the selected download after it failed or was cancelled.
+    RetryDownload,
+    /// Cancel the selected download and delete its file.
+    DeleteDownload,
+
+    // Up Next queue
+    /// Append the selected episode to the queue.
+    Enqueue,
+    /// Queue the selected episode to play next.
+    EnqueueNext,
+    Dequeue,
+    QueueMoveUp,
+    QueueMoveDown,
+    ClearQueue,
+    /// Start the first episode in the queue.
+    PlayQueue,
+    PushQueue,
 
     // Periodic progress push to server
     PushProgress,
@@ -94,9 +209,95 @@ pub struct PodcastListState {
     pub podcasts: Vec<PodcastWithEpisodeStats>,
     pub selected: usize,
     pub loading: bool,
+    /// The podcast `Unsubscribe` was pressed on once; pressing it again
+    /// unsubscribes.
+    pub confirm_unsubscribe: Option<String>,
 }
 
-#[derive(Clone)]
+pub struct AddPodcastState {
+    pub feed_url: String,
+    pub error: Option<String>,
+    pub loading: bool,
+}
+
+/// The settings listed in the podcast settings view, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingField {
+    PlaybackSpeed,
+    SkipIntro,
+    SkipOutro,
+    AutoDownload,
+    HideFromInbox,
+    Notifications,
+    TitleOverride,
+    State,
+}
+
+impl SettingField {
+    pub const ALL: [SettingField; 8] = [
+        SettingField::PlaybackSpeed,
+        SettingField::SkipIntro,
+        SettingField::SkipOutro,
+        SettingField::AutoDownload,
+        SettingField::HideFromInbox,
+        SettingField::Notifications,
+        SettingField::TitleOverride,
+        SettingField::State,
+    ];
+}
+
+/// Seconds the intro and outro skips change by per step.
+const SKIP_STEP_SECS: i32 = 5;
+
+pub struct PodcastSettingsState {
+    pub podcast_id: String,
+    pub podcast_title: String,
+    /// The settings being edited; nothing changes until they're saved.
+    pub settings: SubscriptionSettings,
+    pub selected: usize,
+    /// Whether keys go to the title override.
+    pub editing: bool,
+    pub saving: bool,
+}
+
+impl PodcastSettingsState {
+    pub fn field(&self) -> SettingField {
+        SettingField::ALL[self.selected]
+    }
+
+    /// Step the selected setting by `steps`, or toggle it.
+    fn adjust(&mut self, steps: i32) {
+        let field = self.field();
+        let s = &mut self.settings;
+        match field {
+            SettingField::PlaybackSpeed => {
+                s.playback_speed =
+                    crate::player::step_speed(s.playback_speed as f64, steps) as f32;
+            }
+            SettingField::SkipIntro => {
+                s.skip_intro_secs = (s.skip_intro_secs + steps * SKIP_STEP_SECS).max(0);
+            }
+            SettingField::SkipOutro => {
+                s.skip_outro_secs = (s.skip_outro_secs + steps * SKIP_STEP_SECS).max(0);
+            }
+            SettingField::AutoDownload => s.auto_download = (s.auto_download + steps).max(0),
+            SettingField::HideFromInbox => s.hide_from_inbox = !s.hide_from_inbox,
+            SettingField::Notifications => s.notifications = !s.notifications,
+            SettingField::TitleOverride => {}
+            SettingField::State => {
+                let states = [
+                    SubscriptionState::Active,
+                    SubscriptionState::Paused,
+                    SubscriptionState::Archived,
+                ];
+                let current = states.iter().position(|st| *st == s.state).unwrap_or(0);
+                s.state = states[(current as i32 + steps).rem_euclid(3) as usize];
+            }
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
 pub struct EpisodeRow {
     pub id: String,
     pub podcast_id: String,
@@ -111,6 +312,53 @@ pub struct EpisodeRow {
     /// Set when displaying episodes across multiple podcasts (inbox view).
     pub podcast_title: Option<String>,
     pub download_status: Option<DownloadStatus>,
+    pub season: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub episode_type: Option<String>,
+}
+
+impl EpisodeRow {
+    /// Short label shown before the title: the episode type for trailers
+    /// and bonus episodes, otherwise the season/episode number (`S2E5`).
+    pub fn label(&self) -> Option<String> {
+        match self.episode_type.as_deref() {
+            Some("trailer") => return Some("Trailer".to_string()),
+            Some("bonus") => return Some("Bonus".to_string()),
+            _ => {}
+        }
+        match (self.season, self.episode_number) {
+            (Some(s), Some(e)) => Some(format!("S{}E{}", s, e)),
+            (None, Some(e)) => Some(format!("E{}", e)),
+            _ => None,
+        }
+    }
+}
+
+/// An episode matching a search, with a snippet of its description.
+#[derive(Clone)]
+pub struct SearchHit {
+    pub episode: EpisodeRow,
+    pub snippet: String,
+}
+
+/// An episode's download, as listed in the downloads view.
+#[derive(Clone)]
+pub struct DownloadRow {
+    pub episode: EpisodeRow,
+    pub file_path: String,
+    pub status: DownloadStatus,
+    pub downloaded_bytes: i64,
+    /// Zero until the server has said how big the file is.
+    pub total_bytes: i64,
+    pub error: Option<String>,
+}
+
+pub struct SearchState {
+    pub query: String,
+    pub results: Vec<SearchHit>,
+    pub selected: usize,
+    /// Whether keys go to the query (`true`) or navigate the results.
+    pub editing: bool,
 }
 
 pub struct EpisodeListState {
@@ -128,9 +376,52 @@ pub struct EpisodeDetailState {
     /// Remembered so we can navigate back to the episode list.
     pub podcast_title: String,
     pub episode_index: usize,
+    /// `None` until fetched; empty when the episode has no transcript.
+    pub transcript: Option<Vec<TranscriptSegment>>,
+    pub show_transcript: bool,
+    /// Index of the first transcript segment on screen.
+    pub transcript_offset: usize,
+    /// Keep the segment being played on screen. Scrolling by hand turns
+    /// this off.
+    pub follow_transcript: bool,
+}
+
+impl EpisodeDetailState {
+    /// Index of the transcript segment at the playback position, when this
+    /// episode is the one playing.
+    pub fn playing_segment(&self, now_playing: Option<&NowPlaying>) -> Option<usize> {
+        let np = now_playing.filter(|np| np.episode_id == self.episode.id)?;
+        let position = np.state.position_secs as f64;
+        self.transcript
+            .as_ref()?
+            .iter()
+            .rposition(|s| s.start_time <= position)
+    }
+
+    fn scroll_transcript(&mut self, delta: isize) {
+        let len = self.transcript.as_ref().map_or(0, Vec::len);
+        self.transcript_offset = self
+            .transcript_offset
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+        self.follow_transcript = false;
+    }
+}
+
+pub struct QueueState {
+    pub episodes: Vec<EpisodeRow>,
+    pub selected: usize,
+    pub scroll_tick: usize,
+}
+
+pub struct DownloadsState {
+    pub downloads: Vec<DownloadRow>,
+    pub selected: usize,
 }
 
 pub struct InboxState {
+    /// The saved playlist being shown, or `None` for the plain inbox.
+    pub playlist: Option<Playlist>,
     pub episodes: Vec<EpisodeRow>,
     pub selected: usize,
     pub scroll_tick: usize,
@@ -138,14 +429,42 @@ pub struct InboxState {
     pub has_more: bool,
 }
 
+impl InboxState {
+    fn new(db: &LocalDb, playlist: Option<Playlist>) -> Self {
+        let mut state = Self {
+            playlist,
+            episodes: Vec::new(),
+            selected: 0,
+            scroll_tick: 0,
+            has_more: false,
+        };
+        state.episodes = db.list_inbox_episodes(&state.filter(), INBOX_PAGE_SIZE, 0);
+        state.has_more = state.episodes.len() as i64 >= INBOX_PAGE_SIZE;
+        state
+    }
+
+    fn filter(&self) -> PlaylistFilter {
+        self.playlist
+            .as_ref()
+            .map(|p| p.filter.clone())
+            .unwrap_or_default()
+    }
+}
+
 const INBOX_PAGE_SIZE: i64 = 50;
+const SEARCH_LIMIT: i64 = 100;
 
 pub enum View {
     Login(LoginState),
     Inbox(InboxState),
     PodcastList(PodcastListState),
     EpisodeList(EpisodeListState),
-    EpisodeDetail(EpisodeDetailState),
+    EpisodeDetail(Box<EpisodeDetailState>),
+    Search(SearchState),
+    Queue(QueueState),
+    Downloads(DownloadsState),
+    AddPodcast(AddPodcastState),
+    PodcastSettings(PodcastSettingsState),
 }
 
 // ==============================================================================
@@ -153,10 +472,21 @@ pub enum View {
 // ==============================================================================
 
 /// Playback state visible to the UI.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct NowPlaying {
     pub episode_id: String,
+    pub podcast_id: String,
     pub episode_title: String,
     pub state: PlaybackState,
+    pub speed: f64,
+    /// Sorted by start time; empty when the episode has none.
+    pub chapters: Vec<Chapter>,
+}
+
+impl NowPlaying {
+    pub fn current_chapter(&self) -> Option<&Chapter> {
+        crate::chapters::current(&self.chapters, self.state.position_secs as f64)
+    }
 }
 
 pub struct App {
@@ -168,7 +498,25 @@ pub struct App {
     pub action_tx: mpsc::UnboundedSender<Action>,
     pub action_rx: mpsc::UnboundedReceiver<Action>,
     pub db: LocalDb,
+    pub downloads: DownloadManager,
     pub now_playing: Option<NowPlaying>,
+    /// What episodes are played with; mpv unless set otherwise.
+    pub backend: Backend,
+    /// Player volume in percent, kept across episodes and sessions.
+    pub volume: i32,
+    pub skip_silence: bool,
+    pub sleep_timer: Option<SleepTimer>,
+    /// Volume last sent to the player while the sleep timer fades out.
+    faded_volume: Option<i32>,
+    /// The background daemon playback is handed to, when one is running.
+    /// Playback state is then mirrored from it rather than kept here.
+    pub daemon: Option<DaemonClient>,
+    /// The daemon's status message when it was last mirrored.
+    daemon_message: Option<String>,
+    /// Whether this process applies the download policy. Clients of a
+    /// running daemon leave it to the daemon so two processes never fetch
+    /// one file.
+    pub manage_downloads: bool,
 }
 
 impl App {
@@ -179,14 +527,7 @@ impl App {
         let has_token = db.get_config("auth_token").is_some();
 
         let view = if has_token {
-            let episodes = db.list_inbox_episodes(INBOX_PAGE_SIZE, 0);
-            let has_more = episodes.len() as i64 >= INBOX_PAGE_SIZE;
-            View::Inbox(InboxState {
-                episodes,
-                selected: 0,
-                scroll_tick: 0,
-                has_more,
-            })
+            View::Inbox(InboxState::new(&db, None))
         } else {
             let server_url = db
                 .get_config("server_url")
@@ -201,6 +542,13 @@ impl App {
             })
         };
 
+        let volume = db
+            .get_config("volume")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100);
+        let skip_silence = db.get_config("skip_silence").is_some_and(|v| v == "true");
+        let downloads = DownloadManager::new(action_tx.clone());
+
         Self {
             view,
             should_quit: false,
@@ -209,8 +557,17 @@ impl App {
             sync_status: None,
             action_tx,
             action_rx,
+            downloads,
             db,
             now_playing: None,
+            backend: Backend::Mpv,
+            volume,
+            skip_silence,
+            sleep_timer: None,
+            faded_volume: None,
+            daemon: None,
+            daemon_message: None,
+            manage_downloads: true,
         }
     }
 
@@ -227,8 +584,15 @@ impl App {
                     View::Inbox(ref mut s) => {
                         s.scroll_tick = s.scroll_tick.wrapping_add(1);
                     }
+                    View::Queue(ref mut s) => {
+                        s.scroll_tick = s.scroll_tick.wrapping_add(1);
+                    }
                     _ => {}
                 }
+                // The daemon runs its own sleep timer.
+                if self.daemon.is_none() {
+                    self.check_sleep_timer();
+                }
             }
 
             // Login actions
@@ -287,6 +651,75 @@ impl App {
             Action::ShowInbox => {
                 self.view = View::Inbox(self.new_inbox_state());
             }
+            Action::NextPlaylist | Action::PreviousPlaylist => {
+                if let View::Inbox(ref s) = self.view {
+                    // The plain inbox comes before the first playlist.
+                    let playlists = self.db.list_playlists();
+                    let count = playlists.len() + 1;
+                    let current = s
+                        .playlist
+                        .as_ref()
+                        .and_then(|p| playlists.iter().position(|q| q.id == p.id))
+                        .map_or(0, |i| i + 1);
+                    let next = if matches!(action, Action::NextPlaylist) {
+                        (current + 1) % count
+                    } else {
+                        (current + count - 1) % count
+                    };
+                    let playlist = next.checked_sub(1).map(|i| playlists[i].clone());
+                    self.view = View::Inbox(InboxState::new(&self.db, playlist));
+                    if count == 1 {
+                        self.status_message = Some("No saved playlists".to_string());
+                    }
+                }
+            }
+            Action::ShowQueue => {
+                self.view = View::Queue(QueueState {
+                    episodes: self.db.list_queue_episodes(),
+                    selected: 0,
+                    scroll_tick: 0,
+                });
+            }
+
+            Action::ShowDownloads => {
+                self.view = View::Downloads(DownloadsState {
+                    downloads: self.db.list_downloads(),
+                    selected: 0,
+                });
+            }
+
+            // Search
+            Action::ShowSearch => {
+                if let View::Search(ref mut s) = self.view {
+                    s.editing = true;
+                } else {
+                    self.view = View::Search(SearchState {
+                        query: String::new(),
+                        results: Vec::new(),
+                        selected: 0,
+                        editing: true,
+                    });
+                }
+            }
+            Action::SearchType(ch) => {
+                if let View::Search(ref mut s) = self.view {
+                    s.query.push(ch);
+                    s.results = self.db.search_episodes(&s.query, SEARCH_LIMIT);
+                    s.selected = 0;
+                }
+            }
+            Action::SearchBackspace => {
+                if let View::Search(ref mut s) = self.view {
+                    s.query.pop();
+                    s.results = self.db.search_episodes(&s.query, SEARCH_LIMIT);
+                    s.selected = 0;
+                }
+            }
+            Action::SearchSubmit => {
+                if let View::Search(ref mut s) = self.view {
+                    s.editing = false;
+                }
+            }
 
             // Navigation
             Action::NavigateBack => {
@@ -304,35 +737,71 @@ impl App {
                             scroll_tick: 0,
                         });
                     }
-                    View::EpisodeList(_) | View::Inbox(_) => {
+                    View::EpisodeList(_)
+                    | View::Inbox(_)
+                    | View::AddPodcast(_)
+                    | View::PodcastSettings(_) => {
                         self.load_podcasts();
                     }
+                    View::Search(_) | View::Queue(_) | View::Downloads(_) => {
+                        self.view = View::Inbox(self.new_inbox_state());
+                    }
                     _ => {}
                 }
             }
 
             // List navigation
             Action::ListUp => match self.view {
-                View::PodcastList(ref mut s) => {
-                    if s.selected > 0 { s.selected -= 1; }
+                View::PodcastList(ref mut s) if s.selected > 0 => {
+                    s.selected -= 1;
                 }
-                View::EpisodeList(ref mut s) => {
-                    if s.selected > 0 { s.selected -= 1; s.scroll_tick = 0; }
+                View::EpisodeList(ref mut s) if s.selected > 0 => {
+                    s.selected -= 1;
+                    s.scroll_tick = 0;
                 }
-                View::Inbox(ref mut s) => {
-                    if s.selected > 0 { s.selected -= 1; s.scroll_tick = 0; }
+                View::Inbox(ref mut s) if s.selected > 0 => {
+                    s.selected -= 1;
+                    s.scroll_tick = 0;
+                }
+                View::Search(ref mut s) if s.selected > 0 => {
+                    s.selected -= 1;
+                }
+                View::Queue(ref mut s) if s.selected > 0 => {
+                    s.selected -= 1;
+                    s.scroll_tick = 0;
+                }
+                View::Downloads(ref mut s) if s.selected > 0 => {
+                    s.selected -= 1;
+                }
+                View::PodcastSettings(ref mut s) if s.selected > 0 => {
+                    s.selected -= 1;
                 }
                 _ => {}
             },
             Action::ListDown => match self.view {
-                View::PodcastList(ref mut s) => {
-                    if s.selected + 1 < s.podcasts.len() { s.selected += 1; }
+                View::PodcastList(ref mut s) if s.selected + 1 < s.podcasts.len() => {
+                    s.selected += 1;
                 }
-                View::EpisodeList(ref mut s) => {
-                    if s.selected + 1 < s.episodes.len() { s.selected += 1; s.scroll_tick = 0; }
+                View::EpisodeList(ref mut s) if s.selected + 1 < s.episodes.len() => {
+                    s.selected += 1;
+                    s.scroll_tick = 0;
                 }
-                View::Inbox(ref mut s) => {
-                    if s.selected + 1 < s.episodes.len() { s.selected += 1; s.scroll_tick = 0; }
+                View::Inbox(ref mut s) if s.selected + 1 < s.episodes.len() => {
+                    s.selected += 1;
+                    s.scroll_tick = 0;
+                }
+                View::Search(ref mut s) if s.selected + 1 < s.results.len() => {
+                    s.selected += 1;
+                }
+                View::Queue(ref mut s) if s.selected + 1 < s.episodes.len() => {
+                    s.selected += 1;
+                    s.scroll_tick = 0;
+                }
+                View::Downloads(ref mut s) if s.selected + 1 < s.downloads.len() => {
+                    s.selected += 1;
+                }
+                View::PodcastSettings(ref mut s) if s.selected + 1 < SettingField::ALL.len() => {
+                    s.selected += 1;
                 }
                 _ => {}
             },
@@ -350,6 +819,19 @@ impl App {
                     s.selected = s.selected.saturating_sub(10);
                     s.scroll_tick = 0;
                 }
+                View::Search(ref mut s) => {
+                    s.selected = s.selected.saturating_sub(10);
+                }
+                View::Queue(ref mut s) => {
+                    s.selected = s.selected.saturating_sub(10);
+                    s.scroll_tick = 0;
+                }
+                View::Downloads(ref mut s) => {
+                    s.selected = s.selected.saturating_sub(10);
+                }
+                View::EpisodeDetail(ref mut s) if s.show_transcript => {
+                    s.scroll_transcript(-10);
+                }
                 View::EpisodeDetail(ref mut s) => {
                     s.scroll = s.scroll.saturating_sub(10);
                 }
@@ -367,12 +849,160 @@ impl App {
                     s.selected = (s.selected + 10).min(s.episodes.len().saturating_sub(1));
                     s.scroll_tick = 0;
                 }
+                View::Search(ref mut s) => {
+                    s.selected = (s.selected + 10).min(s.results.len().saturating_sub(1));
+                }
+                View::Queue(ref mut s) => {
+                    s.selected = (s.selected + 10).min(s.episodes.len().saturating_sub(1));
+                    s.scroll_tick = 0;
+                }
+                View::Downloads(ref mut s) => {
+                    s.selected = (s.selected + 10).min(s.downloads.len().saturating_sub(1));
+                }
+                View::EpisodeDetail(ref mut s) if s.show_transcript => {
+                    s.scroll_transcript(10);
+                }
                 View::EpisodeDetail(ref mut s) => {
                     s.scroll = s.scroll.saturating_add(10);
                 }
                 _ => {}
             },
 
+            // Podcast management
+            Action::ShowAddPodcast => {
+                self.view = View::AddPodcast(AddPodcastState {
+                    feed_url: String::new(),
+                    error: None,
+                    loading: false,
+                });
+            }
+            Action::AddPodcastType(ch) => {
+                if let View::AddPodcast(ref mut s) = self.view {
+                    s.feed_url.push(ch);
+                }
+            }
+            Action::AddPodcastBackspace => {
+                if let View::AddPodcast(ref mut s) = self.view {
+                    s.feed_url.pop();
+                }
+            }
+            Action::AddPodcastSubmit => {
+                // Async work handled by event layer.
+            }
+            Action::PodcastAdded(Ok(title)) => {
+                self.status_message = Some(format!("Subscribed to {}", title));
+                self.load_podcasts();
+                let _ = self.action_tx.send(Action::RefreshSync);
+            }
+            Action::PodcastAdded(Err(e)) => {
+                if let View::AddPodcast(ref mut s) = self.view {
+                    s.error = Some(e);
+                    s.loading = false;
+                }
+            }
+            Action::Unsubscribe => {
+                if let View::PodcastList(ref mut s) = self.view {
+                    if let Some(podcast) = s.podcasts.get(s.selected) {
+                        if s.confirm_unsubscribe.as_ref() == Some(&podcast.id) {
+                            // Confirmed; the event layer sends the request.
+                            s.confirm_unsubscribe = None;
+                            self.status_message =
+                                Some(format!("Unsubscribing from {}…", podcast.title));
+                        } else {
+                            s.confirm_unsubscribe = Some(podcast.id.clone());
+                            self.status_message = Some(format!(
+                                "Press X again to unsubscribe from {}",
+                                podcast.title
+                            ));
+                        }
+                    }
+                }
+            }
+            Action::Unsubscribed(Ok(podcast_id)) => {
+                for episode in self.db.list_episodes(&podcast_id) {
+                    self.downloads.cancel(&episode.id);
+                }
+                for path in self.db.delete_podcast(&podcast_id) {
+                    let _ = std::fs::remove_file(path);
+                }
+                self.status_message = Some("Unsubscribed".to_string());
+                self.reload_current_view();
+            }
+            Action::Unsubscribed(Err(e)) => {
+                self.status_message = Some(format!("Unsubscribe failed: {}", e));
+            }
+            Action::ShowPodcastSettings => {
+                if let View::PodcastList(ref s) = self.view {
+                    if let Some(podcast) = s.podcasts.get(s.selected) {
+                        self.view = View::PodcastSettings(PodcastSettingsState {
+                            podcast_id: podcast.id.clone(),
+                            podcast_title: podcast.title.clone(),
+                            settings: self.db.get_podcast_settings(&podcast.id),
+                            selected: 0,
+                            editing: false,
+                            saving: false,
+                        });
+                    }
+                }
+            }
+            Action::AdjustSetting(steps) => {
+                if let View::PodcastSettings(ref mut s) = self.view {
+                    s.adjust(steps);
+                }
+            }
+            Action::EditSettingText => {
+                if let View::PodcastSettings(ref mut s) = self.view {
+                    if s.field() == SettingField::TitleOverride {
+                        s.editing = !s.editing;
+                        let title = &mut s.settings.title_override;
+                        if s.editing {
+                            title.get_or_insert_with(String::new);
+                        } else if title.as_deref().is_some_and(|t| t.trim().is_empty()) {
+                            *title = None;
+                        }
+                    }
+                }
+            }
+            Action::SettingsType(ch) => {
+                if let View::PodcastSettings(ref mut s) = self.view {
+                    s.settings.title_override.get_or_insert_with(String::new).push(ch);
+                }
+            }
+            Action::SettingsBackspace => {
+                if let View::PodcastSettings(ref mut s) = self.view {
+                    if let Some(title) = s.settings.title_override.as_mut() {
+                        title.pop();
+                    }
+                }
+            }
+            Action::SaveSettings => {
+                // Sent to the server by the event layer.
+            }
+            Action::SettingsSaved(Ok((podcast_id, settings))) => {
+                self.db.set_podcast_settings(&podcast_id, &settings);
+                if let View::PodcastSettings(ref mut s) = self.view {
+                    if s.podcast_id == podcast_id {
+                        s.settings = settings;
+                        s.saving = false;
+                    }
+                }
+                self.status_message = Some("Settings saved".to_string());
+                // A changed auto-download count takes effect right away.
+                self.apply_download_policy();
+            }
+            Action::SettingsSaved(Err(e)) => {
+                if let View::PodcastSettings(ref mut s) = self.view {
+                    s.saving = false;
+                }
+                self.status_message = Some(format!("Saving settings failed: {}", e));
+            }
+            Action::SavePodcastSpeed { .. } => {
+                // Sent to the server by the event layer.
+            }
+            Action::PodcastSpeedFailed(e) => {
+                self.status_message = Some(format!("Saving the speed failed: {}", e));
+            }
+
             // Podcast selection
             Action::SelectPodcast => {
                 if let View::PodcastList(ref s) = self.view {
@@ -398,30 +1028,77 @@ impl App {
                     View::Inbox(s) => s.episodes.get(s.selected).map(|e| {
                         (e.clone(), e.podcast_title.clone().unwrap_or_default(), s.selected)
                     }),
+                    View::Search(s) => s.results.get(s.selected).map(|h| {
+                        let e = &h.episode;
+                        (e.clone(), e.podcast_title.clone().unwrap_or_default(), 0)
+                    }),
+                    View::Queue(s) => s.episodes.get(s.selected).map(|e| {
+                        (e.clone(), e.podcast_title.clone().unwrap_or_default(), 0)
+                    }),
+                    View::Downloads(s) => s.downloads.get(s.selected).map(|d| {
+                        let e = &d.episode;
+                        (e.clone(), e.podcast_title.clone().unwrap_or_default(), 0)
+                    }),
                     _ => None,
                 };
                 if let Some((episode, podcast_title, index)) = detail {
-                    self.view = View::EpisodeDetail(EpisodeDetailState {
+                    self.view = View::EpisodeDetail(Box::new(EpisodeDetailState {
                         episode,
                         scroll: 0,
                         podcast_title,
                         episode_index: index,
-                    });
+                        transcript: None,
+                        show_transcript: false,
+                        transcript_offset: 0,
+                        follow_transcript: true,
+                    }));
                 }
             }
 
             // Scroll in detail view
             Action::ScrollUp => {
                 if let View::EpisodeDetail(ref mut s) = self.view {
-                    s.scroll = s.scroll.saturating_sub(1);
+                    if s.show_transcript {
+                        s.scroll_transcript(-1);
+                    } else {
+                        s.scroll = s.scroll.saturating_sub(1);
+                    }
                 }
             }
             Action::ScrollDown => {
                 if let View::EpisodeDetail(ref mut s) = self.view {
-                    s.scroll = s.scroll.saturating_add(1);
+                    if s.show_transcript {
+                        s.scroll_transcript(1);
+                    } else {
+                        s.scroll = s.scroll.saturating_add(1);
+                    }
                 }
             }
 
+            // Transcript in detail view. Fetching is handled by the event
+            // layer the first time it is shown.
+            Action::ToggleTranscript => {
+                if let View::EpisodeDetail(ref mut s) = self.view {
+                    s.show_transcript = !s.show_transcript;
+                    s.follow_transcript = true;
+                }
+                self.follow_transcript();
+            }
+            Action::FollowTranscript => {
+                if let View::EpisodeDetail(ref mut s) = self.view {
+                    s.follow_transcript = true;
+                }
+                self.follow_transcript();
+            }
+            Action::TranscriptLoaded { episode_id, segments } => {
+                if let View::EpisodeDetail(ref mut s) = self.view {
+                    if s.episode.id == episode_id {
+                        s.transcript = Some(segments);
+                    }
+                }
+                self.follow_transcript();
+            }
+
             // Toggle done on selected episode
             Action::ToggleDone => {
                 match self.view {
@@ -458,8 +1135,26 @@ impl App {
                             s.scroll_tick = 0;
                         }
                     }
+                    View::Search(ref mut s) => {
+                        if let Some(hit) = s.results.get_mut(s.selected) {
+                            let episode = &mut hit.episode;
+                            episode.done = !episode.done;
+                            self.db.upsert_progress(
+                                &episode.id, episode.progress, episode.done, true,
+                            );
+                        }
+                    }
+                    View::Queue(ref mut s) => {
+                        if let Some(episode) = s.episodes.get_mut(s.selected) {
+                            episode.done = !episode.done;
+                            self.db.upsert_progress(
+                                &episode.id, episode.progress, episode.done, true,
+                            );
+                        }
+                    }
                     _ => {}
                 }
+                self.apply_download_policy();
             }
 
             // Sync
@@ -476,6 +1171,7 @@ impl App {
                 self.sync_status = None;
                 self.status_message = Some("Sync complete".to_string());
                 self.reload_current_view();
+                self.apply_download_policy();
             }
             Action::SyncComplete(Err(e)) => {
                 self.syncing = false;
@@ -498,35 +1194,184 @@ impl App {
                 }
             }
 
+            // Up Next queue. Edits are stored locally and pushed by the
+            // event layer.
+            Action::Enqueue => {
+                if let Some(ep) = self.selected_episode().cloned() {
+                    let added = self.edit_queue(|ids| {
+                        if !ids.contains(&ep.id) {
+                            ids.push(ep.id.clone());
+                        }
+                    });
+                    self.status_message = Some(if added {
+                        format!("Queued: {}", ep.title)
+                    } else {
+                        "Already queued".to_string()
+                    });
+                }
+            }
+            Action::EnqueueNext => {
+                if let Some(ep) = self.selected_episode().cloned() {
+                    self.edit_queue(|ids| {
+                        ids.retain(|id| *id != ep.id);
+                        ids.insert(0, ep.id.clone());
+                    });
+                    self.status_message = Some(format!("Up next: {}", ep.title));
+                }
+            }
+            Action::Dequeue => {
+                if let View::Queue(ref s) = self.view {
+                    if let Some(ep) = s.episodes.get(s.selected).cloned() {
+                        self.edit_queue(|ids| ids.retain(|id| *id != ep.id));
+                    }
+                }
+            }
+            Action::QueueMoveUp | Action::QueueMoveDown => {
+                if let View::Queue(ref s) = self.view {
+                    // Swap with the neighbour on screen; episodes that haven't
+                    // synced yet keep their place in between.
+                    let up = matches!(action, Action::QueueMoveUp);
+                    let target = if up {
+                        s.selected.checked_sub(1)
+                    } else {
+                        Some(s.selected + 1).filter(|&i| i < s.episodes.len())
+                    };
+                    if let Some(target) = target {
+                        let a = s.episodes[s.selected].id.clone();
+                        let b = s.episodes[target].id.clone();
+                        self.edit_queue(|ids| {
+                            let i = ids.iter().position(|id| *id == a);
+                            let j = ids.iter().position(|id| *id == b);
+                            if let (Some(i), Some(j)) = (i, j) {
+                                ids.swap(i, j);
+                            }
+                        });
+                        if let View::Queue(ref mut s) = self.view {
+                            s.selected = target;
+                        }
+                    }
+                }
+            }
+            Action::ClearQueue => {
+                self.edit_queue(Vec::clear);
+                self.status_message = Some("Queue cleared".to_string());
+            }
+            Action::PushQueue => {
+                // Handled by event layer.
+            }
+
             // Playback
             Action::PlayEpisode => {
-                // Set now_playing from current view before event layer spawns mpv.
-                let episode = match &self.view {
-                    View::EpisodeList(s) => s.episodes.get(s.selected).cloned(),
-                    View::Inbox(s) => s.episodes.get(s.selected).cloned(),
-                    View::EpisodeDetail(s) => Some(s.episode.clone()),
-                    _ => None,
+                // Set now_playing from current view before event layer starts the player.
+                if let Some(ep) = self.selected_episode().cloned() {
+                    self.start_now_playing(&ep);
+                }
+            }
+            Action::PlayEpisodeById(ref id) => {
+                // The event layer starts the same episode.
+                if let Some(ep) = self.db.get_episode(id) {
+                    self.start_now_playing(&ep);
+                }
+            }
+            Action::PlayQueue => {
+                // The event layer starts the same episode.
+                if let Some(ep) = self.db.list_queue_episodes().into_iter().next() {
+                    self.start_now_playing(&ep);
+                }
+            }
+            Action::TogglePause
+            | Action::SeekForward
+            | Action::SeekBackward
+            | Action::SeekTo(_)
+            | Action::SetSpeed(_)
+            | Action::SetVolume(_)
+            | Action::SetSkipSilence(_) => {
+                // Handled by event layer — sends IPC commands.
+            }
+            Action::SpeedUp | Action::SpeedDown => {
+                if let Some(ref mut np) = self.now_playing {
+                    let steps = if matches!(action, Action::SpeedUp) { 1 } else { -1 };
+                    np.speed = crate::player::step_speed(np.speed, steps);
+                    self.db.set_podcast_speed(&np.podcast_id, np.speed as f32);
+                    self.status_message = Some(format!("Speed {:.1}x", np.speed));
+                    let _ = self.action_tx.send(Action::SetSpeed(np.speed));
+                    let _ = self.action_tx.send(Action::SavePodcastSpeed {
+                        podcast_id: np.podcast_id.clone(),
+                        speed: np.speed as f32,
+                    });
+                }
+            }
+            Action::VolumeUp | Action::VolumeDown => {
+                let step = if matches!(action, Action::VolumeUp) {
+                    crate::player::VOLUME_STEP
+                } else {
+                    -crate::player::VOLUME_STEP
                 };
-                if let Some(ep) = episode {
-                    self.now_playing = Some(NowPlaying {
-                        episode_id: ep.id.clone(),
-                        episode_title: ep.title.clone(),
-                        state: PlaybackState {
-                            position_secs: ep.progress,
-                            duration_secs: ep.audio_duration,
-                            paused: false,
-                            finished: false,
-                        },
+                self.volume = (self.volume + step).clamp(0, 100);
+                self.faded_volume = None;
+                self.db.set_config("volume", &self.volume.to_string());
+                self.status_message = Some(format!("Volume {}%", self.volume));
+                let _ = self.action_tx.send(Action::SetVolume(self.volume));
+            }
+            Action::ToggleSkipSilence => {
+                self.skip_silence = !self.skip_silence;
+                self.db
+                    .set_config("skip_silence", &self.skip_silence.to_string());
+                self.status_message = Some(if self.skip_silence {
+                    "Skipping silence".to_string()
+                } else {
+                    "Not skipping silence".to_string()
+                });
+                let _ = self.action_tx.send(Action::SetSkipSilence(self.skip_silence));
+            }
+            Action::CycleSleepTimer => {
+                if let Some(ref np) = self.now_playing {
+                    self.sleep_timer = SleepTimer::cycle(
+                        self.sleep_timer.as_ref(),
+                        np,
+                        std::time::Instant::now(),
+                    );
+                    self.restore_volume();
+                    self.status_message = Some(match self.sleep_timer {
+                        Some(timer) => format!("Sleep timer: {}", timer.label()),
+                        None => "Sleep timer off".to_string(),
                     });
-                    self.status_message = Some(format!("Starting: {}", ep.title));
                 }
             }
-            Action::TogglePause | Action::SeekForward | Action::SeekBackward => {
-                // Handled by event layer — sends IPC commands.
+            Action::SleepTimerFired => {
+                // The event layer pauses the player, restores its volume and
+                // pushes progress.
+                self.faded_volume = None;
+                self.status_message = Some("Sleep timer: paused".to_string());
+            }
+            Action::NextChapter | Action::PreviousChapter => {
+                if let Some(ref mut np) = self.now_playing {
+                    let position = np.state.position_secs as f64;
+                    let target = if matches!(action, Action::NextChapter) {
+                        crate::chapters::next_start(&np.chapters, position)
+                    } else {
+                        crate::chapters::previous_start(&np.chapters, position)
+                    };
+                    if let Some(target) = target {
+                        // Move the cursor now so repeated presses step through
+                        // chapters before the player reports the new position.
+                        np.state.position_secs = target.ceil() as i32;
+                        let _ = self.action_tx.send(Action::SeekTo(target));
+                    }
+                }
+            }
+            Action::ChaptersLoaded { episode_id, chapters } => {
+                if let Some(ref mut np) = self.now_playing {
+                    if np.episode_id == episode_id {
+                        np.chapters = chapters;
+                    }
+                }
             }
             Action::StopPlayback => {
-                // Handled by event layer — kills mpv.
+                // Handled by event layer — stops the player.
                 self.now_playing = None;
+                self.sleep_timer = None;
+                self.faded_volume = None;
                 self.status_message = Some("Playback stopped".to_string());
                 let _ = self.action_tx.send(Action::PushProgress);
             }
@@ -537,6 +1382,15 @@ impl App {
                 self.now_playing = None;
                 self.status_message = Some(format!("Playback error: {}", e));
             }
+            Action::PlaybackError(e) => {
+                // The position so far is already saved; the episode isn't
+                // done and the queue doesn't move on.
+                self.now_playing = None;
+                self.sleep_timer = None;
+                self.faded_volume = None;
+                self.status_message = Some(format!("Playback error: {}", e));
+                let _ = self.action_tx.send(Action::PushProgress);
+            }
             Action::PlaybackUpdate(state) => {
                 if let Some(ref mut np) = self.now_playing {
                     // Persist progress locally.
@@ -544,12 +1398,42 @@ impl App {
                         self.db.upsert_progress(
                             &np.episode_id,
                             state.position_secs,
-                            state.finished,
+                            false,
                             true,
                         );
                     }
                     np.state = state;
                 }
+                self.follow_transcript();
+                self.check_sleep_timer();
+            }
+            Action::DaemonStatus(status) => {
+                let playing = |np: &Option<NowPlaying>| np.as_ref().map(|np| np.episode_id.clone());
+                // Progress and the queue change as the daemon moves on.
+                let moved_on = playing(&self.now_playing) != playing(&status.now_playing);
+                self.now_playing = status.now_playing;
+                self.volume = status.volume;
+                self.skip_silence = status.skip_silence;
+                self.sleep_timer = status.sleep_timer;
+                if status.message != self.daemon_message {
+                    self.status_message = status.message.clone();
+                    self.daemon_message = status.message;
+                }
+                if moved_on {
+                    self.reload_current_view();
+                }
+                self.follow_transcript();
+            }
+            Action::DaemonError(e) => {
+                self.status_message = Some(format!("Daemon: {}", e));
+            }
+            Action::DaemonLost(e) => {
+                self.daemon = None;
+                self.manage_downloads = true;
+                self.now_playing = None;
+                self.sleep_timer = None;
+                self.status_message =
+                    Some(format!("Lost the daemon ({}); playing here from now on", e));
             }
             Action::PushProgress => {
                 // Handled by event layer.
@@ -567,34 +1451,102 @@ impl App {
                 self.status_message = Some("Starting download…".to_string());
                 // Async work handled by event layer.
             }
+            // Reports from a download that was cancelled since are ignored.
             Action::DownloadProgress {
                 episode_id,
                 downloaded_bytes,
                 total_bytes,
-            } => {
-                let pct = if total_bytes > 0 {
-                    (downloaded_bytes * 100 / total_bytes) as u8
-                } else {
-                    0
-                };
-                self.update_episode_download_status(&episode_id, DownloadStatus::Downloading);
+            } if self.downloads.is_active(&episode_id) => {
+                self.db.update_download_progress(
+                    &episode_id,
+                    downloaded_bytes as i64,
+                    total_bytes as i64,
+                );
+                let pct = (downloaded_bytes * 100)
+                    .checked_div(total_bytes)
+                    .unwrap_or(0) as u8;
+                self.update_episode_download_status(&episode_id, Some(DownloadStatus::Downloading));
                 self.status_message = Some(format!("Downloading: {}%", pct));
             }
-            Action::DownloadComplete(Ok(episode_id)) => {
-                self.update_episode_download_status(&episode_id, DownloadStatus::Complete);
+            Action::DownloadComplete(episode_id) if self.downloads.is_active(&episode_id) => {
+                self.downloads.finished(&episode_id);
+                self.db.complete_download(&episode_id);
+                self.update_episode_download_status(&episode_id, Some(DownloadStatus::Complete));
                 self.status_message = Some("Download complete".to_string());
+                self.apply_download_policy();
+            }
+            Action::DownloadFailed {
+                episode_id,
+                error,
+                retry_in_secs,
+            } if self.downloads.is_active(&episode_id) => match retry_in_secs {
+                Some(secs) => {
+                    self.status_message =
+                        Some(format!("Download failed: {} (retrying in {}s)", error, secs));
+                }
+                None => {
+                    self.downloads.finished(&episode_id);
+                    self.db.fail_download(&episode_id, &error);
+                    self.update_episode_download_status(&episode_id, Some(DownloadStatus::Failed));
+                    self.status_message = Some(format!("Download failed: {}", error));
+                }
+            },
+            Action::DownloadProgress { .. }
+            | Action::DownloadComplete(_)
+            | Action::DownloadFailed { .. } => {}
+            Action::DownloadNotStarted(e) => {
+                self.status_message = Some(format!("Download not started: {}", e));
             }
-            Action::DownloadComplete(Err(msg)) => {
-                self.status_message = Some(msg);
+            Action::CancelDownload => {
+                if let View::Downloads(ref s) = self.view {
+                    if let Some(d) = s.downloads.get(s.selected) {
+                        let episode_id = d.episode.id.clone();
+                        if self.downloads.cancel(&episode_id) {
+                            self.db.fail_download(&episode_id, "Cancelled");
+                            self.status_message = Some("Download cancelled".to_string());
+                            self.reload_current_view();
+                        }
+                    }
+                }
+            }
+            Action::RetryDownload => {
+                // Restarted by the event layer.
+                self.reload_current_view();
+            }
+            Action::DeleteDownload => {
+                if let View::Downloads(ref s) = self.view {
+                    if let Some(d) = s.downloads.get(s.selected) {
+                        let episode_id = d.episode.id.clone();
+                        self.downloads.cancel(&episode_id);
+                        self.db.delete_download(&episode_id);
+                        let _ = std::fs::remove_file(&d.file_path);
+                        self.status_message = Some("Download deleted".to_string());
+                        self.reload_current_view();
+                    }
+                }
             }
 
             Action::PlaybackFinished => {
-                if let Some(ref np) = self.now_playing {
+                if let Some(np) = self.now_playing.take() {
                     self.db.upsert_progress(&np.episode_id, np.state.position_secs, true, true);
+                    self.edit_queue(|ids| ids.retain(|id| *id != np.episode_id));
                 }
-                self.now_playing = None;
                 self.status_message = Some("Playback finished".to_string());
+                self.apply_download_policy();
                 let _ = self.action_tx.send(Action::PushProgress);
+                // A timer waiting for the end of the episode (or of its
+                // last chapter) stops here; a fixed one carries on.
+                if matches!(
+                    self.sleep_timer,
+                    Some(SleepTimer::EndOfEpisode | SleepTimer::EndOfChapter { .. })
+                ) {
+                    self.sleep_timer = None;
+                    self.faded_volume = None;
+                    self.status_message = Some("Sleep timer: stopped".to_string());
+                } else if !self.db.list_queue_episodes().is_empty() {
+                    // Carry on with whatever is up next.
+                    let _ = self.action_tx.send(Action::PlayQueue);
+                }
             }
         }
 
@@ -602,32 +1554,143 @@ impl App {
         self.maybe_load_more_inbox();
     }
 
+    /// The episode under the cursor in list views, or the one shown in the
+    /// detail view.
+    pub fn selected_episode(&self) -> Option<&EpisodeRow> {
+        match &self.view {
+            View::EpisodeList(s) => s.episodes.get(s.selected),
+            View::Inbox(s) => s.episodes.get(s.selected),
+            View::EpisodeDetail(s) => Some(&s.episode),
+            View::Search(s) => s.results.get(s.selected).map(|h| &h.episode),
+            View::Queue(s) => s.episodes.get(s.selected),
+            View::Downloads(s) => s.downloads.get(s.selected).map(|d| &d.episode),
+            _ => None,
+        }
+    }
+
+    fn start_now_playing(&mut self, ep: &EpisodeRow) {
+        let settings = self.db.get_podcast_settings(&ep.podcast_id);
+        // The player starts at the full volume.
+        self.faded_volume = None;
+        self.now_playing = Some(NowPlaying {
+            episode_id: ep.id.clone(),
+            podcast_id: ep.podcast_id.clone(),
+            episode_title: ep.title.clone(),
+            state: PlaybackState {
+                position_secs: ep.progress,
+                duration_secs: ep.audio_duration,
+                paused: false,
+            },
+            speed: settings.playback_speed.into(),
+            chapters: Vec::new(),
+        });
+        self.status_message = Some(format!("Starting: {}", ep.title));
+    }
+
+    /// Apply an edit to the queue's episode ids, keep it for the next push
+    /// and refresh the queue view. Returns whether the queue changed.
+    fn edit_queue(&mut self, edit: impl FnOnce(&mut Vec<String>)) -> bool {
+        let mut ids = self.db.queue_ids();
+        let before = ids.clone();
+        edit(&mut ids);
+        if ids == before {
+            return false;
+        }
+        self.db.set_queue(&ids, true);
+        let _ = self.action_tx.send(Action::PushQueue);
+        if let View::Queue(ref mut s) = self.view {
+            s.episodes = self.db.list_queue_episodes();
+            s.selected = s.selected.min(s.episodes.len().saturating_sub(1));
+        }
+        true
+    }
+
+    /// Fade the volume out as the sleep timer runs down, and fire it once it
+    /// runs out. End-of-episode timers fire when playback finishes instead.
+    fn check_sleep_timer(&mut self) {
+        let (Some(timer), Some(np)) = (self.sleep_timer, &self.now_playing) else {
+            return;
+        };
+        let Some(remaining) = timer.remaining(np, std::time::Instant::now()) else {
+            return;
+        };
+        if np.state.paused {
+            // Nothing left to pause if the time ran out in the meantime.
+            if remaining <= 0.0 {
+                self.sleep_timer = None;
+                self.restore_volume();
+            }
+            return;
+        }
+        if remaining <= 0.0 && timer != SleepTimer::EndOfEpisode {
+            self.sleep_timer = None;
+            let _ = self.action_tx.send(Action::SleepTimerFired);
+            return;
+        }
+        let volume = sleep_timer::fade_volume(self.volume, remaining);
+        if volume != self.faded_volume.unwrap_or(self.volume) {
+            self.faded_volume = Some(volume);
+            let _ = self.action_tx.send(Action::SetVolume(volume));
+        }
+    }
+
+    /// Undo any sleep timer fade.
+    fn restore_volume(&mut self) {
+        if self.faded_volume.take().is_some() {
+            let _ = self.action_tx.send(Action::SetVolume(self.volume));
+        }
+    }
+
+    /// Scroll the transcript in the detail view so the segment being played
+    /// stays on screen, with a little of what came before it.
+    fn follow_transcript(&mut self) {
+        if let View::EpisodeDetail(ref mut s) = self.view {
+            if !s.show_transcript || !s.follow_transcript {
+                return;
+            }
+            if let Some(index) = s.playing_segment(self.now_playing.as_ref()) {
+                s.transcript_offset = index.saturating_sub(2);
+            }
+        }
+    }
+
     /// Reload the current view's data from the local database.
     fn reload_current_view(&mut self) {
         match self.view {
             View::PodcastList(ref mut s) => {
                 s.podcasts = self.db.list_podcasts();
+                s.selected = s.selected.min(s.podcasts.len().saturating_sub(1));
                 s.loading = false;
             }
             View::Inbox(ref mut s) => {
+                // Pick up edits to the playlist; if it was deleted, fall
+                // back to the plain inbox.
+                if let Some(id) = s.playlist.as_ref().map(|p| p.id.clone()) {
+                    s.playlist = self.db.list_playlists().into_iter().find(|p| p.id == id);
+                }
                 // Reload keeping at least as many episodes as currently loaded.
                 let count = (s.episodes.len() as i64).max(INBOX_PAGE_SIZE);
-                s.episodes = self.db.list_inbox_episodes(count, 0);
+                s.episodes = self.db.list_inbox_episodes(&s.filter(), count, 0);
                 s.has_more = s.episodes.len() as i64 >= count;
             }
+            View::Search(ref mut s) => {
+                s.results = self.db.search_episodes(&s.query, SEARCH_LIMIT);
+                s.selected = s.selected.min(s.results.len().saturating_sub(1));
+            }
+            View::Queue(ref mut s) => {
+                s.episodes = self.db.list_queue_episodes();
+                s.selected = s.selected.min(s.episodes.len().saturating_sub(1));
+            }
+            View::Downloads(ref mut s) => {
+                s.downloads = self.db.list_downloads();
+                s.selected = s.selected.min(s.downloads.len().saturating_sub(1));
+            }
             _ => {}
         }
     }
 
     fn new_inbox_state(&self) -> InboxState {
-        let episodes = self.db.list_inbox_episodes(INBOX_PAGE_SIZE, 0);
-        let has_more = episodes.len() as i64 >= INBOX_PAGE_SIZE;
-        InboxState {
-            episodes,
-            selected: 0,
-            scroll_tick: 0,
-            has_more,
-        }
+        InboxState::new(&self.db, None)
     }
 
     /// Load more episodes into the inbox when the user scrolls near the end.
@@ -639,7 +1702,7 @@ impl App {
             // Load more when within 5 items of the end.
             if s.selected + 5 >= s.episodes.len() {
                 let offset = s.episodes.len() as i64;
-                let more = self.db.list_inbox_episodes(INBOX_PAGE_SIZE, offset);
+                let more = self.db.list_inbox_episodes(&s.filter(), INBOX_PAGE_SIZE, offset);
                 s.has_more = more.len() as i64 >= INBOX_PAGE_SIZE;
                 s.episodes.extend(more);
             }
@@ -648,15 +1711,76 @@ impl App {
 
     /// Update the download status of an episode in the current view's episode
     /// list, avoiding a full reload.
-    fn update_episode_download_status(&mut self, episode_id: &str, status: DownloadStatus) {
-        let episodes: Option<&mut Vec<EpisodeRow>> = match self.view {
-            View::EpisodeList(ref mut s) => Some(&mut s.episodes),
-            View::Inbox(ref mut s) => Some(&mut s.episodes),
+    fn update_episode_download_status(&mut self, episode_id: &str, status: Option<DownloadStatus>) {
+        if let View::Downloads(_) = self.view {
+            // The downloads view shows progress too, so reload it instead.
+            self.reload_current_view();
+            return;
+        }
+        let episode: Option<&mut EpisodeRow> = match self.view {
+            View::EpisodeList(ref mut s) => s.episodes.iter_mut().find(|e| e.id == episode_id),
+            View::Inbox(ref mut s) => s.episodes.iter_mut().find(|e| e.id == episode_id),
+            View::Search(ref mut s) => s
+                .results
+                .iter_mut()
+                .map(|h| &mut h.episode)
+                .find(|e| e.id == episode_id),
+            View::Queue(ref mut s) => s.episodes.iter_mut().find(|e| e.id == episode_id),
             _ => None,
         };
-        if let Some(episodes) = episodes {
-            if let Some(ep) = episodes.iter_mut().find(|e| e.id == episode_id) {
-                ep.download_status = Some(status);
+        if let Some(ep) = episode {
+            ep.download_status = status;
+        }
+    }
+
+    /// Bring downloads in line with the [`DownloadPolicy`]: delete the ones
+    /// it no longer wants, start the ones it does, then delete the least
+    /// recently played until the rest fit in the quota. The playing
+    /// episode's download is kept.
+    fn apply_download_policy(&mut self) {
+        if !self.manage_downloads {
+            return;
+        }
+        let policy = DownloadPolicy::load(&self.db);
+        let cutoff = policy.cutoff();
+        let playing = self.now_playing.as_ref().map(|np| np.episode_id.clone());
+
+        let expired = self.db.list_expired_downloads(policy.delete_done, cutoff.as_deref());
+        for (episode_id, path) in expired {
+            if playing.as_ref() != Some(&episode_id) {
+                self.db.delete_download(&episode_id);
+                let _ = std::fs::remove_file(path);
+                self.update_episode_download_status(&episode_id, None);
+            }
+        }
+
+        for episode in self.db.list_auto_download_episodes(policy.queue, cutoff.as_deref()) {
+            let path = match downloads::file_path(&episode) {
+                Ok(path) => path,
+                // Every episode would fail the same way.
+                Err(e) => {
+                    self.status_message = Some(format!("Download not started: {}", e));
+                    break;
+                }
+            };
+            self.db.insert_download(&episode.id, &path, true);
+            self.downloads.start(&episode.id, &episode.audio_url, std::path::Path::new(&path));
+            self.update_episode_download_status(&episode.id, Some(DownloadStatus::Pending));
+        }
+
+        if let Some(quota) = policy.quota_bytes() {
+            let downloads = self.db.list_downloads_by_last_use();
+            let mut used: i64 = downloads.iter().map(|(_, _, bytes)| bytes).sum();
+            for (episode_id, path, bytes) in downloads {
+                if used <= quota {
+                    break;
+                }
+                if playing.as_ref() != Some(&episode_id) {
+                    self.db.evict_download(&episode_id, "Deleted to stay within the quota");
+                    let _ = std::fs::remove_file(path);
+                    self.update_episode_download_status(&episode_id, Some(DownloadStatus::Failed));
+                    used -= bytes;
+                }
             }
         }
     }
@@ -667,6 +1791,258 @@ impl App {
             podcasts,
             selected: 0,
             loading: false,
+            confirm_unsubscribe: None,
         });
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::Mutex;
+
+    use super::*;
+    use crate::event::{handle_async_action, PlayerHandle};
+
+    /// An app on a scratch database that plays with the fake player, with
+    /// episodes `e1` and `e2` queued.
+    pub(crate) fn queued_app(name: &str) -> (App, PlayerHandle) {
+        let path = std::env::temp_dir().join(format!("pod-app-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = LocalDb::open(path.to_str().unwrap()).unwrap();
+        let now = chrono::Utc::now();
+        db.upsert_podcast(&PodcastWithEpisodeStats {
+            id: "p1".to_string(),
+            title: "Test".to_string(),
+            description: String::new(),
+            image_link: String::new(),
+            feed_url: String::new(),
+            feed_type: "rss".to_string(),
+            created_at: now,
+            last_updated: now,
+            last_publication_date: None,
+            feed_etag: None,
+            feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
+        });
+        for id in ["e1", "e2"] {
+            db.upsert_episode(&pod_model::Episode {
+                id: id.to_string(),
+                podcast_id: "p1".to_string(),
+                title: format!("Episode {}", id),
+                summary: String::new(),
+                summary_type: "text/plain".to_string(),
+                content_encoded: String::new(),
+                content_encoded_type: "text/html".to_string(),
+                publication_date: now,
+                audio_url: format!("https://audio.example/{}.mp3", id),
+                audio_type: "audio/mpeg".to_string(),
+                audio_duration: 600,
+                thumbnail_url: None,
+                created_at: now,
+                last_updated: now,
+                deleted_at: None,
+                season: None,
+                episode_number: None,
+                episode_type: None,
+                chapters_url: None,
+                chapters_type: None,
+            });
+        }
+        db.set_queue(&["e1".to_string(), "e2".to_string()], false);
+
+        let mut app = App::new(db);
+        app.backend = Backend::Fake { duration_secs: 600 };
+        (app, Arc::new(Mutex::new(None)))
+    }
+
+    /// Dispatch `action` the way the main loop does, then everything it
+    /// leads to until the app goes quiet.
+    async fn run(app: &mut App, player: &PlayerHandle, action: Action) {
+        let mut next = Some(action);
+        while let Some(action) = next {
+            handle_async_action(&action, app, player);
+            app.update(action);
+            next = tokio::time::timeout(Duration::from_millis(100), app.action_rx.recv())
+                .await
+                .ok()
+                .flatten();
+        }
+    }
+
+    fn episode(app: &App, id: &str) -> EpisodeRow {
+        app.db.list_episodes("p1").into_iter().find(|e| e.id == id).unwrap()
+    }
+
+    #[tokio::test]
+    async fn queue_plays_through_with_progress_saved() {
+        let (mut app, player) = queued_app("queue");
+
+        run(&mut app, &player, Action::PlayQueue).await;
+        let np = app.now_playing.as_ref().unwrap();
+        assert_eq!(np.episode_id, "e1");
+        assert_eq!(np.state.duration_secs, 0);
+
+        run(&mut app, &player, Action::TogglePause).await;
+        assert!(app.now_playing.as_ref().unwrap().state.paused);
+
+        run(&mut app, &player, Action::SeekForward).await;
+        assert_eq!(app.now_playing.as_ref().unwrap().state.position_secs, 30);
+        assert_eq!(episode(&app, "e1").progress, 30);
+
+        // Finishing the episode marks it done and moves on to the next.
+        run(&mut app, &player, Action::SeekTo(600.0)).await;
+        assert!(episode(&app, "e1").done);
+        assert_eq!(app.db.queue_ids(), ["e2"]);
+        assert_eq!(app.now_playing.as_ref().unwrap().episode_id, "e2");
+        let playing = player.lock().await;
+        assert!(playing.as_ref().is_some_and(|p| !p.has_ended()));
+    }
+
+    #[tokio::test]
+    async fn sleep_timer_pauses_and_keeps_the_position() {
+        let (mut app, player) = queued_app("sleep");
+
+        run(&mut app, &player, Action::PlayQueue).await;
+        run(&mut app, &player, Action::SeekTo(200.0)).await;
+        app.sleep_timer = Some(SleepTimer::EndOfEpisode);
+        run(&mut app, &player, Action::SleepTimerFired).await;
+
+        let np = app.now_playing.as_ref().unwrap();
+        assert!(np.state.paused);
+        assert_eq!(np.state.position_secs, 200);
+        assert_eq!(episode(&app, "e1").progress, 200);
+        assert!(!episode(&app, "e1").done);
+    }
+
+    #[tokio::test]
+    async fn cancelled_download_ignores_late_reports() {
+        let (mut app, player) = queued_app("cancel");
+        let path = std::env::temp_dir().join(format!("pod-app-cancel-{}.mp3", std::process::id()));
+        app.db.insert_download("e1", path.to_str().unwrap(), false);
+        // A server that never answers keeps the download running, so only
+        // the reports sent below reach the app.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/e1.mp3", listener.local_addr().unwrap());
+        app.downloads.start("e1", &url, &path);
+        run(&mut app, &player, Action::ShowDownloads).await;
+        let failed = |retry_in_secs| Action::DownloadFailed {
+            episode_id: "e1".to_string(),
+            error: "connection reset".to_string(),
+            retry_in_secs,
+        };
+        run(&mut app, &player, failed(Some(5))).await;
+        assert!(app.status_message.as_deref().unwrap().contains("retrying in 5s"));
+
+        run(&mut app, &player, Action::CancelDownload).await;
+        assert!(!app.downloads.is_active("e1"));
+        run(
+            &mut app,
+            &player,
+            Action::DownloadProgress {
+                episode_id: "e1".to_string(),
+                downloaded_bytes: 10,
+                total_bytes: 100,
+            },
+        )
+        .await;
+        run(&mut app, &player, failed(None)).await;
+        let download = &app.db.list_downloads()[0];
+        assert_eq!(download.status, DownloadStatus::Failed);
+        assert_eq!(download.error.as_deref(), Some("Cancelled"));
+        assert_eq!(download.downloaded_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn download_policy_deletes_done_and_evicts_over_quota() {
+        let (mut app, player) = queued_app("policy");
+        let dir = std::env::temp_dir().join(format!("pod-app-policy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for id in ["e1", "e2"] {
+            let path = dir.join(format!("{}.mp3", id));
+            std::fs::write(&path, b"audio").unwrap();
+            app.db.insert_download(id, path.to_str().unwrap(), false);
+            app.db.update_download_progress(id, 2_000_000, 2_000_000);
+            app.db.complete_download(id);
+        }
+        app.db.mark_download_played("e1");
+
+        // Over the quota: the download played least recently goes first.
+        app.db.set_config("download_quota_mb", "3");
+        run(&mut app, &player, Action::SyncComplete(Ok(()))).await;
+        assert_eq!(app.db.get_download_status("e2"), Some(DownloadStatus::Failed));
+        assert!(!dir.join("e2.mp3").exists());
+        assert!(app.db.get_download_path("e1").is_some());
+
+        // Marking an episode done deletes its download.
+        app.view = View::Queue(QueueState {
+            episodes: app.db.list_queue_episodes(),
+            selected: 0,
+            scroll_tick: 0,
+        });
+        run(&mut app, &player, Action::ToggleDone).await;
+        assert_eq!(app.db.get_download_status("e1"), None);
+        assert!(!dir.join("e1.mp3").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stopping_releases_the_player() {
+        let (mut app, player) = queued_app("stop");
+
+        run(&mut app, &player, Action::PlayQueue).await;
+        assert!(player.lock().await.is_some());
+        run(&mut app, &player, Action::StopPlayback).await;
+        assert!(app.now_playing.is_none());
+        assert!(player.lock().await.is_none());
+        assert_eq!(app.db.queue_ids(), ["e1", "e2"]);
+    }
+
+    #[tokio::test]
+    async fn podcast_settings_edit_into_a_patch() {
+        let (mut app, _) = queued_app("settings");
+        app.load_podcasts();
+        app.update(Action::ShowPodcastSettings);
+
+        // Intro skip can't go below zero; state wraps around.
+        app.update(Action::ListDown);
+        app.update(Action::AdjustSetting(-1));
+        app.update(Action::AdjustSetting(3));
+        for _ in 0..6 {
+            app.update(Action::ListDown);
+        }
+        app.update(Action::AdjustSetting(-1));
+
+        // Enter edits the title; clearing it drops the override.
+        app.update(Action::ListUp);
+        app.update(Action::EditSettingText);
+        for ch in "New".chars() {
+            app.update(Action::SettingsType(ch));
+        }
+        app.update(Action::EditSettingText);
+
+        let View::PodcastSettings(ref s) = app.view else { panic!("not in settings") };
+        assert_eq!(s.settings.skip_intro_secs, 15);
+        assert_eq!(s.settings.state, SubscriptionState::Archived);
+        let patch = app.db.get_podcast_settings("p1").diff(&s.settings);
+        assert_eq!(patch.skip_intro_secs, Some(15));
+        assert_eq!(patch.title_override.as_deref(), Some("New"));
+        assert_eq!(patch.playback_speed, None);
+
+        app.update(Action::EditSettingText);
+        for _ in 0..3 {
+            app.update(Action::SettingsBackspace);
+        }
+        app.update(Action::EditSettingText);
+        let View::PodcastSettings(ref s) = app.view else { panic!("not in settings") };
+        assert_eq!(s.settings.title_override, None);
+    }
+}
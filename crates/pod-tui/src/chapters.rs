@@ -0,0 +1,74 @@
+//! Chapter navigation for the now-playing episode.
+
+use pod_model::Chapter;
+
+/// Going to the previous chapter this soon after a chapter starts skips to
+/// the one before it instead of restarting the current one.
+const RESTART_GRACE_SECS: f64 = 3.0;
+
+/// The chapter playing at `position`, assuming chapters are sorted by start.
+pub fn current(chapters: &[Chapter], position: f64) -> Option<&Chapter> {
+    chapters.iter().rev().find(|c| c.start_time <= position)
+}
+
+/// Start of the chapter after the one playing at `position`.
+pub fn next_start(chapters: &[Chapter], position: f64) -> Option<f64> {
+    chapters
+        .iter()
+        .map(|c| c.start_time)
+        .find(|start| *start > position)
+}
+
+/// Where "previous chapter" should seek to: the start of the current
+/// chapter, or of the one before it when the current one has only just
+/// started.
+pub fn previous_start(chapters: &[Chapter], position: f64) -> Option<f64> {
+    let index = chapters.iter().rposition(|c| c.start_time <= position)?;
+    let start = chapters[index].start_time;
+    if index > 0 && position - start < RESTART_GRACE_SECS {
+        Some(chapters[index - 1].start_time)
+    } else {
+        Some(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapters() -> Vec<Chapter> {
+        [(0.0, "Intro"), (60.0, "Topic"), (300.0, "Outro")]
+            .into_iter()
+            .map(|(start_time, title)| Chapter {
+                start_time,
+                end_time: None,
+                title: title.to_string(),
+                img: None,
+                url: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn current_chapter() {
+        let chapters = chapters();
+        assert_eq!(current(&chapters, 0.0).unwrap().title, "Intro");
+        assert_eq!(current(&chapters, 299.0).unwrap().title, "Topic");
+        assert_eq!(current(&chapters, 1000.0).unwrap().title, "Outro");
+        assert!(current(&[], 10.0).is_none());
+    }
+
+    #[test]
+    fn next_and_previous() {
+        let chapters = chapters();
+        assert_eq!(next_start(&chapters, 30.0), Some(60.0));
+        assert_eq!(next_start(&chapters, 300.0), None);
+
+        // Well into a chapter: restart it.
+        assert_eq!(previous_start(&chapters, 120.0), Some(60.0));
+        // Just after it started: go to the one before.
+        assert_eq!(previous_start(&chapters, 61.0), Some(0.0));
+        // The first chapter can only restart.
+        assert_eq!(previous_start(&chapters, 1.0), Some(0.0));
+    }
+}
@@ -0,0 +1,523 @@
+//! Non-interactive subcommands of the `pod` binary, for scripts, cron jobs
+//! and status bars. They work on the same local database as the TUI.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use pod_model::PlaylistFilter;
+use serde_json::Value;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::api_client::ApiClient;
+use crate::app::{Action, App, EpisodeRow};
+use crate::daemon::DaemonClient;
+use crate::event::{self, PlayerHandle};
+use crate::local_db::LocalDb;
+use crate::player::Backend;
+use crate::ui::text::format_time;
+
+pub const USAGE: &str = "\
+Usage: pod [COMMAND]
+
+Without a command, pod starts the terminal UI.
+
+Commands:
+  sync                      Pull changes from the server and push local ones
+  ls podcasts [--json]      List subscriptions
+  ls inbox [--json] [-n N]  List the newest unfinished episodes (default 50)
+  ls queue [--json]         List the Up Next queue
+  add <feed-url>            Subscribe to a podcast
+  play <episode-id>         Play an episode, in the daemon if one is running,
+                            otherwise here until it ends or Ctrl-C
+  status [--json]           Show what the daemon is playing
+  pause                     Pause or resume the daemon's playback
+  stop                      Stop the daemon's playback
+  daemon                    Run in the background, controlled over a socket
+  mark-done <episode-id>... Mark episodes as done
+  download <episode-id>...  Download episodes for offline playback
+  help                      Show this message
+
+Listings print one tab-separated line per item, starting with its id.";
+
+const DEFAULT_INBOX_LIMIT: i64 = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Tui,
+    Help,
+    Sync,
+    List { listing: Listing, json: bool, limit: i64 },
+    Add(String),
+    Play(String),
+    MarkDone(Vec<String>),
+    Download(Vec<String>),
+    Daemon,
+    Status { json: bool },
+    Pause,
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Listing {
+    Podcasts,
+    Inbox,
+    Queue,
+}
+
+impl Command {
+    /// Parse the arguments after the program name.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let Some((command, rest)) = args.split_first() else {
+            return Ok(Command::Tui);
+        };
+        let command = match command.as_str() {
+            "help" | "-h" | "--help" => Command::Help,
+            "sync" => Command::Sync,
+            "ls" => parse_list(rest)?,
+            "add" => Command::Add(single_arg(rest, "a feed URL")?),
+            "play" => Command::Play(single_arg(rest, "an episode id")?),
+            "mark-done" => Command::MarkDone(ids(rest)?),
+            "download" => Command::Download(ids(rest)?),
+            "daemon" => Command::Daemon,
+            "status" => match rest {
+                [flag] if flag == "--json" => return Ok(Command::Status { json: true }),
+                _ => Command::Status { json: false },
+            },
+            "pause" => Command::Pause,
+            "stop" => Command::Stop,
+            other => return Err(format!("unknown command {:?}", other)),
+        };
+        // The list and id commands check their own arguments.
+        let takes_args = matches!(
+            command,
+            Command::List { .. }
+                | Command::Add(_)
+                | Command::Play(_)
+                | Command::MarkDone(_)
+                | Command::Download(_)
+        );
+        if !takes_args && !rest.is_empty() {
+            return Err(format!("unexpected argument {:?}", rest[0]));
+        }
+        Ok(command)
+    }
+}
+
+fn parse_list(args: &[String]) -> Result<Command, String> {
+    let mut listing = None;
+    let mut json = false;
+    let mut limit = DEFAULT_INBOX_LIMIT;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-n" | "--limit" => {
+                limit = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("{} needs a positive number", arg))?;
+            }
+            "podcasts" if listing.is_none() => listing = Some(Listing::Podcasts),
+            "inbox" if listing.is_none() => listing = Some(Listing::Inbox),
+            "queue" if listing.is_none() => listing = Some(Listing::Queue),
+            other => return Err(format!("unexpected argument {:?}", other)),
+        }
+    }
+    let listing = listing.ok_or("ls needs one of podcasts, inbox or queue")?;
+    Ok(Command::List { listing, json, limit })
+}
+
+fn single_arg(args: &[String], what: &str) -> Result<String, String> {
+    match args {
+        [arg] => Ok(arg.clone()),
+        [] => Err(format!("expected {}", what)),
+        [_, extra, ..] => Err(format!("unexpected argument {:?}", extra)),
+    }
+}
+
+fn ids(args: &[String]) -> Result<Vec<String>, String> {
+    if args.is_empty() {
+        return Err("expected at least one episode id".to_string());
+    }
+    Ok(args.to_vec())
+}
+
+/// Run a subcommand, writing its output to `out`.
+pub async fn run(
+    command: Command,
+    db: LocalDb,
+    backend: Backend,
+    socket: &Path,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    match run_command(command, db, backend, socket, out).await {
+        // Whatever reads the output, like `head`, may stop early.
+        Err(e) if e
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+async fn run_command(
+    command: Command,
+    db: LocalDb,
+    backend: Backend,
+    socket: &Path,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    match command {
+        Command::Tui | Command::Daemon => unreachable!("{:?} isn't a one-off command", command),
+        Command::Help => writeln!(out, "{}", USAGE)?,
+        Command::Sync => sync(&db).await?,
+        Command::List { listing, json, limit } => list(&db, listing, json, limit, out)?,
+        Command::Add(feed_url) => {
+            let client = ApiClient::from_db(&db)?;
+            let podcast = client.add_podcast(&feed_url).await?;
+            // Fetch the new show's episodes.
+            sync(&db).await?;
+            writeln!(out, "{}\t{}", podcast.id, podcast.title)?;
+        }
+        Command::Play(id) => match DaemonClient::connect(socket).await {
+            Some(daemon) => {
+                let episode = find_episode(&db, &id)?;
+                daemon.call("play", serde_json::json!({ "episode_id": episode.id })).await?;
+                print_playing(&episode, out)?;
+            }
+            None => play(db, backend, &id, out).await?,
+        },
+        Command::MarkDone(ids) => {
+            let episodes = find_episodes(&db, &ids)?;
+            for episode in &episodes {
+                db.upsert_progress(&episode.id, episode.progress, true, true);
+            }
+            event::push_dirty_progress(db.path()).await?;
+        }
+        Command::Download(ids) => {
+            let daemon_running = DaemonClient::connect(socket).await.is_some();
+            download(db, &ids, !daemon_running, out).await?
+        }
+        Command::Status { json } => {
+            let status = daemon(socket).await?.status().await?;
+            if json {
+                serde_json::to_writer_pretty(&mut *out, &status)?;
+                writeln!(out)?;
+            } else if let Some(np) = &status.now_playing {
+                writeln!(
+                    out,
+                    "{} {}\t{} / {}\t{:.1}x",
+                    if np.state.paused { "⏸" } else { "▶" },
+                    np.episode_title,
+                    format_time(np.state.position_secs),
+                    format_time(np.state.duration_secs),
+                    np.speed,
+                )?;
+            } else {
+                writeln!(out, "Nothing playing")?;
+            }
+        }
+        Command::Pause => {
+            daemon(socket).await?.call("toggle_pause", Value::Null).await?;
+        }
+        Command::Stop => {
+            daemon(socket).await?.call("stop", Value::Null).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn daemon(socket: &Path) -> anyhow::Result<DaemonClient> {
+    DaemonClient::connect(socket)
+        .await
+        .context("no daemon running; start one with `pod daemon`")
+}
+
+async fn sync(db: &LocalDb) -> anyhow::Result<()> {
+    // Nothing shows sync progress here.
+    let (tx, _rx) = mpsc::unbounded_channel();
+    crate::sync::run_sync(db.path(), tx).await
+}
+
+fn list(
+    db: &LocalDb,
+    listing: Listing,
+    json: bool,
+    limit: i64,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    if listing == Listing::Podcasts {
+        let podcasts = db.list_podcasts();
+        if json {
+            serde_json::to_writer_pretty(&mut *out, &podcasts)?;
+            writeln!(out)?;
+        } else {
+            for p in &podcasts {
+                writeln!(out, "{}\t{}\t{}", p.id, p.title, p.feed_url)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let episodes = match listing {
+        Listing::Inbox => db.list_inbox_episodes(&PlaylistFilter::default(), limit, 0),
+        _ => db.list_queue_episodes(),
+    };
+    if json {
+        serde_json::to_writer_pretty(&mut *out, &episodes)?;
+        writeln!(out)?;
+    } else {
+        for e in &episodes {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}",
+                e.id,
+                e.publication_date.get(..10).unwrap_or(&e.publication_date),
+                e.podcast_title.as_deref().unwrap_or(""),
+                e.title,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn find_episode(db: &LocalDb, id: &str) -> anyhow::Result<EpisodeRow> {
+    db.get_episode(id)
+        .with_context(|| format!("no episode {:?}; try `pod sync`", id))
+}
+
+/// All of the episodes, or an error before anything is done with them.
+fn find_episodes(db: &LocalDb, ids: &[String]) -> anyhow::Result<Vec<EpisodeRow>> {
+    ids.iter().map(|id| find_episode(db, id)).collect()
+}
+
+/// Play an episode the way the TUI does, saving progress as it goes, until
+/// it ends or Ctrl-C stops it.
+async fn play(
+    db: LocalDb,
+    backend: Backend,
+    id: &str,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let episode = find_episode(&db, id)?;
+    print_playing(&episode, out)?;
+
+    let mut app = App::new(db);
+    app.backend = backend;
+    let player: PlayerHandle = Arc::new(Mutex::new(None));
+    let mut tick = tokio::time::interval(Duration::from_millis(250));
+    let _ = app.action_tx.send(Action::PlayEpisodeById(episode.id));
+
+    let result = loop {
+        tokio::select! {
+            Some(action) = app.action_rx.recv() => {
+                let outcome = match &action {
+                    Action::PlaybackFinished | Action::StopPlayback => Some(Ok(())),
+                    Action::PlaybackStarted(Err(e)) | Action::PlaybackError(e) => {
+                        Some(Err(anyhow::anyhow!("playback failed: {}", e)))
+                    }
+                    _ => None,
+                };
+                event::handle_async_action(&action, &mut app, &player);
+                app.update(action);
+                if let Some(outcome) = outcome {
+                    break outcome;
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                let _ = app.action_tx.send(Action::StopPlayback);
+            }
+            _ = tick.tick() => app.update(Action::Tick),
+        }
+    };
+
+    // Wait for the player to be stopped, then save the position.
+    if let Some(mut p) = player.lock().await.take() {
+        let _ = p.stop().await;
+    }
+    event::push_dirty_progress(app.db.path()).await?;
+    result
+}
+
+fn print_playing(episode: &EpisodeRow, out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "Playing {} — {}",
+        episode.podcast_title.as_deref().unwrap_or("?"),
+        episode.title
+    )
+}
+
+/// Download episodes the way the TUI does, returning once each one is
+/// complete or has failed for good. `manage_downloads` is cleared when a
+/// daemon applies the download policy instead.
+async fn download(
+    db: LocalDb,
+    ids: &[String],
+    manage_downloads: bool,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let episodes = find_episodes(&db, ids)?;
+
+    let mut app = App::new(db);
+    app.manage_downloads = manage_downloads;
+    let player: PlayerHandle = Arc::new(Mutex::new(None));
+    let mut pending = HashSet::new();
+    let mut failures = Vec::new();
+    for episode in &episodes {
+        if let Err(e) = event::start_download(&app, episode) {
+            failures.push(format!("{}: {}", episode.id, e));
+        }
+        if app.downloads.is_active(&episode.id) {
+            pending.insert(episode.id.clone());
+        }
+    }
+
+    while !pending.is_empty() {
+        let Some(action) = app.action_rx.recv().await else { break };
+        match &action {
+            Action::DownloadComplete(id) => {
+                pending.remove(id);
+            }
+            Action::DownloadFailed { episode_id, error, retry_in_secs: None }
+                if pending.contains(episode_id) =>
+            {
+                pending.remove(episode_id);
+                failures.push(format!("{}: {}", episode_id, error));
+            }
+            _ => {}
+        }
+        event::handle_async_action(&action, &mut app, &player);
+        app.update(action);
+    }
+
+    for episode in &episodes {
+        if let Some(path) = app.db.get_download_path(&episode.id) {
+            writeln!(out, "{}\t{}", episode.id, path)?;
+        }
+    }
+    if !failures.is_empty() {
+        bail!("downloads failed:\n  {}", failures.join("\n  "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pod_model::PodcastWithEpisodeStats;
+
+    use super::*;
+
+    fn parse(args: &str) -> Result<Command, String> {
+        let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+        Command::parse(&args)
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        assert_eq!(parse(""), Ok(Command::Tui));
+        assert_eq!(parse("sync"), Ok(Command::Sync));
+        assert_eq!(
+            parse("ls inbox --json -n 5"),
+            Ok(Command::List { listing: Listing::Inbox, json: true, limit: 5 })
+        );
+        assert_eq!(
+            parse("ls podcasts"),
+            Ok(Command::List { listing: Listing::Podcasts, json: false, limit: 50 })
+        );
+        assert_eq!(parse("add https://x/feed"), Ok(Command::Add("https://x/feed".into())));
+        assert_eq!(parse("mark-done a b"), Ok(Command::MarkDone(vec!["a".into(), "b".into()])));
+        assert_eq!(parse("daemon"), Ok(Command::Daemon));
+        assert_eq!(parse("status --json"), Ok(Command::Status { json: true }));
+        assert_eq!(parse("pause"), Ok(Command::Pause));
+
+        assert!(parse("ls").is_err());
+        assert!(parse("ls inbox queue").is_err());
+        assert!(parse("ls inbox -n 0").is_err());
+        assert!(parse("play").is_err());
+        assert!(parse("play a b").is_err());
+        assert!(parse("sync now").is_err());
+        assert!(parse("status --yaml").is_err());
+        assert!(parse("stop now").is_err());
+        assert!(parse("frobnicate").is_err());
+    }
+
+    #[test]
+    fn lists_for_scripts() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let published: chrono::DateTime<chrono::Utc> = "2024-01-02T03:04:05Z".parse().unwrap();
+        db.upsert_podcast(&PodcastWithEpisodeStats {
+            id: "p1".to_string(),
+            title: "Show".to_string(),
+            description: String::new(),
+            image_link: String::new(),
+            feed_url: "https://feed.example/p1".to_string(),
+            feed_type: "rss".to_string(),
+            created_at: published,
+            last_updated: published,
+            last_publication_date: Some(published),
+            feed_etag: None,
+            feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
+        });
+        db.upsert_episode(&pod_model::Episode {
+            id: "e1".to_string(),
+            podcast_id: "p1".to_string(),
+            title: "e1".to_string(),
+            summary: String::new(),
+            summary_type: "text/plain".to_string(),
+            content_encoded: String::new(),
+            content_encoded_type: "text/html".to_string(),
+            publication_date: published,
+            audio_url: "https://audio.example/e1.mp3".to_string(),
+            audio_type: "audio/mpeg".to_string(),
+            audio_duration: 100,
+            thumbnail_url: None,
+            created_at: published,
+            last_updated: published,
+            deleted_at: None,
+            season: None,
+            episode_number: None,
+            episode_type: None,
+            chapters_url: None,
+            chapters_type: None,
+        });
+        db.set_queue(&["e1".to_string()], false);
+        db.insert_download("e1", "/tmp/e1.mp3", false);
+        db.complete_download("e1");
+
+        let output = |listing, json| {
+            let mut out = Vec::new();
+            list(&db, listing, json, 10, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(output(Listing::Podcasts, false), "p1\tShow\thttps://feed.example/p1\n");
+        assert_eq!(output(Listing::Inbox, false), "e1\t2024-01-02\tShow\te1\n");
+        assert_eq!(output(Listing::Queue, false), "e1\t2024-01-02\tShow\te1\n");
+
+        let json = |listing| -> Value { serde_json::from_str(&output(listing, true)).unwrap() };
+        let podcasts = json(Listing::Podcasts);
+        assert_eq!(podcasts[0]["id"], "p1");
+        assert_eq!(podcasts[0]["feed_url"], "https://feed.example/p1");
+        let queue = json(Listing::Queue);
+        assert_eq!(queue.as_array().unwrap().len(), 1);
+        assert_eq!(queue[0]["id"], "e1");
+        assert_eq!(queue[0]["podcast_title"], "Show");
+        assert_eq!(queue[0]["progress"], 0);
+        assert_eq!(queue[0]["done"], false);
+        assert_eq!(queue[0]["download_status"], "complete");
+    }
+}
@@ -0,0 +1,452 @@
+//! Headless background daemon that owns the player, so playback outlives
+//! the terminal. It runs the same `App` state machine as the TUI, without
+//! views, and takes JSON-RPC 2.0 requests on a Unix socket: one JSON object
+//! per line each way. The TUI and CLI attach to it as clients.
+//!
+//! Methods: `status`, `play {episode_id}`, `play_queue`, `toggle_pause`,
+//! `stop`, `seek_forward`, `seek_backward`, `seek_to {position_secs}`,
+//! `next_chapter`, `previous_chapter`, `speed_up`, `speed_down`,
+//! `volume_up`, `volume_down`, `toggle_skip_silence`, `cycle_sleep_timer`,
+//! `sync` and `shutdown`.
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use crate::app::{Action, App, NowPlaying};
+use crate::event::{self, PlayerHandle};
+use crate::sleep_timer::SleepTimer;
+
+/// How often the daemon syncs with the server, besides on start.
+const SYNC_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// How long a client waits for the daemon to answer.
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Where the control socket lives: `$XDG_RUNTIME_DIR/pod/control.sock`, or
+/// the data directory on systems without a runtime directory.
+pub fn socket_path(data_dir: &Path) -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("pod").join("control.sock"),
+        None => data_dir.join("control.sock"),
+    }
+}
+
+/// Playback state reported by `status`.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Status {
+    pub now_playing: Option<NowPlaying>,
+    pub volume: i32,
+    pub skip_silence: bool,
+    pub sleep_timer: Option<SleepTimer>,
+    /// The daemon's latest status message, like the TUI's status bar.
+    pub message: Option<String>,
+}
+
+impl Status {
+    fn of(app: &App) -> Self {
+        Self {
+            now_playing: app.now_playing.clone(),
+            volume: app.volume,
+            skip_silence: app.skip_silence,
+            sleep_timer: app.sleep_timer,
+            message: app.status_message.clone(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct Request {
+    #[serde(default)]
+    jsonrpc: String,
+    /// Missing for notifications, which get no response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl Response {
+    fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(value) => (Some(value), None),
+            Err(e) => (None, Some(e)),
+        };
+        Self { jsonrpc: "2.0".to_string(), id, result, error }
+    }
+}
+
+/// The playback actions that take no parameters, by method name.
+fn simple_action(method: &str) -> Option<Action> {
+    Some(match method {
+        "play_queue" => Action::PlayQueue,
+        "toggle_pause" => Action::TogglePause,
+        "stop" => Action::StopPlayback,
+        "seek_forward" => Action::SeekForward,
+        "seek_backward" => Action::SeekBackward,
+        "next_chapter" => Action::NextChapter,
+        "previous_chapter" => Action::PreviousChapter,
+        "speed_up" => Action::SpeedUp,
+        "speed_down" => Action::SpeedDown,
+        "volume_up" => Action::VolumeUp,
+        "volume_down" => Action::VolumeDown,
+        "toggle_skip_silence" => Action::ToggleSkipSilence,
+        "cycle_sleep_timer" => Action::CycleSleepTimer,
+        "sync" => Action::RefreshSync,
+        _ => return None,
+    })
+}
+
+/// The method that asks the daemon for `action`, if the daemon carries it
+/// out rather than the TUI. The inverse of [`simple_action`], plus the
+/// actions that take parameters.
+pub fn remote_call(app: &App, action: &Action) -> Option<(&'static str, Value)> {
+    let method = match action {
+        Action::PlayEpisode => {
+            let episode = app.selected_episode()?;
+            return Some(("play", json!({ "episode_id": episode.id })));
+        }
+        Action::PlayEpisodeById(id) => return Some(("play", json!({ "episode_id": id }))),
+        Action::SeekTo(position) => {
+            return Some(("seek_to", json!({ "position_secs": position })));
+        }
+        Action::PlayQueue => "play_queue",
+        Action::TogglePause => "toggle_pause",
+        Action::StopPlayback => "stop",
+        Action::SeekForward => "seek_forward",
+        Action::SeekBackward => "seek_backward",
+        Action::NextChapter => "next_chapter",
+        Action::PreviousChapter => "previous_chapter",
+        Action::SpeedUp => "speed_up",
+        Action::SpeedDown => "speed_down",
+        Action::VolumeUp => "volume_up",
+        Action::VolumeDown => "volume_down",
+        Action::ToggleSkipSilence => "toggle_skip_silence",
+        Action::CycleSleepTimer => "cycle_sleep_timer",
+        _ => return None,
+    };
+    Some((method, Value::Null))
+}
+
+// ==============================================================================
+// Client
+// ==============================================================================
+
+/// Returned by [`DaemonClient::call`] when nothing listens on the socket,
+/// as opposed to a daemon that answered with an error or too slowly.
+#[derive(Debug)]
+pub struct DaemonGone;
+
+impl std::fmt::Display for DaemonGone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the daemon isn't running")
+    }
+}
+
+impl std::error::Error for DaemonGone {}
+
+/// A connection to a running daemon. Each call opens its own connection,
+/// so clones can be handed to spawned tasks.
+#[derive(Debug, Clone)]
+pub struct DaemonClient {
+    path: PathBuf,
+}
+
+impl DaemonClient {
+    /// The daemon listening at `path`, if one answers.
+    pub async fn connect(path: &Path) -> Option<Self> {
+        let client = Self { path: path.to_path_buf() };
+        client.status().await.ok()?;
+        Some(client)
+    }
+
+    pub async fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        tokio::time::timeout(CALL_TIMEOUT, self.call_inner(method, params))
+            .await
+            .context("the daemon didn't answer")?
+    }
+
+    async fn call_inner(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let stream = UnixStream::connect(&self.path)
+            .await
+            .map_err(|e| anyhow::Error::new(e).context(DaemonGone))?;
+        let (read, mut write) = stream.into_split();
+        let request = Request {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: method.to_string(),
+            params,
+        };
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        write.write_all(line.as_bytes()).await?;
+
+        let mut lines = BufReader::new(read).lines();
+        let line = lines.next_line().await?.context("the daemon hung up")?;
+        let response: Response = serde_json::from_str(&line).context("malformed response")?;
+        if let Some(error) = response.error {
+            bail!("{}", error.message);
+        }
+        Ok(response.result.unwrap_or(Value::Null))
+    }
+
+    pub async fn status(&self) -> anyhow::Result<Status> {
+        let status = self.call("status", Value::Null).await?;
+        Ok(serde_json::from_value(status)?)
+    }
+}
+
+// ==============================================================================
+// Server
+// ==============================================================================
+
+/// Listen on the control socket at `path`, replacing a stale one left by a
+/// daemon that didn't exit cleanly.
+pub async fn bind(path: &Path) -> anyhow::Result<UnixListener> {
+    if DaemonClient::connect(path).await.is_some() {
+        bail!("pod is already running in the background ({})", path.display());
+    }
+    if let Some(dir) = path.parent() {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::create_dir_all(dir)?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    let _ = std::fs::remove_file(path);
+    UnixListener::bind(path).with_context(|| format!("listen on {}", path.display()))
+}
+
+type Call = (Request, oneshot::Sender<Result<Value, RpcError>>);
+
+/// Run the daemon until it's told to shut down or gets SIGINT or SIGTERM,
+/// then stop playback, push progress and remove the socket.
+pub async fn serve(
+    mut app: App,
+    listener: UnixListener,
+    mpris: Option<Rc<mpris_server::Player>>,
+) -> anyhow::Result<()> {
+    let player: PlayerHandle = Arc::new(Mutex::new(None));
+    event::resume_downloads(&app);
+    event::spawn_progress_pusher(app.action_tx.clone());
+    spawn_sync_loop(app.action_tx.clone());
+
+    let (calls_tx, mut calls) = mpsc::unbounded_channel::<Call>();
+    let mut tick = tokio::time::interval(Duration::from_millis(250));
+    let mut terminate =
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+
+    loop {
+        tokio::select! {
+            Ok((stream, _)) = listener.accept() => {
+                tokio::spawn(handle_connection(stream, calls_tx.clone()));
+            }
+            Some((request, reply)) = calls.recv() => {
+                let shutdown = request.method == "shutdown";
+                let _ = reply.send(handle_request(&mut app, &player, request));
+                if shutdown {
+                    break;
+                }
+            }
+            Some(action) = app.action_rx.recv() => {
+                event::handle_async_action(&action, &mut app, &player);
+                app.update(action);
+            }
+            _ = tick.tick() => app.update(Action::Tick),
+            _ = tokio::signal::ctrl_c() => break,
+            _ = terminate.recv() => break,
+        }
+
+        if let Some(ref mpris) = mpris {
+            crate::mpris::update_from_app(mpris, &app).await;
+        }
+    }
+
+    if let Some(mut p) = player.lock().await.take() {
+        let _ = p.stop().await;
+    }
+    // Anything that can't be pushed now goes with the next sync.
+    let _ = event::push_dirty_progress(app.db.path()).await;
+    if let Some(path) = listener.local_addr()?.as_pathname() {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(())
+}
+
+/// Sync now and every [`SYNC_INTERVAL`].
+fn spawn_sync_loop(tx: mpsc::UnboundedSender<Action>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SYNC_INTERVAL);
+        loop {
+            interval.tick().await;
+            let _ = tx.send(Action::RefreshSync);
+        }
+    });
+}
+
+/// Read requests off one client connection, answering each in turn.
+async fn handle_connection(stream: UnixStream, calls: mpsc::UnboundedSender<Call>) {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let id = request.id.clone();
+                let (reply_tx, reply) = oneshot::channel();
+                if calls.send((request, reply_tx)).is_err() {
+                    return;
+                }
+                let Ok(result) = reply.await else { return };
+                // Notifications get no response.
+                let Some(id) = id else { continue };
+                Response::new(id, result)
+            }
+            Err(e) => Response::new(Value::Null, Err(RpcError::new(PARSE_ERROR, e.to_string()))),
+        };
+        let Ok(mut line) = serde_json::to_string(&response) else { return };
+        line.push('\n');
+        if write.write_all(line.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn handle_request(
+    app: &mut App,
+    player: &PlayerHandle,
+    request: Request,
+) -> Result<Value, RpcError> {
+    let action = match request.method.as_str() {
+        "status" => {
+            return serde_json::to_value(Status::of(app))
+                .map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()));
+        }
+        "shutdown" => return Ok(Value::Null),
+        "play" => {
+            let id = request.params["episode_id"]
+                .as_str()
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "play needs an episode_id"))?;
+            if app.db.get_episode(id).is_none() {
+                return Err(RpcError::new(INVALID_PARAMS, format!("no episode {:?}", id)));
+            }
+            Action::PlayEpisodeById(id.to_string())
+        }
+        "seek_to" => {
+            let position = request.params["position_secs"]
+                .as_f64()
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "seek_to needs a position_secs"))?;
+            Action::SeekTo(position)
+        }
+        method => simple_action(method).ok_or_else(|| {
+            RpcError::new(METHOD_NOT_FOUND, format!("unknown method {:?}", method))
+        })?,
+    };
+    event::handle_async_action(&action, app, player);
+    app.update(action);
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::queued_app;
+
+    #[test]
+    fn remote_calls_map_back_to_actions() {
+        let (app, _) = queued_app("daemon-methods");
+        for method in [
+            "play_queue",
+            "toggle_pause",
+            "stop",
+            "seek_forward",
+            "seek_backward",
+            "next_chapter",
+            "previous_chapter",
+            "speed_up",
+            "speed_down",
+            "volume_up",
+            "volume_down",
+            "toggle_skip_silence",
+            "cycle_sleep_timer",
+        ] {
+            let action = simple_action(method).unwrap();
+            assert_eq!(remote_call(&app, &action).map(|(m, _)| m), Some(method));
+        }
+        // Syncing stays with whoever asks for it.
+        assert!(remote_call(&app, &Action::RefreshSync).is_none());
+    }
+
+    #[tokio::test]
+    async fn clients_control_playback() {
+        let (app, _) = queued_app("daemon");
+        let path = std::env::temp_dir().join(format!("pod-daemon-{}.sock", std::process::id()));
+        let listener = bind(&path).await.unwrap();
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async {
+                let daemon = tokio::task::spawn_local(serve(app, listener, None));
+                let client = DaemonClient::connect(&path).await.unwrap();
+
+                client.call("play", json!({ "episode_id": "e2" })).await.unwrap();
+                client.call("toggle_pause", Value::Null).await.unwrap();
+                let mut status = client.status().await.unwrap();
+                for _ in 0..50 {
+                    if status.now_playing.as_ref().is_some_and(|np| np.state.paused) {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    status = client.status().await.unwrap();
+                }
+                let np = status.now_playing.expect("nothing playing");
+                assert_eq!(np.episode_id, "e2");
+                assert!(np.state.paused);
+
+                // Errors the daemon answers with don't mean it's gone.
+                let error = client.call("play", json!({ "episode_id": "nope" })).await;
+                let error = error.unwrap_err();
+                assert!(error.to_string().contains("no episode"));
+                assert!(!error.is::<DaemonGone>());
+                assert!(client.call("frobnicate", Value::Null).await.is_err());
+
+                client.call("shutdown", Value::Null).await.unwrap();
+                daemon.await.unwrap().unwrap();
+                assert!(!path.exists());
+                assert!(client.status().await.is_err_and(|e| e.is::<DaemonGone>()));
+            })
+            .await;
+    }
+}
@@ -0,0 +1,422 @@
+//! Background episode downloads: a few at a time, resuming partial files
+//! with HTTP Range requests and retrying with backoff. Workers report
+//! through the action channel; `App::update` records progress in the local
+//! database and applies the [`DownloadPolicy`].
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::AbortHandle;
+
+use crate::app::{Action, EpisodeRow};
+use crate::local_db::LocalDb;
+
+/// How many episodes download at once; the rest wait their turn.
+const MAX_CONCURRENT: usize = 3;
+
+/// Delays before retrying a failed download. It is given up on once these
+/// are used up.
+const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+    Duration::from_secs(120),
+];
+
+/// Minimum time between progress reports for one download.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest `download_max_age_days` honoured, about a century.
+const MAX_AGE_DAYS: i64 = 36_500;
+
+/// What is downloaded and deleted without being asked, on top of each
+/// podcast's `auto_download` setting. Kept in the local config table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadPolicy {
+    /// Download everything in the Up Next queue (`download_queue`).
+    pub queue: bool,
+    /// Delete an episode's download once it is marked done
+    /// (`download_delete_done`).
+    pub delete_done: bool,
+    /// Delete automatic downloads of episodes published more than this many
+    /// days ago, unless they are queued; 0 keeps them
+    /// (`download_max_age_days`).
+    pub max_age_days: i64,
+    /// Megabytes of downloads to keep at most, deleting the least recently
+    /// played first; 0 is no limit (`download_quota_mb`).
+    pub quota_mb: i64,
+}
+
+impl Default for DownloadPolicy {
+    fn default() -> Self {
+        Self {
+            queue: false,
+            delete_done: true,
+            max_age_days: 0,
+            quota_mb: 0,
+        }
+    }
+}
+
+impl DownloadPolicy {
+    pub fn load(db: &LocalDb) -> Self {
+        let default = Self::default();
+        let flag = |key: &str, default: bool| db.get_config(key).map_or(default, |v| v == "true");
+        let number = |key: &str, default: i64| {
+            db.get_config(key)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            queue: flag("download_queue", default.queue),
+            delete_done: flag("download_delete_done", default.delete_done),
+            max_age_days: number("download_max_age_days", default.max_age_days)
+                .clamp(0, MAX_AGE_DAYS),
+            quota_mb: number("download_quota_mb", default.quota_mb),
+        }
+    }
+
+    /// Publication date before which downloads are deleted, if there is one.
+    pub fn cutoff(&self) -> Option<String> {
+        if self.max_age_days <= 0 {
+            return None;
+        }
+        chrono::TimeDelta::try_days(self.max_age_days)
+            .and_then(|age| chrono::Utc::now().checked_sub_signed(age))
+            .map(|cutoff| cutoff.to_rfc3339())
+    }
+
+    pub fn quota_bytes(&self) -> Option<i64> {
+        (self.quota_mb > 0).then(|| self.quota_mb.saturating_mul(1_000_000))
+    }
+}
+
+/// Where an episode is downloaded to, keeping the extension of its audio
+/// URL when it has a plausible one. Download paths are stored as text, so
+/// this fails when the data directory isn't valid UTF-8.
+pub fn file_path(episode: &EpisodeRow) -> anyhow::Result<String> {
+    dirs::data_dir()
+        .expect("could not determine data directory")
+        .join("pod/downloads")
+        .join(format!("{}.{}", episode.id, file_extension(&episode.audio_url)))
+        .into_os_string()
+        .into_string()
+        .map_err(|path| anyhow::anyhow!("{} isn't valid UTF-8", Path::new(&path).display()))
+}
+
+fn file_extension(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    match path.rsplit_once('.') {
+        Some((_, ext)) if !ext.is_empty() && ext.len() <= 4 && !ext.contains('/') => ext,
+        _ => "mp3",
+    }
+}
+
+/// Runs downloads in background tasks. Cheap to clone; clones share the
+/// same downloads.
+#[derive(Clone)]
+pub struct DownloadManager {
+    client: reqwest::Client,
+    slots: Arc<Semaphore>,
+    /// Downloads queued or running, by episode id.
+    active: Arc<Mutex<HashMap<String, AbortHandle>>>,
+    tx: mpsc::UnboundedSender<Action>,
+}
+
+impl DownloadManager {
+    pub fn new(tx: mpsc::UnboundedSender<Action>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT)),
+            active: Default::default(),
+            tx,
+        }
+    }
+
+    /// Download `url` to `path`, picking up after whatever part of the file
+    /// is already there. Does nothing if the episode is already queued or
+    /// downloading.
+    pub fn start(&self, episode_id: &str, url: &str, path: &Path) {
+        let mut active = self.active.lock().expect("downloads lock poisoned");
+        if active.contains_key(episode_id) {
+            return;
+        }
+        let job = Job {
+            episode_id: episode_id.to_string(),
+            url: url.to_string(),
+            path: path.to_path_buf(),
+        };
+        let task = tokio::spawn(run(
+            job,
+            self.client.clone(),
+            Arc::clone(&self.slots),
+            self.tx.clone(),
+        ));
+        active.insert(episode_id.to_string(), task.abort_handle());
+    }
+
+    /// Whether the episode is queued or downloading. Reports from downloads
+    /// that were cancelled since are stale and should be ignored.
+    pub fn is_active(&self, episode_id: &str) -> bool {
+        self.active
+            .lock()
+            .expect("downloads lock poisoned")
+            .contains_key(episode_id)
+    }
+
+    /// Stop downloading an episode, keeping the partial file to resume
+    /// later. Returns whether it was queued or downloading.
+    pub fn cancel(&self, episode_id: &str) -> bool {
+        let task = self
+            .active
+            .lock()
+            .expect("downloads lock poisoned")
+            .remove(episode_id);
+        if let Some(task) = &task {
+            task.abort();
+        }
+        task.is_some()
+    }
+
+    /// Forget a download once its final report has been handled.
+    pub fn finished(&self, episode_id: &str) {
+        self.active
+            .lock()
+            .expect("downloads lock poisoned")
+            .remove(episode_id);
+    }
+}
+
+struct Job {
+    episode_id: String,
+    url: String,
+    path: PathBuf,
+}
+
+/// Why an attempt at a download failed.
+#[derive(Debug)]
+enum Failure {
+    /// Worth trying again, e.g. a dropped connection or a server error.
+    Transient(String),
+    /// The server refused the file; trying again won't help.
+    Permanent(String),
+}
+
+impl From<reqwest::Error> for Failure {
+    fn from(e: reqwest::Error) -> Self {
+        Failure::Transient(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for Failure {
+    fn from(e: std::io::Error) -> Self {
+        Failure::Transient(e.to_string())
+    }
+}
+
+/// Download a job once a slot is free, retrying failed attempts. Slots are
+/// only held while transferring, not while waiting to retry.
+async fn run(
+    job: Job,
+    client: reqwest::Client,
+    slots: Arc<Semaphore>,
+    tx: mpsc::UnboundedSender<Action>,
+) {
+    let mut retries = RETRY_DELAYS.iter();
+    loop {
+        let result = {
+            let Ok(_slot) = slots.acquire().await else { return };
+            fetch(&client, &job, &tx).await
+        };
+        let (error, retry_in) = match result {
+            Ok(()) => {
+                let _ = tx.send(Action::DownloadComplete(job.episode_id));
+                return;
+            }
+            Err(Failure::Transient(error)) => (error, retries.next()),
+            Err(Failure::Permanent(error)) => (error, None),
+        };
+        let _ = tx.send(Action::DownloadFailed {
+            episode_id: job.episode_id.clone(),
+            error,
+            retry_in_secs: retry_in.map(Duration::as_secs),
+        });
+        match retry_in {
+            Some(delay) => tokio::time::sleep(*delay).await,
+            None => return,
+        }
+    }
+}
+
+/// One attempt at a download, resuming from the end of the partial file.
+async fn fetch(
+    client: &reqwest::Client,
+    job: &Job,
+    tx: &mpsc::UnboundedSender<Action>,
+) -> Result<(), Failure> {
+    if let Some(dir) = job.path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let existing = match tokio::fs::metadata(&job.path).await {
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    };
+
+    let mut request = client.get(&job.url);
+    if existing > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+    }
+    let mut resp = request.send().await?;
+    let status = resp.status();
+    let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file doesn't match what the server has now; start
+        // over on the next attempt.
+        tokio::fs::remove_file(&job.path).await?;
+        return Err(Failure::Transient("partial download is out of date".to_string()));
+    }
+    if !status.is_success() {
+        let message = format!("server returned {}", status);
+        let transient = status.is_server_error()
+            || status == reqwest::StatusCode::REQUEST_TIMEOUT
+            || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+        return Err(if transient {
+            Failure::Transient(message)
+        } else {
+            Failure::Permanent(message)
+        });
+    }
+
+    // A server that ignores the Range header sends the whole file again.
+    let mut downloaded = if resumed { existing } else { 0 };
+    let total = resp.content_length().map_or(0, |len| len + downloaded);
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&job.path)
+        .await?;
+
+    let report = |downloaded: u64| {
+        let _ = tx.send(Action::DownloadProgress {
+            episode_id: job.episode_id.clone(),
+            downloaded_bytes: downloaded,
+            total_bytes: total,
+        });
+    };
+    report(downloaded);
+    let mut last_report = tokio::time::Instant::now();
+    while let Some(chunk) = resp.chunk().await? {
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            report(downloaded);
+            last_report = tokio::time::Instant::now();
+        }
+    }
+    file.flush().await?;
+    report(downloaded);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn policy_limits_survive_huge_config_values() {
+        let db = LocalDb::open(":memory:").unwrap();
+        db.set_config("download_max_age_days", &i64::MAX.to_string());
+        db.set_config("download_quota_mb", &i64::MAX.to_string());
+        let policy = DownloadPolicy::load(&db);
+        assert_eq!(policy.max_age_days, MAX_AGE_DAYS);
+        assert!(policy.cutoff().is_some());
+        assert_eq!(policy.quota_bytes(), Some(i64::MAX));
+
+        db.set_config("download_max_age_days", "-3");
+        assert_eq!(DownloadPolicy::load(&db).cutoff(), None);
+    }
+
+    #[test]
+    fn extension_comes_from_the_url_path() {
+        assert_eq!(file_extension("https://cdn.example/ep1.m4a"), "m4a");
+        assert_eq!(file_extension("https://cdn.example/ep1.mp3?token=a.b.c"), "mp3");
+        assert_eq!(file_extension("https://cdn.example/ep1.ogg#t=10"), "ogg");
+        assert_eq!(file_extension("https://cdn.example/download/ep1"), "mp3");
+        assert_eq!(file_extension("https://cdn.example/ep1.mpeg3audio"), "mp3");
+    }
+
+    /// Serve `body` to one request, honouring a `Range: bytes=N-` header.
+    /// Returns the address and the request's Range header, if any.
+    async fn serve_once(
+        body: &'static [u8],
+    ) -> (String, tokio::task::JoinHandle<Option<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut byte = [0];
+                socket.read_exact(&mut byte).await.unwrap();
+                request.push(byte[0]);
+            }
+            let range = String::from_utf8(request)
+                .unwrap()
+                .lines()
+                .find_map(|l| l.strip_prefix("range: bytes=").map(str::to_string))
+                .map(|r| r.trim_end_matches('-').to_string());
+            let start = range.as_deref().map_or(0, |r| r.parse().unwrap());
+            let status = if range.is_some() { "206 Partial Content" } else { "200 OK" };
+            let head = format!(
+                "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                status,
+                body.len() - start
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body[start..]).await.unwrap();
+            range
+        });
+        (format!("http://{}/ep.mp3", addr), server)
+    }
+
+    #[tokio::test]
+    async fn resumes_partial_file() {
+        let dir = std::env::temp_dir().join(format!("pod-downloads-{}", std::process::id()));
+        let path = dir.join("e1.mp3");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, b"01234").unwrap();
+
+        let (url, server) = serve_once(b"0123456789").await;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let job = Job {
+            episode_id: "e1".to_string(),
+            url,
+            path: path.clone(),
+        };
+        fetch(&reqwest::Client::new(), &job, &tx).await.unwrap();
+
+        assert_eq!(server.await.unwrap().as_deref(), Some("5"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"0123456789");
+        let mut last = None;
+        while let Ok(action) = rx.try_recv() {
+            last = Some(action);
+        }
+        assert!(matches!(
+            last,
+            Some(Action::DownloadProgress {
+                downloaded_bytes: 10,
+                total_bytes: 10,
+                ..
+            })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
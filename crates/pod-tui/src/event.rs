@@ -1,17 +1,22 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Context;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MediaKeyCode};
 use tokio::sync::Mutex;
 
+use pod_model::{Chapter, SubscriptionSettingsPatch, TranscriptSegment};
+
 use crate::api_client::ApiClient;
-use crate::app::{Action, App, View};
+use crate::app::{Action, App, EpisodeRow, View};
+use crate::daemon::{self, DaemonClient, DaemonGone};
+use crate::downloads;
 use crate::local_db::{DownloadStatus, LocalDb};
-use crate::player::{PlaybackState, Player};
+use crate::player::{start_offset, PlaybackBackend, PlaybackState};
 
-/// Shared handle to the mpv player, accessible from the event handler and
-/// the periodic poll task.
-pub type PlayerHandle = Arc<Mutex<Option<Player>>>;
+/// Shared handle to the playing episode's player, accessible from the event
+/// handler and the tasks it spawns.
+pub type PlayerHandle = Arc<Mutex<Option<Box<dyn PlaybackBackend>>>>;
 
 /// Map a key event to an action based on the current view.
 pub fn map_key(app: &App, key: KeyEvent) -> Option<Action> {
@@ -20,8 +25,8 @@ pub fn map_key(app: &App, key: KeyEvent) -> Option<Action> {
         return Some(Action::Quit);
     }
 
-    // Media keys work regardless of whether something is playing — mpv
-    // handles the no-op case gracefully.
+    // Media keys work regardless of whether something is playing — the
+    // player handlers do nothing when nothing is playing.
     match key.code {
         KeyCode::Media(MediaKeyCode::PlayPause) | KeyCode::Media(MediaKeyCode::Play) | KeyCode::Media(MediaKeyCode::Pause) => {
             return Some(Action::TogglePause);
@@ -29,16 +34,33 @@ pub fn map_key(app: &App, key: KeyEvent) -> Option<Action> {
         KeyCode::Media(MediaKeyCode::Stop) => return Some(Action::StopPlayback),
         KeyCode::Media(MediaKeyCode::FastForward) => return Some(Action::SeekForward),
         KeyCode::Media(MediaKeyCode::Rewind) => return Some(Action::SeekBackward),
+        KeyCode::Media(MediaKeyCode::TrackNext) => return Some(Action::NextChapter),
+        KeyCode::Media(MediaKeyCode::TrackPrevious) => return Some(Action::PreviousChapter),
         _ => {}
     }
 
-    // Global playback controls (available in any view when playing).
-    if app.now_playing.is_some() {
+    // Global playback controls (available in any view when playing, unless
+    // the keys are being typed into a search).
+    let typing = match &app.view {
+        View::Search(s) => s.editing,
+        View::PodcastSettings(s) => s.editing,
+        View::AddPodcast(_) => true,
+        _ => false,
+    };
+    if app.now_playing.is_some() && !typing {
         match key.code {
             KeyCode::Char(' ') => return Some(Action::TogglePause),
             KeyCode::Char('s') => return Some(Action::StopPlayback),
             KeyCode::Right => return Some(Action::SeekForward),
             KeyCode::Left => return Some(Action::SeekBackward),
+            KeyCode::Char(']') => return Some(Action::NextChapter),
+            KeyCode::Char('[') => return Some(Action::PreviousChapter),
+            KeyCode::Char('+') | KeyCode::Char('=') => return Some(Action::SpeedUp),
+            KeyCode::Char('-') => return Some(Action::SpeedDown),
+            KeyCode::Char('0') => return Some(Action::VolumeUp),
+            KeyCode::Char('9') => return Some(Action::VolumeDown),
+            KeyCode::Char('S') => return Some(Action::ToggleSkipSilence),
+            KeyCode::Char('z') => return Some(Action::CycleSleepTimer),
             _ => {}
         }
     }
@@ -64,8 +86,15 @@ pub fn map_key(app: &App, key: KeyEvent) -> Option<Action> {
             KeyCode::Char('p') => Some(Action::PlayEpisode),
             KeyCode::Char('d') => Some(Action::ToggleDone),
             KeyCode::Char('D') => Some(Action::DownloadEpisode),
+            KeyCode::Char('a') => Some(Action::Enqueue),
+            KeyCode::Char('A') => Some(Action::EnqueueNext),
             KeyCode::Char('r') => Some(Action::RefreshSync),
             KeyCode::Char('l') => Some(Action::NavigateBack),
+            KeyCode::Char('u') => Some(Action::ShowQueue),
+            KeyCode::Char('o') => Some(Action::ShowDownloads),
+            KeyCode::Char('/') => Some(Action::ShowSearch),
+            KeyCode::Tab => Some(Action::NextPlaylist),
+            KeyCode::BackTab => Some(Action::PreviousPlaylist),
             _ => None,
         },
         View::PodcastList(_) => match key.code {
@@ -77,6 +106,12 @@ pub fn map_key(app: &App, key: KeyEvent) -> Option<Action> {
             KeyCode::Enter => Some(Action::SelectPodcast),
             KeyCode::Char('r') => Some(Action::RefreshSync),
             KeyCode::Char('i') => Some(Action::ShowInbox),
+            KeyCode::Char('u') => Some(Action::ShowQueue),
+            KeyCode::Char('o') => Some(Action::ShowDownloads),
+            KeyCode::Char('/') => Some(Action::ShowSearch),
+            KeyCode::Char('a') => Some(Action::ShowAddPodcast),
+            KeyCode::Char('e') => Some(Action::ShowPodcastSettings),
+            KeyCode::Char('X') => Some(Action::Unsubscribe),
             _ => None,
         },
         View::EpisodeList(_) => match key.code {
@@ -90,7 +125,12 @@ pub fn map_key(app: &App, key: KeyEvent) -> Option<Action> {
             KeyCode::Char('p') => Some(Action::PlayEpisode),
             KeyCode::Char('d') => Some(Action::ToggleDone),
             KeyCode::Char('D') => Some(Action::DownloadEpisode),
+            KeyCode::Char('a') => Some(Action::Enqueue),
+            KeyCode::Char('A') => Some(Action::EnqueueNext),
             KeyCode::Char('r') => Some(Action::RefreshSync),
+            KeyCode::Char('u') => Some(Action::ShowQueue),
+            KeyCode::Char('o') => Some(Action::ShowDownloads),
+            KeyCode::Char('/') => Some(Action::ShowSearch),
             _ => None,
         },
         View::EpisodeDetail(_) => match key.code {
@@ -102,8 +142,137 @@ pub fn map_key(app: &App, key: KeyEvent) -> Option<Action> {
             KeyCode::PageUp => Some(Action::PageUp),
             KeyCode::Char('p') | KeyCode::Enter => Some(Action::PlayEpisode),
             KeyCode::Char('D') => Some(Action::DownloadEpisode),
+            KeyCode::Char('a') => Some(Action::Enqueue),
+            KeyCode::Char('A') => Some(Action::EnqueueNext),
+            KeyCode::Char('t') => Some(Action::ToggleTranscript),
+            KeyCode::Char('f') => Some(Action::FollowTranscript),
+            _ => None,
+        },
+        View::Search(s) if s.editing => match key.code {
+            KeyCode::Enter => Some(Action::SearchSubmit),
+            KeyCode::Backspace => Some(Action::SearchBackspace),
+            KeyCode::Esc => Some(Action::NavigateBack),
+            KeyCode::Down => Some(Action::ListDown),
+            KeyCode::Up => Some(Action::ListUp),
+            KeyCode::Char(ch) => Some(Action::SearchType(ch)),
+            _ => None,
+        },
+        View::Search(_) => match key.code {
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Esc => Some(Action::NavigateBack),
+            KeyCode::Char('/') => Some(Action::ShowSearch),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::ListDown),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::ListUp),
+            KeyCode::PageDown => Some(Action::PageDown),
+            KeyCode::PageUp => Some(Action::PageUp),
+            KeyCode::Enter => Some(Action::SelectEpisode),
+            KeyCode::Char('p') => Some(Action::PlayEpisode),
+            KeyCode::Char('d') => Some(Action::ToggleDone),
+            KeyCode::Char('D') => Some(Action::DownloadEpisode),
+            KeyCode::Char('a') => Some(Action::Enqueue),
+            KeyCode::Char('A') => Some(Action::EnqueueNext),
             _ => None,
         },
+        View::Queue(_) => match key.code {
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Esc => Some(Action::NavigateBack),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::ListDown),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::ListUp),
+            KeyCode::PageDown => Some(Action::PageDown),
+            KeyCode::PageUp => Some(Action::PageUp),
+            KeyCode::Char('J') => Some(Action::QueueMoveDown),
+            KeyCode::Char('K') => Some(Action::QueueMoveUp),
+            KeyCode::Enter => Some(Action::SelectEpisode),
+            KeyCode::Char('p') => Some(Action::PlayEpisode),
+            KeyCode::Char('d') => Some(Action::ToggleDone),
+            KeyCode::Char('D') => Some(Action::DownloadEpisode),
+            KeyCode::Char('x') => Some(Action::Dequeue),
+            KeyCode::Char('C') => Some(Action::ClearQueue),
+            KeyCode::Char('r') => Some(Action::RefreshSync),
+            KeyCode::Char('o') => Some(Action::ShowDownloads),
+            KeyCode::Char('/') => Some(Action::ShowSearch),
+            _ => None,
+        },
+        View::Downloads(_) => match key.code {
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Esc => Some(Action::NavigateBack),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::ListDown),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::ListUp),
+            KeyCode::PageDown => Some(Action::PageDown),
+            KeyCode::PageUp => Some(Action::PageUp),
+            KeyCode::Enter => Some(Action::SelectEpisode),
+            KeyCode::Char('p') => Some(Action::PlayEpisode),
+            KeyCode::Char('c') => Some(Action::CancelDownload),
+            KeyCode::Char('r') => Some(Action::RetryDownload),
+            KeyCode::Char('x') => Some(Action::DeleteDownload),
+            KeyCode::Char('u') => Some(Action::ShowQueue),
+            KeyCode::Char('/') => Some(Action::ShowSearch),
+            _ => None,
+        },
+        View::AddPodcast(_) => match key.code {
+            KeyCode::Enter => Some(Action::AddPodcastSubmit),
+            KeyCode::Backspace => Some(Action::AddPodcastBackspace),
+            KeyCode::Esc => Some(Action::NavigateBack),
+            KeyCode::Char(ch) => Some(Action::AddPodcastType(ch)),
+            _ => None,
+        },
+        View::PodcastSettings(s) if s.editing => match key.code {
+            KeyCode::Enter | KeyCode::Esc => Some(Action::EditSettingText),
+            KeyCode::Backspace => Some(Action::SettingsBackspace),
+            KeyCode::Char(ch) => Some(Action::SettingsType(ch)),
+            _ => None,
+        },
+        View::PodcastSettings(_) => match key.code {
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Esc => Some(Action::NavigateBack),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::ListDown),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::ListUp),
+            KeyCode::Char('l') | KeyCode::Right => Some(Action::AdjustSetting(1)),
+            KeyCode::Char('h') | KeyCode::Left => Some(Action::AdjustSetting(-1)),
+            KeyCode::Enter => Some(Action::EditSettingText),
+            KeyCode::Char('w') => Some(Action::SaveSettings),
+            _ => None,
+        },
+    }
+}
+
+/// Hand `action` to the daemon if one is running and it carries the action
+/// out, and handle it here otherwise.
+pub fn dispatch(action: Action, app: &mut App, player: &PlayerHandle) {
+    if let Some(ref client) = app.daemon {
+        if let Some((method, params)) = daemon::remote_call(app, &action) {
+            let client = client.clone();
+            let tx = app.action_tx.clone();
+            tokio::spawn(async move {
+                if let Err(e) = client.call(method, params).await {
+                    daemon_call_failed(&client, e, &tx).await;
+                }
+            });
+            return;
+        }
+    }
+    handle_async_action(&action, app, player);
+    app.update(action);
+}
+
+/// Report a failed call to the daemon. Errors the daemon answered with, and
+/// calls it was too busy to answer, are shown; the app only stops relying on
+/// the daemon once it can't be reached twice in a row, so two players never
+/// run at once.
+async fn daemon_call_failed(
+    client: &DaemonClient,
+    error: anyhow::Error,
+    tx: &tokio::sync::mpsc::UnboundedSender<Action>,
+) {
+    if !error.is::<DaemonGone>() {
+        let _ = tx.send(Action::DaemonError(error.to_string()));
+        return;
+    }
+    match client.status().await {
+        Err(e) if e.is::<DaemonGone>() => {
+            let _ = tx.send(Action::DaemonLost(format!("{:#}", error)));
+        }
+        _ => {}
     }
 }
 
@@ -154,6 +323,15 @@ pub fn handle_async_action(action: &Action, app: &mut App, player: &PlayerHandle
                 }
             });
         }
+        Action::PushQueue => {
+            let db_path = app.db.path().to_string();
+
+            tokio::spawn(async move {
+                if let Err(e) = push_local_queue(&db_path).await {
+                    tracing::warn!("failed to push queue: {}", e);
+                }
+            });
+        }
         Action::RefreshSync => {
             let tx = app.action_tx.clone();
             let db_path = app.db.path().to_string();
@@ -167,200 +345,179 @@ pub fn handle_async_action(action: &Action, app: &mut App, player: &PlayerHandle
             });
         }
 
-        // -- Download actions --
+        Action::DaemonLost(_) => {
+            // Take over the background work the daemon did.
+            resume_downloads(app);
+            spawn_progress_pusher(app.action_tx.clone());
+        }
 
-        Action::DownloadEpisode => {
-            let episode = match &app.view {
-                View::EpisodeList(s) => s.episodes.get(s.selected).cloned(),
-                View::Inbox(s) => s.episodes.get(s.selected).cloned(),
-                View::EpisodeDetail(s) => Some(s.episode.clone()),
-                _ => None,
-            };
-            let Some(episode) = episode else { return };
-
-            // Skip if already downloaded or in progress.
-            match app.db.get_download_status(&episode.id) {
-                Some(DownloadStatus::Complete) | Some(DownloadStatus::Downloading) => return,
-                Some(DownloadStatus::Failed) | Some(DownloadStatus::Pending) => {
-                    // Clean up stale record so we can retry.
-                    app.db.delete_download(&episode.id);
+        Action::Tick => {
+            // Mirror what the daemon is playing.
+            let Some(client) = app.daemon.clone() else { return };
+            let tx = app.action_tx.clone();
+            tokio::spawn(async move {
+                match client.status().await {
+                    Ok(status) => {
+                        let _ = tx.send(Action::DaemonStatus(status));
+                    }
+                    Err(e) => daemon_call_failed(&client, e, &tx).await,
                 }
-                None => {}
-            }
+            });
+        }
 
-            // Derive file extension from the audio URL.
-            let ext = episode
-                .audio_url
-                .rsplit('.')
-                .next()
-                .and_then(|e| {
-                    let e = e.split('?').next().unwrap_or(e);
-                    if e.len() <= 4 { Some(e) } else { None }
-                })
-                .unwrap_or("mp3");
-
-            let downloads_dir = dirs::data_dir()
-                .expect("could not determine data directory")
-                .join("pod/downloads");
-            let file_path = downloads_dir.join(format!("{}.{}", episode.id, ext));
-
-            app.db
-                .insert_download(&episode.id, file_path.to_str().expect("valid UTF-8 path"));
+        // -- Podcast management --
 
+        Action::AddPodcastSubmit => {
+            let View::AddPodcast(ref mut s) = app.view else { return };
+            let feed_url = s.feed_url.trim().to_string();
+            if s.loading || feed_url.is_empty() {
+                return;
+            }
+            s.loading = true;
+            s.error = None;
             let tx = app.action_tx.clone();
-            let audio_url = episode.audio_url.clone();
-            let episode_id = episode.id.clone();
             let db_path = app.db.path().to_string();
 
             tokio::spawn(async move {
-                if let Err(e) = std::fs::create_dir_all(&downloads_dir) {
-                    let db = LocalDb::open(&db_path).expect("open local db");
-                    db.fail_download(&episode_id, &e.to_string());
-                    let _ = tx.send(Action::DownloadComplete(Err(format!(
-                        "Failed to create downloads dir: {}",
-                        e
-                    ))));
-                    return;
+                let result = async {
+                    let db = LocalDb::open(&db_path)?;
+                    let client = ApiClient::from_db(&db)?;
+                    client.add_podcast(&feed_url).await
                 }
+                .await;
+                let _ = tx.send(Action::PodcastAdded(
+                    result.map(|p| p.title).map_err(|e| e.to_string()),
+                ));
+            });
+        }
 
-                let client = reqwest::Client::new();
-                let resp = match client.get(&audio_url).send().await {
-                    Ok(r) => r,
-                    Err(e) => {
-                        let db = LocalDb::open(&db_path).expect("open local db");
-                        db.fail_download(&episode_id, &e.to_string());
-                        let _ = tx.send(Action::DownloadComplete(Err(format!(
-                            "Download failed: {}",
-                            e
-                        ))));
-                        return;
-                    }
-                };
-
-                let total = resp.content_length().unwrap_or(0);
-
-                let mut file = match tokio::fs::File::create(&file_path).await {
-                    Ok(f) => f,
-                    Err(e) => {
-                        let db = LocalDb::open(&db_path).expect("open local db");
-                        db.fail_download(&episode_id, &e.to_string());
-                        let _ = tx.send(Action::DownloadComplete(Err(format!(
-                            "Failed to create file: {}",
-                            e
-                        ))));
-                        return;
-                    }
-                };
-
-                use tokio::io::AsyncWriteExt;
-                use tokio_stream::StreamExt;
-                let mut stream = resp.bytes_stream();
-                let mut downloaded: u64 = 0;
-                let mut last_update = tokio::time::Instant::now();
-
-                while let Some(chunk) = stream.next().await {
-                    match chunk {
-                        Ok(bytes) => {
-                            if let Err(e) = file.write_all(&bytes).await {
-                                let db = LocalDb::open(&db_path).expect("open local db");
-                                db.fail_download(&episode_id, &e.to_string());
-                                let _ = tx.send(Action::DownloadComplete(Err(format!(
-                                    "Write error: {}",
-                                    e
-                                ))));
-                                let _ = tokio::fs::remove_file(&file_path).await;
-                                return;
-                            }
-                            downloaded += bytes.len() as u64;
-
-                            // Throttle progress updates to ~4/sec.
-                            if last_update.elapsed() > std::time::Duration::from_millis(250) {
-                                let db = LocalDb::open(&db_path).expect("open local db");
-                                db.update_download_progress(
-                                    &episode_id,
-                                    downloaded as i64,
-                                    total as i64,
-                                );
-                                let _ = tx.send(Action::DownloadProgress {
-                                    episode_id: episode_id.clone(),
-                                    downloaded_bytes: downloaded,
-                                    total_bytes: total,
-                                });
-                                last_update = tokio::time::Instant::now();
-                            }
-                        }
-                        Err(e) => {
-                            let db = LocalDb::open(&db_path).expect("open local db");
-                            db.fail_download(&episode_id, &e.to_string());
-                            let _ = tx.send(Action::DownloadComplete(Err(format!(
-                                "Download error: {}",
-                                e
-                            ))));
-                            let _ = tokio::fs::remove_file(&file_path).await;
-                            return;
-                        }
-                    }
-                }
+        Action::Unsubscribe => {
+            let View::PodcastList(ref s) = app.view else { return };
+            let Some(podcast) = s.podcasts.get(s.selected) else { return };
+            // The first press only asks for confirmation.
+            if s.confirm_unsubscribe.as_ref() != Some(&podcast.id) {
+                return;
+            }
+            let tx = app.action_tx.clone();
+            let podcast_id = podcast.id.clone();
+            let db_path = app.db.path().to_string();
 
-                let db = LocalDb::open(&db_path).expect("open local db");
-                db.complete_download(&episode_id);
-                let _ = tx.send(Action::DownloadComplete(Ok(episode_id)));
+            tokio::spawn(async move {
+                let result = async {
+                    let db = LocalDb::open(&db_path)?;
+                    let client = ApiClient::from_db(&db)?;
+                    client.unsubscribe(&podcast_id).await
+                }
+                .await;
+                let _ = tx.send(Action::Unsubscribed(
+                    result.map(|()| podcast_id).map_err(|e| e.to_string()),
+                ));
             });
         }
 
-        // -- Playback actions --
+        Action::SaveSettings => {
+            let View::PodcastSettings(ref mut s) = app.view else { return };
+            if s.saving {
+                return;
+            }
+            // Only send what changed, so edits made elsewhere survive.
+            let patch = app.db.get_podcast_settings(&s.podcast_id).diff(&s.settings);
+            s.saving = true;
+            let tx = app.action_tx.clone();
+            let podcast_id = s.podcast_id.clone();
+            let db_path = app.db.path().to_string();
 
-        Action::PlayEpisode => {
-            // Determine which episode to play from the current view.
-            let episode = match &app.view {
-                View::EpisodeList(s) => s.episodes.get(s.selected).cloned(),
-                View::Inbox(s) => s.episodes.get(s.selected).cloned(),
-                View::EpisodeDetail(s) => Some(s.episode.clone()),
-                _ => None,
-            };
-            let Some(episode) = episode else { return };
+            tokio::spawn(async move {
+                let result = async {
+                    let db = LocalDb::open(&db_path)?;
+                    let client = ApiClient::from_db(&db)?;
+                    client.update_podcast_settings(&podcast_id, &patch).await
+                }
+                .await;
+                let _ = tx.send(Action::SettingsSaved(
+                    result.map(|settings| (podcast_id, settings)).map_err(|e| e.to_string()),
+                ));
+            });
+        }
 
+        Action::SavePodcastSpeed { podcast_id, speed } => {
+            let patch = SubscriptionSettingsPatch {
+                playback_speed: Some(*speed),
+                ..Default::default()
+            };
             let tx = app.action_tx.clone();
-            let player = Arc::clone(player);
-            // Prefer local file if the episode has been downloaded.
-            let audio_source = app
-                .db
-                .get_download_path(&episode.id)
-                .unwrap_or_else(|| episode.audio_url.clone());
-            let start_pos = episode.progress;
+            let podcast_id = podcast_id.clone();
+            let db_path = app.db.path().to_string();
 
             tokio::spawn(async move {
-                // Stop any existing playback first.
-                {
-                    let mut guard = player.lock().await;
-                    if let Some(mut p) = guard.take() {
-                        let _ = p.stop().await;
-                    }
+                let result = async {
+                    let db = LocalDb::open(&db_path)?;
+                    let client = ApiClient::from_db(&db)?;
+                    client.update_podcast_settings(&podcast_id, &patch).await
                 }
-
-                match Player::start(&audio_source, start_pos).await {
-                    Ok(new_player) => {
-                        *player.lock().await = Some(new_player);
-                        let _ = tx.send(Action::PlaybackStarted(Ok(())));
-                    }
-                    Err(e) => {
-                        let _ = tx.send(Action::PlaybackStarted(Err(e.to_string())));
-                        return;
-                    }
+                .await;
+                if let Err(e) = result {
+                    let _ = tx.send(Action::PodcastSpeedFailed(e.to_string()));
                 }
+            });
+        }
+
+        // -- Download actions --
+
+        Action::DownloadEpisode => {
+            let Some(episode) = app.selected_episode().cloned() else { return };
+            if let Err(e) = start_download(app, &episode) {
+                let _ = app.action_tx.send(Action::DownloadNotStarted(e.to_string()));
+            }
+        }
 
-                // Send the NowPlaying info. We piggyback this on
-                // PlaybackUpdate with initial state.
-                let _ = tx.send(Action::PlaybackUpdate(PlaybackState {
-                    position_secs: start_pos,
-                    duration_secs: 0,
-                    paused: false,
-                    finished: false,
-                }));
+        Action::RetryDownload => {
+            let View::Downloads(ref s) = app.view else { return };
+            let Some(d) = s.downloads.get(s.selected) else { return };
+            if d.status == DownloadStatus::Complete || app.downloads.is_active(&d.episode.id) {
+                return;
+            }
+            app.db.retry_download(&d.episode.id);
+            app.downloads.start(&d.episode.id, &d.episode.audio_url, Path::new(&d.file_path));
+        }
+
+        Action::ToggleTranscript => {
+            let View::EpisodeDetail(ref s) = app.view else { return };
+            // Only fetch when the transcript is about to be shown for the
+            // first time; app.update flips show_transcript afterwards.
+            if s.show_transcript || s.transcript.is_some() {
+                return;
+            }
+            let tx = app.action_tx.clone();
+            let episode_id = s.episode.id.clone();
+            let db_path = app.db.path().to_string();
+
+            tokio::spawn(async move {
+                let segments = load_transcript(&db_path, &episode_id).await;
+                let _ = tx.send(Action::TranscriptLoaded {
+                    episode_id,
+                    segments,
+                });
             });
+        }
+
+        // -- Playback actions --
+
+        Action::PlayEpisode => {
+            // Determine which episode to play from the current view.
+            let Some(episode) = app.selected_episode().cloned() else { return };
+            start_playback(app, player, episode);
             // app.update(PlayEpisode) sets now_playing from the current
             // view state before this async task completes.
         }
+        Action::PlayEpisodeById(id) => {
+            let Some(episode) = app.db.get_episode(id) else { return };
+            start_playback(app, player, episode);
+        }
+        Action::PlayQueue => {
+            let Some(episode) = app.db.list_queue_episodes().into_iter().next() else { return };
+            start_playback(app, player, episode);
+        }
 
         Action::TogglePause => {
             let player = Arc::clone(player);
@@ -392,6 +549,90 @@ pub fn handle_async_action(action: &Action, app: &mut App, player: &PlayerHandle
             });
         }
 
+        Action::SeekTo(position) => {
+            let player = Arc::clone(player);
+            let position = *position;
+            tokio::spawn(async move {
+                let guard = player.lock().await;
+                if let Some(ref p) = *guard {
+                    let _ = p.seek_to(position).await;
+                }
+            });
+        }
+
+        Action::SetSpeed(speed) => {
+            let player = Arc::clone(player);
+            let speed = *speed;
+            tokio::spawn(async move {
+                let guard = player.lock().await;
+                if let Some(ref p) = *guard {
+                    let _ = p.set_speed(speed).await;
+                }
+            });
+        }
+
+        Action::SetVolume(volume) => {
+            let player = Arc::clone(player);
+            let volume = *volume;
+            tokio::spawn(async move {
+                let guard = player.lock().await;
+                if let Some(ref p) = *guard {
+                    let _ = p.set_volume(volume).await;
+                }
+            });
+        }
+
+        Action::SetSkipSilence(enabled) => {
+            let player = Arc::clone(player);
+            let enabled = *enabled;
+            tokio::spawn(async move {
+                let guard = player.lock().await;
+                if let Some(ref p) = *guard {
+                    let _ = p.set_skip_silence(enabled).await;
+                }
+            });
+        }
+
+        Action::SleepTimerFired => {
+            let tx = app.action_tx.clone();
+            let player = Arc::clone(player);
+            let db_path = app.db.path().to_string();
+            let volume = app.volume;
+            let Some(episode_id) = app.now_playing.as_ref().map(|np| np.episode_id.clone()) else {
+                return;
+            };
+            tokio::spawn(async move {
+                {
+                    let mut guard = player.lock().await;
+                    let Some(ref mut p) = *guard else { return };
+                    let _ = p.pause().await;
+                    let _ = p.set_volume(volume).await;
+                    // Save the exact position rather than the last update's.
+                    if let Some(position) = p.position().await.filter(|p| *p > 0) {
+                        if let Ok(db) = LocalDb::open(&db_path) {
+                            db.upsert_progress(&episode_id, position, false, true);
+                        }
+                    }
+                }
+                let result = push_dirty_progress(&db_path).await;
+                let _ = tx.send(Action::PushProgressComplete(result.map_err(|e| e.to_string())));
+            });
+        }
+
+        Action::PlaybackFinished | Action::PlaybackError(_) => {
+            let player = Arc::clone(player);
+            tokio::spawn(async move {
+                let mut guard = player.lock().await;
+                // The queue may already have started the next episode; only
+                // reap the player whose playback ended.
+                if guard.as_ref().is_some_and(|p| p.has_ended()) {
+                    if let Some(mut p) = guard.take() {
+                        let _ = p.stop().await;
+                    }
+                }
+            });
+        }
+
         Action::StopPlayback => {
             let player = Arc::clone(player);
             tokio::spawn(async move {
@@ -406,29 +647,176 @@ pub fn handle_async_action(action: &Action, app: &mut App, player: &PlayerHandle
     }
 }
 
-/// Spawn a background task that polls mpv for playback state every second
-/// and sends updates to the app.
-pub fn spawn_playback_poller(player: PlayerHandle, tx: tokio::sync::mpsc::UnboundedSender<Action>) {
+/// Restart downloads that were queued or running when the app last quit.
+pub fn resume_downloads(app: &App) {
+    for d in app.db.list_downloads() {
+        if matches!(d.status, DownloadStatus::Pending | DownloadStatus::Downloading) {
+            app.downloads.start(&d.episode.id, &d.episode.audio_url, Path::new(&d.file_path));
+        }
+    }
+}
+
+/// Download `episode` unless it's already downloaded or on its way.
+/// Returns whether a download was started, or why it couldn't be.
+pub fn start_download(app: &App, episode: &EpisodeRow) -> anyhow::Result<bool> {
+    if app.downloads.is_active(&episode.id) || app.db.get_download_path(&episode.id).is_some() {
+        return Ok(false);
+    }
+    match app.db.download_file_path(&episode.id) {
+        // Pick up a failed or cancelled download where it stopped.
+        Some(path) => {
+            app.db.retry_download(&episode.id);
+            app.downloads.start(&episode.id, &episode.audio_url, Path::new(&path));
+        }
+        None => {
+            let path = downloads::file_path(episode)?;
+            app.db.insert_download(&episode.id, &path, false);
+            app.downloads.start(&episode.id, &episode.audio_url, Path::new(&path));
+        }
+    }
+    Ok(true)
+}
+
+/// Stop whatever is playing and start `episode`, preferring a downloaded
+/// copy, then load its chapters.
+fn start_playback(app: &App, player: &PlayerHandle, episode: EpisodeRow) {
+    let tx = app.action_tx.clone();
+    let player = Arc::clone(player);
+    // Prefer local file if the episode has been downloaded.
+    let download_path = app.db.get_download_path(&episode.id);
+    let is_local = download_path.is_some();
+    if is_local {
+        app.db.mark_download_played(&episode.id);
+    }
+    let audio_source = download_path.unwrap_or_else(|| episode.audio_url.clone());
+    let settings = app.db.get_podcast_settings(&episode.podcast_id);
+    let start_pos = start_offset(episode.progress, &settings);
+    let episode_id = episode.id.clone();
+    let db_path = app.db.path().to_string();
+    let (volume, skip_silence) = (app.volume, app.skip_silence);
+    let backend = app.backend;
+
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
-        loop {
-            interval.tick().await;
+        // Stop any existing playback first.
+        {
             let mut guard = player.lock().await;
-            if let Some(ref mut p) = *guard {
-                let state = p.poll_state().await;
-                if state.finished {
-                    let _ = tx.send(Action::PlaybackFinished);
-                    if let Some(mut p) = guard.take() {
-                        let _ = p.stop().await;
-                    }
-                } else {
-                    let _ = tx.send(Action::PlaybackUpdate(state));
+            if let Some(mut p) = guard.take() {
+                let _ = p.stop().await;
+            }
+        }
+
+        match backend.start(&audio_source, start_pos, &settings, tx.clone()).await {
+            Ok(new_player) => {
+                // Speed comes from the show's settings; volume and skipping
+                // silence carry over from the last episode.
+                let _ = new_player.set_volume(volume).await;
+                if skip_silence {
+                    let _ = new_player.set_skip_silence(true).await;
                 }
+                *player.lock().await = Some(new_player);
+                let _ = tx.send(Action::PlaybackStarted(Ok(())));
+            }
+            Err(e) => {
+                let _ = tx.send(Action::PlaybackStarted(Err(e.to_string())));
+                return;
             }
         }
+
+        // Send the NowPlaying info. We piggyback this on
+        // PlaybackUpdate with initial state.
+        let _ = tx.send(Action::PlaybackUpdate(PlaybackState {
+            position_secs: start_pos,
+            duration_secs: 0,
+            paused: false,
+        }));
+
+        let mut chapters = load_chapters(&db_path, &episode_id).await;
+        if chapters.is_empty() && is_local {
+            chapters = embedded_chapters(&player).await;
+        }
+        if !chapters.is_empty() {
+            let _ = tx.send(Action::ChaptersLoaded {
+                episode_id,
+                chapters,
+            });
+        }
     });
 }
 
+/// Chapters for an episode from the local database, fetching and storing
+/// them from the server on first play. Failures just mean no chapters.
+async fn load_chapters(db_path: &str, episode_id: &str) -> Vec<Chapter> {
+    let Ok(db) = LocalDb::open(db_path) else {
+        return Vec::new();
+    };
+    let chapters = db.list_chapters(episode_id);
+    if !chapters.is_empty() {
+        return chapters;
+    }
+
+    let (Some(server_url), Some(token)) = (db.get_config("server_url"), db.get_config("auth_token"))
+    else {
+        return Vec::new();
+    };
+    let client = ApiClient::new(&server_url, Some(token));
+    match client.get_chapters(episode_id).await {
+        Ok(chapters) => {
+            db.replace_chapters(episode_id, &chapters);
+            chapters
+        }
+        Err(e) => {
+            tracing::warn!(episode_id, "failed to fetch chapters: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Transcript of an episode from the local database, fetching and storing
+/// it from the server the first time it is shown. Failures just mean no
+/// transcript.
+async fn load_transcript(db_path: &str, episode_id: &str) -> Vec<TranscriptSegment> {
+    let Ok(db) = LocalDb::open(db_path) else {
+        return Vec::new();
+    };
+    let segments = db.list_transcript(episode_id);
+    if !segments.is_empty() {
+        return segments;
+    }
+
+    let (Some(server_url), Some(token)) = (db.get_config("server_url"), db.get_config("auth_token"))
+    else {
+        return Vec::new();
+    };
+    let client = ApiClient::new(&server_url, Some(token));
+    match client.get_transcript(episode_id).await {
+        Ok(segments) => {
+            db.replace_transcript(episode_id, &segments);
+            segments
+        }
+        Err(e) => {
+            tracing::warn!(episode_id, "failed to fetch transcript: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Chapters embedded in a local download, as read by the player. They are
+/// only known once the file is loaded, so retry for a few seconds.
+async fn embedded_chapters(player: &PlayerHandle) -> Vec<Chapter> {
+    for _ in 0..10 {
+        if let Some(ref p) = *player.lock().await {
+            let chapters = p.chapter_list().await;
+            if !chapters.is_empty() {
+                return chapters;
+            }
+        } else {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    Vec::new()
+}
+
 /// Spawn a background task that pushes dirty progress to the server every
 /// 30 seconds.
 pub fn spawn_progress_pusher(tx: tokio::sync::mpsc::UnboundedSender<Action>) {
@@ -441,16 +829,17 @@ pub fn spawn_progress_pusher(tx: tokio::sync::mpsc::UnboundedSender<Action>) {
     });
 }
 
-/// Push all dirty local progress entries to the server.
-async fn push_dirty_progress(db_path: &str) -> anyhow::Result<usize> {
+/// Push the locally edited queue to the server.
+async fn push_local_queue(db_path: &str) -> anyhow::Result<()> {
     let db = LocalDb::open(db_path).context("open local database")?;
+    let client = ApiClient::from_db(&db)?;
+    crate::sync::push_queue(db_path, &client).await
+}
 
-    let server_url = db.get_config("server_url")
-        .ok_or_else(|| anyhow::anyhow!("no server_url configured"))?;
-    let token = db.get_config("auth_token")
-        .ok_or_else(|| anyhow::anyhow!("not logged in"))?;
-
-    let client = ApiClient::new(&server_url, Some(token));
+/// Push all dirty local progress entries to the server.
+pub async fn push_dirty_progress(db_path: &str) -> anyhow::Result<usize> {
+    let db = LocalDb::open(db_path).context("open local database")?;
+    let client = ApiClient::from_db(&db)?;
     let dirty = db.list_dirty_progress();
     let mut pushed = 0;
 
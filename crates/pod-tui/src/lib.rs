@@ -2,9 +2,14 @@
 
 pub mod api_client;
 pub mod app;
+pub mod chapters;
+pub mod cli;
+pub mod daemon;
+pub mod downloads;
 pub mod event;
 pub mod local_db;
 pub mod mpris;
 pub mod player;
+pub mod sleep_timer;
 pub mod sync;
 pub mod ui;
@@ -1,7 +1,10 @@
 use rusqlite::{params, Connection};
 
-use pod_model::PodcastWithEpisodeStats;
-use crate::app::EpisodeRow;
+use pod_model::{
+    Chapter, Playlist, PlaylistFilter, PodcastWithEpisodeStats, SubscriptionSettings,
+    TranscriptSegment,
+};
+use crate::app::{DownloadRow, EpisodeRow, SearchHit};
 
 const SCHEMA: &str = r#"
 CREATE TABLE IF NOT EXISTS config (
@@ -63,11 +66,82 @@ CREATE TABLE IF NOT EXISTS episode_download (
 );
 "#;
 
+/// Schema changes applied on top of `SCHEMA`, in order. `PRAGMA
+/// user_version` records how many have run, so only append to this list.
+const MIGRATIONS: &[&str] = &[
+    // Episode numbering and type from iTunes / Podcasting 2.0 tags.
+    "ALTER TABLE episode ADD COLUMN season INTEGER;
+     ALTER TABLE episode ADD COLUMN episode_number INTEGER;
+     ALTER TABLE episode ADD COLUMN episode_type TEXT;",
+    // Chapter markers fetched from the server.
+    "CREATE TABLE episode_chapter (
+         episode_id  TEXT NOT NULL REFERENCES episode(id),
+         position    INTEGER NOT NULL,
+         start_time  REAL NOT NULL,
+         end_time    REAL,
+         title       TEXT NOT NULL,
+         PRIMARY KEY (episode_id, position)
+     );",
+    // Transcript segments fetched from the server.
+    "CREATE TABLE episode_transcript_segment (
+         episode_id  TEXT NOT NULL REFERENCES episode(id),
+         position    INTEGER NOT NULL,
+         start_time  REAL NOT NULL,
+         end_time    REAL,
+         speaker     TEXT,
+         body        TEXT NOT NULL,
+         PRIMARY KEY (episode_id, position)
+     );",
+    // Full-text search index over episodes, keyed by episode rowid and
+    // maintained by upsert_episode. Filled by rebuild_search_index.
+    "CREATE VIRTUAL TABLE episode_fts USING fts5(
+         title, podcast_title, body,
+         tokenize = 'unicode61 remove_diacritics 2'
+     );",
+    // Up Next queue. Not tied to the episode table: the server may list
+    // episodes that haven't synced yet.
+    "CREATE TABLE queue_item (
+         episode_id  TEXT PRIMARY KEY,
+         position    INTEGER NOT NULL
+     );",
+    // Saved playlists, with their filter stored as JSON.
+    "CREATE TABLE playlist (
+         id          TEXT PRIMARY KEY,
+         position    INTEGER NOT NULL,
+         name        TEXT NOT NULL,
+         filter      TEXT NOT NULL
+     );",
+    // Per-subscription settings, stored as JSON.
+    "CREATE TABLE podcast_settings (
+         podcast_id  TEXT PRIMARY KEY,
+         settings    TEXT NOT NULL
+     );",
+    // When a download was last played from, to evict the least recently
+    // used ones first.
+    "ALTER TABLE episode_download ADD COLUMN last_played_at TEXT;",
+    // Whether the download policy started a download rather than the user,
+    // since only those are deleted for being old.
+    "ALTER TABLE episode_download ADD COLUMN automatic INTEGER NOT NULL DEFAULT 0;",
+    // The server's fetch state for each feed, to show which ones are failing.
+    "ALTER TABLE podcast ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0;
+     ALTER TABLE podcast ADD COLUMN last_error TEXT;
+     ALTER TABLE podcast ADD COLUMN next_fetch_at TEXT;",
+];
+
+/// `user_version` once the search index exists. Databases migrated from
+/// before it have their existing episodes indexed on open.
+const SEARCH_INDEX_VERSION: usize = 4;
+
+/// Markers around matched terms in search snippets.
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
 // ==============================================================================
 // Download tracking
 // ==============================================================================
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DownloadStatus {
     Pending,
     Downloading,
@@ -87,6 +161,56 @@ impl DownloadStatus {
 
 }
 
+/// Apply pending migrations, returning the version the database was at.
+fn migrate(conn: &Connection) -> rusqlite::Result<usize> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(version)
+}
+
+/// Text of an episode's description to index: the richer of its two
+/// descriptions with markup removed.
+fn search_body(summary: &str, content_encoded: &str) -> String {
+    let html = if content_encoded.is_empty() { summary } else { content_encoded };
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    text.replace("&amp;", "&")
+        .replace("&nbsp;", " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Turn what the user typed into an FTS5 query: every word must match, the
+/// last one as a prefix so results update while typing. Words are quoted so
+/// FTS5 syntax characters are taken literally.
+fn fts_query(input: &str) -> Option<String> {
+    let words: Vec<String> = input
+        .split_whitespace()
+        .map(|w| format!("\"{}\"", w.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", words.join(" ")))
+}
+
 pub struct LocalDb {
     conn: Connection,
     path: String,
@@ -102,11 +226,19 @@ impl LocalDb {
             Connection::open(path)?
         };
         conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+        // The daemon and the TUI or CLI write to the same database; wait for
+        // each other's transactions rather than failing.
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
         conn.execute_batch(SCHEMA)?;
-        Ok(Self {
+        let version = migrate(&conn)?;
+        let db = Self {
             conn,
             path: path.to_string(),
-        })
+        };
+        if version < SEARCH_INDEX_VERSION {
+            db.rebuild_search_index();
+        }
+        Ok(db)
     }
 
     pub fn path(&self) -> &str {
@@ -161,15 +293,26 @@ impl LocalDb {
             .expect("failed to set sync state");
     }
 
+    pub fn delete_sync_state(&self, key: &str) {
+        self.conn
+            .execute("DELETE FROM sync_state WHERE key = ?1", params![key])
+            .expect("failed to delete sync state");
+    }
+
     // ==========================================================================
     // Podcasts
     // ==========================================================================
 
     pub fn upsert_podcast(&self, p: &PodcastWithEpisodeStats) {
+        let old_title: Option<String> = self
+            .conn
+            .query_row("SELECT title FROM podcast WHERE id = ?1", params![p.id], |row| row.get(0))
+            .ok();
         self.conn
             .execute(
-                "INSERT INTO podcast (id, title, description, image_link, feed_url, feed_type, created_at, last_updated, last_publication_date)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                "INSERT INTO podcast (id, title, description, image_link, feed_url, feed_type, created_at, last_updated, last_publication_date,
+                                      consecutive_failures, last_error, next_fetch_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
                  ON CONFLICT(id) DO UPDATE SET
                      title = excluded.title,
                      description = excluded.description,
@@ -177,7 +320,10 @@ impl LocalDb {
                      feed_url = excluded.feed_url,
                      feed_type = excluded.feed_type,
                      last_updated = excluded.last_updated,
-                     last_publication_date = excluded.last_publication_date",
+                     last_publication_date = excluded.last_publication_date,
+                     consecutive_failures = excluded.consecutive_failures,
+                     last_error = excluded.last_error,
+                     next_fetch_at = excluded.next_fetch_at",
                 params![
                     p.id,
                     p.title,
@@ -188,18 +334,35 @@ impl LocalDb {
                     p.created_at.to_rfc3339(),
                     p.last_updated.to_rfc3339(),
                     p.last_publication_date.map(|d| d.to_rfc3339()),
+                    p.consecutive_failures,
+                    p.last_error,
+                    p.next_fetch_at.map(|d| d.to_rfc3339()),
                 ],
             )
             .expect("failed to upsert podcast");
+
+        if old_title.is_some_and(|t| t != p.title) {
+            self.conn
+                .execute(
+                    "UPDATE episode_fts SET podcast_title = ?2
+                     WHERE rowid IN (SELECT rowid FROM episode WHERE podcast_id = ?1)",
+                    params![p.id, p.title],
+                )
+                .expect("failed to update search index");
+        }
     }
 
     pub fn list_podcasts(&self) -> Vec<PodcastWithEpisodeStats> {
         let mut stmt = self
             .conn
             .prepare(
-                "SELECT id, title, description, image_link, feed_url, feed_type,
-                        created_at, last_updated, last_publication_date
-                 FROM podcast ORDER BY last_publication_date DESC NULLS LAST",
+                "SELECT p.id, COALESCE(json_extract(ps.settings, '$.title_override'), p.title),
+                        p.description, p.image_link, p.feed_url, p.feed_type,
+                        p.created_at, p.last_updated, p.last_publication_date,
+                        p.consecutive_failures, p.last_error, p.next_fetch_at
+                 FROM podcast p
+                 LEFT JOIN podcast_settings ps ON ps.podcast_id = p.id
+                 ORDER BY p.last_publication_date DESC NULLS LAST",
             )
             .expect("failed to prepare podcast query");
 
@@ -218,6 +381,13 @@ impl LocalDb {
                     .map(parse_datetime),
                 feed_etag: None,
                 feed_last_modified: None,
+                feed_update_interval_secs: None,
+                last_fetch_at: None,
+                last_success_at: None,
+                consecutive_failures: row.get(9)?,
+                last_error: row.get(10)?,
+                next_fetch_at: row.get::<_, Option<String>>(11)?.map(parse_datetime),
+                podcast_guid: None,
             })
         })
         .expect("failed to query podcasts")
@@ -225,6 +395,79 @@ impl LocalDb {
         .collect()
     }
 
+    /// Delete a podcast together with its episodes and their local progress
+    /// and download records. Returns the file paths of any downloads that
+    /// belonged to the podcast so the caller can remove them from disk.
+    pub fn delete_podcast(&self, podcast_id: &str) -> Vec<String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT ed.file_path FROM episode_download ed
+                 JOIN episode e ON e.id = ed.episode_id
+                 WHERE e.podcast_id = ?1",
+            )
+            .expect("failed to prepare download path query");
+        let paths = stmt
+            .query_map(params![podcast_id], |row| row.get(0))
+            .expect("failed to query download paths")
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .expect("failed to begin transaction");
+        for sql in [
+            "DELETE FROM episode_progress WHERE episode_id IN (SELECT id FROM episode WHERE podcast_id = ?1)",
+            "DELETE FROM episode_download WHERE episode_id IN (SELECT id FROM episode WHERE podcast_id = ?1)",
+            "DELETE FROM episode_chapter WHERE episode_id IN (SELECT id FROM episode WHERE podcast_id = ?1)",
+            "DELETE FROM episode_transcript_segment WHERE episode_id IN (SELECT id FROM episode WHERE podcast_id = ?1)",
+            "DELETE FROM episode_fts WHERE rowid IN (SELECT rowid FROM episode WHERE podcast_id = ?1)",
+            "DELETE FROM queue_item WHERE episode_id IN (SELECT id FROM episode WHERE podcast_id = ?1)",
+            "DELETE FROM podcast_settings WHERE podcast_id = ?1",
+            "DELETE FROM episode WHERE podcast_id = ?1",
+            "DELETE FROM podcast WHERE id = ?1",
+        ] {
+            tx.execute(sql, params![podcast_id])
+                .expect("failed to delete podcast");
+        }
+        tx.commit().expect("failed to commit podcast deletion");
+
+        paths
+    }
+
+    /// The user's settings for a podcast; the defaults if none have synced.
+    pub fn get_podcast_settings(&self, podcast_id: &str) -> SubscriptionSettings {
+        self.conn
+            .query_row(
+                "SELECT settings FROM podcast_settings WHERE podcast_id = ?1",
+                params![podcast_id],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+            .and_then(|settings| serde_json::from_str(&settings).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_podcast_settings(&self, podcast_id: &str, settings: &SubscriptionSettings) {
+        let settings = serde_json::to_string(settings).expect("settings serialize");
+        self.conn
+            .execute(
+                "INSERT INTO podcast_settings (podcast_id, settings) VALUES (?1, ?2)
+                 ON CONFLICT(podcast_id) DO UPDATE SET settings = excluded.settings",
+                params![podcast_id, settings],
+            )
+            .expect("failed to store podcast settings");
+    }
+
+    /// Remember the speed a podcast was last played at. Kept until the
+    /// show's settings next change on the server.
+    pub fn set_podcast_speed(&self, podcast_id: &str, speed: f32) {
+        let mut settings = self.get_podcast_settings(podcast_id);
+        settings.playback_speed = speed;
+        self.set_podcast_settings(podcast_id, &settings);
+    }
+
     // ==========================================================================
     // Episodes
     // ==========================================================================
@@ -232,19 +475,23 @@ impl LocalDb {
     pub fn upsert_episode(&self, e: &pod_model::Episode) {
         self.conn
             .execute(
-                "INSERT INTO episode (id, podcast_id, title, summary, summary_type, content_encoded, content_encoded_type, publication_date, audio_url, audio_type, audio_duration, thumbnail_url, created_at, last_updated)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                "INSERT INTO episode (id, podcast_id, title, summary, summary_type, content_encoded, content_encoded_type, publication_date, audio_url, audio_type, audio_duration, thumbnail_url, created_at, last_updated, season, episode_number, episode_type)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
                  ON CONFLICT(id) DO UPDATE SET
                      title = excluded.title,
                      summary = excluded.summary,
                      summary_type = excluded.summary_type,
                      content_encoded = excluded.content_encoded,
                      content_encoded_type = excluded.content_encoded_type,
+                     publication_date = excluded.publication_date,
                      audio_url = excluded.audio_url,
                      audio_type = excluded.audio_type,
                      audio_duration = excluded.audio_duration,
                      thumbnail_url = excluded.thumbnail_url,
-                     last_updated = excluded.last_updated",
+                     last_updated = excluded.last_updated,
+                     season = excluded.season,
+                     episode_number = excluded.episode_number,
+                     episode_type = excluded.episode_type",
                 params![
                     e.id,
                     e.podcast_id,
@@ -260,9 +507,151 @@ impl LocalDb {
                     e.thumbnail_url,
                     e.created_at.to_rfc3339(),
                     e.last_updated.to_rfc3339(),
+                    e.season,
+                    e.episode_number,
+                    e.episode_type,
                 ],
             )
             .expect("failed to upsert episode");
+        self.index_episode(&e.id, &search_body(&e.summary, &e.content_encoded));
+    }
+
+    /// (Re-)index an episode for search. The index shares the episode
+    /// table's rowids, which upserts keep stable.
+    fn index_episode(&self, episode_id: &str, body: &str) {
+        self.conn
+            .execute(
+                "DELETE FROM episode_fts WHERE rowid = (SELECT rowid FROM episode WHERE id = ?1)",
+                params![episode_id],
+            )
+            .expect("failed to remove episode from search index");
+        self.conn
+            .execute(
+                "INSERT INTO episode_fts (rowid, title, podcast_title, body)
+                 SELECT e.rowid, e.title, COALESCE(p.title, ''), ?2
+                 FROM episode e
+                 LEFT JOIN podcast p ON p.id = e.podcast_id
+                 WHERE e.id = ?1",
+                params![episode_id, body],
+            )
+            .expect("failed to index episode");
+    }
+
+    /// Index every episode from scratch.
+    fn rebuild_search_index(&self) {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, summary, content_encoded FROM episode")
+            .expect("episode text query is valid static SQL");
+        let episodes: Vec<(String, String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .expect("episode text query execution")
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .expect("failed to begin transaction");
+        tx.execute("DELETE FROM episode_fts", [])
+            .expect("failed to clear search index");
+        for (id, summary, content_encoded) in &episodes {
+            self.index_episode(id, &search_body(summary, content_encoded));
+        }
+        tx.commit().expect("failed to commit search index");
+    }
+
+    /// Episodes matching a search typed by the user, best matches first,
+    /// with a snippet of the matching text. Matches are wrapped in
+    /// [`MATCH_START`] and [`MATCH_END`].
+    pub fn search_episodes(&self, input: &str, limit: i64) -> Vec<SearchHit> {
+        let Some(query) = fts_query(input) else {
+            return Vec::new();
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT e.id, e.podcast_id, e.title, e.publication_date,
+                        e.audio_url, e.audio_duration,
+                        e.summary, e.content_encoded,
+                        COALESCE(ep.progress, 0), COALESCE(ep.done, 0),
+                        COALESCE(json_extract(ps.settings, '$.title_override'), p.title),
+                        ed.status,
+                        e.season, e.episode_number, e.episode_type,
+                        snippet(episode_fts, 2, char(2), char(3), '…', 16)
+                 FROM episode_fts
+                 JOIN episode e ON e.rowid = episode_fts.rowid
+                 JOIN podcast p ON p.id = e.podcast_id
+                 LEFT JOIN podcast_settings ps ON ps.podcast_id = e.podcast_id
+                 LEFT JOIN episode_progress ep ON ep.episode_id = e.id
+                 LEFT JOIN episode_download ed ON ed.episode_id = e.id
+                 WHERE episode_fts MATCH ?1
+                 ORDER BY bm25(episode_fts, 10.0, 5.0, 1.0)
+                 LIMIT ?2",
+            )
+            .expect("search query is valid static SQL");
+
+        stmt.query_map(params![query, limit], |row| {
+            Ok(SearchHit {
+                episode: EpisodeRow {
+                    id: row.get(0)?,
+                    podcast_id: row.get(1)?,
+                    title: row.get(2)?,
+                    publication_date: row.get(3)?,
+                    audio_url: row.get(4)?,
+                    audio_duration: row.get(5)?,
+                    summary: row.get(6)?,
+                    content_encoded: row.get(7)?,
+                    progress: row.get(8)?,
+                    done: row.get::<_, i32>(9)? != 0,
+                    podcast_title: row.get(10)?,
+                    download_status: row
+                        .get::<_, Option<String>>(11)?
+                        .map(|s| DownloadStatus::from_str(&s)),
+                    season: row.get(12)?,
+                    episode_number: row.get(13)?,
+                    episode_type: row.get(14)?,
+                },
+                snippet: row.get(15)?,
+            })
+        })
+        .expect("search query execution")
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
+    /// Delete an episode together with its local progress and download
+    /// record. Returns the download's file path, if any, so the caller can
+    /// remove it from disk.
+    pub fn delete_episode(&self, episode_id: &str) -> Option<String> {
+        let path = self
+            .conn
+            .query_row(
+                "SELECT file_path FROM episode_download WHERE episode_id = ?1",
+                params![episode_id],
+                |row| row.get(0),
+            )
+            .ok();
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .expect("failed to begin transaction");
+        for sql in [
+            "DELETE FROM episode_progress WHERE episode_id = ?1",
+            "DELETE FROM episode_download WHERE episode_id = ?1",
+            "DELETE FROM episode_chapter WHERE episode_id = ?1",
+            "DELETE FROM episode_transcript_segment WHERE episode_id = ?1",
+            "DELETE FROM episode_fts WHERE rowid = (SELECT rowid FROM episode WHERE id = ?1)",
+            "DELETE FROM queue_item WHERE episode_id = ?1",
+            "DELETE FROM episode WHERE id = ?1",
+        ] {
+            tx.execute(sql, params![episode_id])
+                .expect("failed to delete episode");
+        }
+        tx.commit().expect("failed to commit episode deletion");
+
+        path
     }
 
     pub fn list_episodes(&self, podcast_id: &str) -> Vec<EpisodeRow> {
@@ -273,7 +662,8 @@ impl LocalDb {
                         e.audio_url, e.audio_duration,
                         e.summary, e.content_encoded,
                         COALESCE(ep.progress, 0), COALESCE(ep.done, 0),
-                        ed.status
+                        ed.status,
+                        e.season, e.episode_number, e.episode_type
                  FROM episode e
                  LEFT JOIN episode_progress ep ON ep.episode_id = e.id
                  LEFT JOIN episode_download ed ON ed.episode_id = e.id
@@ -298,6 +688,9 @@ impl LocalDb {
                 download_status: row
                     .get::<_, Option<String>>(10)?
                     .map(|s| DownloadStatus::from_str(&s)),
+                season: row.get(11)?,
+                episode_number: row.get(12)?,
+                episode_type: row.get(13)?,
             })
         })
         .expect("failed to query episodes")
@@ -305,9 +698,66 @@ impl LocalDb {
         .collect()
     }
 
-    /// List episodes across all podcasts, excluding done episodes, sorted by
-    /// publication date descending. Used for the inbox view.
-    pub fn list_inbox_episodes(&self, limit: i64, offset: i64) -> Vec<EpisodeRow> {
+    /// A single episode, with its podcast's title.
+    pub fn get_episode(&self, episode_id: &str) -> Option<EpisodeRow> {
+        self.conn
+            .query_row(
+                "SELECT e.id, e.podcast_id, e.title, e.publication_date,
+                        e.audio_url, e.audio_duration,
+                        e.summary, e.content_encoded,
+                        COALESCE(ep.progress, 0), COALESCE(ep.done, 0),
+                        COALESCE(json_extract(ps.settings, '$.title_override'), p.title),
+                        ed.status,
+                        e.season, e.episode_number, e.episode_type
+                 FROM episode e
+                 JOIN podcast p ON p.id = e.podcast_id
+                 LEFT JOIN podcast_settings ps ON ps.podcast_id = e.podcast_id
+                 LEFT JOIN episode_progress ep ON ep.episode_id = e.id
+                 LEFT JOIN episode_download ed ON ed.episode_id = e.id
+                 WHERE e.id = ?1",
+                params![episode_id],
+                |row| {
+                    Ok(EpisodeRow {
+                        id: row.get(0)?,
+                        podcast_id: row.get(1)?,
+                        title: row.get(2)?,
+                        publication_date: row.get(3)?,
+                        audio_url: row.get(4)?,
+                        audio_duration: row.get(5)?,
+                        summary: row.get(6)?,
+                        content_encoded: row.get(7)?,
+                        progress: row.get(8)?,
+                        done: row.get::<_, i32>(9)? != 0,
+                        podcast_title: row.get(10)?,
+                        download_status: row
+                            .get::<_, Option<String>>(11)?
+                            .map(|s| DownloadStatus::from_str(&s)),
+                        season: row.get(12)?,
+                        episode_number: row.get(13)?,
+                        episode_type: row.get(14)?,
+                    })
+                },
+            )
+            .ok()
+    }
+
+    /// Episodes across all podcasts matching `filter`, newest first. The
+    /// default filter gives the inbox; saved playlists pass their own.
+    /// Trailers are always left out, as are shows that are hidden from the
+    /// inbox, paused or archived unless the filter names them.
+    pub fn list_inbox_episodes(
+        &self,
+        filter: &PlaylistFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Vec<EpisodeRow> {
+        let podcast_ids =
+            serde_json::to_string(&filter.podcast_ids).expect("podcast ids serialize");
+        let published_after = filter
+            .published_within_days
+            .and_then(|days| chrono::TimeDelta::try_days(days.into()))
+            .and_then(|window| chrono::Utc::now().checked_sub_signed(window))
+            .map(|after| after.to_rfc3339());
         let mut stmt = self
             .conn
             .prepare(
@@ -315,19 +765,46 @@ impl LocalDb {
                         e.audio_url, e.audio_duration,
                         e.summary, e.content_encoded,
                         COALESCE(ep.progress, 0), COALESCE(ep.done, 0),
-                        p.title,
-                        ed.status
+                        COALESCE(json_extract(ps.settings, '$.title_override'), p.title),
+                        ed.status,
+                        e.season, e.episode_number, e.episode_type
                  FROM episode e
                  JOIN podcast p ON p.id = e.podcast_id
+                 LEFT JOIN podcast_settings ps ON ps.podcast_id = e.podcast_id
                  LEFT JOIN episode_progress ep ON ep.episode_id = e.id
                  LEFT JOIN episode_download ed ON ed.episode_id = e.id
-                 WHERE COALESCE(ep.done, 0) = 0
+                 WHERE CASE ?3
+                         WHEN 'unfinished' THEN COALESCE(ep.done, 0) = 0
+                         WHEN 'unplayed' THEN COALESCE(ep.done, 0) = 0
+                                          AND COALESCE(ep.progress, 0) = 0
+                         WHEN 'in_progress' THEN COALESCE(ep.done, 0) = 0
+                                             AND COALESCE(ep.progress, 0) > 0
+                         WHEN 'done' THEN COALESCE(ep.done, 0) = 1
+                         ELSE 1
+                       END
+                   AND COALESCE(e.episode_type, '') <> 'trailer'
+                   AND (?4 = '[]' OR e.podcast_id IN (SELECT value FROM json_each(?4)))
+                   AND ((COALESCE(json_extract(ps.settings, '$.hide_from_inbox'), 0) = 0
+                         AND COALESCE(json_extract(ps.settings, '$.state'), 'active') = 'active')
+                        OR e.podcast_id IN (SELECT value FROM json_each(?4)))
+                   AND (?5 IS NULL OR e.audio_duration >= ?5)
+                   AND (?6 IS NULL OR e.audio_duration <= ?6)
+                   AND (?7 IS NULL OR e.publication_date >= ?7)
                  ORDER BY e.publication_date DESC
                  LIMIT ?1 OFFSET ?2",
             )
             .expect("inbox query is valid static SQL");
 
-        stmt.query_map(params![limit, offset], |row| {
+        let query = params![
+            limit,
+            offset,
+            filter.progress.as_str(),
+            podcast_ids,
+            filter.min_duration_secs,
+            filter.max_duration_secs,
+            published_after,
+        ];
+        stmt.query_map(query, |row| {
             Ok(EpisodeRow {
                 id: row.get(0)?,
                 podcast_id: row.get(1)?,
@@ -343,6 +820,9 @@ impl LocalDb {
                 download_status: row
                     .get::<_, Option<String>>(11)?
                     .map(|s| DownloadStatus::from_str(&s)),
+                season: row.get(12)?,
+                episode_number: row.get(13)?,
+                episode_type: row.get(14)?,
             })
         })
         .expect("inbox query execution")
@@ -350,6 +830,245 @@ impl LocalDb {
         .collect()
     }
 
+    // ==========================================================================
+    // Queue
+    // ==========================================================================
+
+    /// Ids of the episodes in the Up Next queue, in play order.
+    pub fn queue_ids(&self) -> Vec<String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT episode_id FROM queue_item ORDER BY position")
+            .expect("failed to prepare queue query");
+        stmt.query_map([], |row| row.get(0))
+            .expect("failed to query queue")
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    /// Replace the queue. `dirty` marks it as a local edit that still has to
+    /// be pushed to the server.
+    pub fn set_queue(&self, episode_ids: &[String], dirty: bool) {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .expect("failed to begin transaction");
+        tx.execute("DELETE FROM queue_item", [])
+            .expect("failed to clear queue");
+        for (position, episode_id) in episode_ids.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO queue_item (episode_id, position) VALUES (?1, ?2)",
+                params![episode_id, position as i64],
+            )
+            .expect("failed to insert queue item");
+        }
+        if dirty {
+            tx.execute(
+                "INSERT OR REPLACE INTO sync_state (key, value) VALUES ('queue_dirty', '1')",
+                [],
+            )
+            .expect("failed to mark queue dirty");
+        } else {
+            tx.execute("DELETE FROM sync_state WHERE key = 'queue_dirty'", [])
+                .expect("failed to mark queue clean");
+        }
+        tx.commit().expect("failed to commit queue");
+    }
+
+    /// Whether the queue has local edits the server hasn't seen yet.
+    pub fn queue_dirty(&self) -> bool {
+        self.get_sync_state("queue_dirty").is_some()
+    }
+
+    /// Queued episodes in play order. Episodes that haven't been synced yet
+    /// are left out.
+    pub fn list_queue_episodes(&self) -> Vec<EpisodeRow> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT e.id, e.podcast_id, e.title, e.publication_date,
+                        e.audio_url, e.audio_duration,
+                        e.summary, e.content_encoded,
+                        COALESCE(ep.progress, 0), COALESCE(ep.done, 0),
+                        COALESCE(json_extract(ps.settings, '$.title_override'), p.title),
+                        ed.status,
+                        e.season, e.episode_number, e.episode_type
+                 FROM queue_item q
+                 JOIN episode e ON e.id = q.episode_id
+                 JOIN podcast p ON p.id = e.podcast_id
+                 LEFT JOIN podcast_settings ps ON ps.podcast_id = e.podcast_id
+                 LEFT JOIN episode_progress ep ON ep.episode_id = e.id
+                 LEFT JOIN episode_download ed ON ed.episode_id = e.id
+                 ORDER BY q.position",
+            )
+            .expect("queue query is valid static SQL");
+
+        stmt.query_map([], |row| {
+            Ok(EpisodeRow {
+                id: row.get(0)?,
+                podcast_id: row.get(1)?,
+                title: row.get(2)?,
+                publication_date: row.get(3)?,
+                audio_url: row.get(4)?,
+                audio_duration: row.get(5)?,
+                summary: row.get(6)?,
+                content_encoded: row.get(7)?,
+                progress: row.get(8)?,
+                done: row.get::<_, i32>(9)? != 0,
+                podcast_title: row.get(10)?,
+                download_status: row
+                    .get::<_, Option<String>>(11)?
+                    .map(|s| DownloadStatus::from_str(&s)),
+                season: row.get(12)?,
+                episode_number: row.get(13)?,
+                episode_type: row.get(14)?,
+            })
+        })
+        .expect("queue query execution")
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
+    // ==========================================================================
+    // Playlists
+    // ==========================================================================
+
+    /// Saved playlists in the order the server lists them.
+    pub fn list_playlists(&self) -> Vec<Playlist> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, filter FROM playlist ORDER BY position")
+            .expect("failed to prepare playlist query");
+        stmt.query_map([], |row| {
+            let filter: String = row.get(2)?;
+            Ok(Playlist {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                filter: serde_json::from_str(&filter).unwrap_or_default(),
+            })
+        })
+        .expect("failed to query playlists")
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
+    /// Replace the saved playlists with the server's.
+    pub fn set_playlists(&self, playlists: &[Playlist]) {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .expect("failed to begin transaction");
+        tx.execute("DELETE FROM playlist", [])
+            .expect("failed to clear playlists");
+        for (position, playlist) in playlists.iter().enumerate() {
+            let filter =
+                serde_json::to_string(&playlist.filter).expect("playlist filter serializes");
+            tx.execute(
+                "INSERT INTO playlist (id, position, name, filter) VALUES (?1, ?2, ?3, ?4)",
+                params![playlist.id, position as i64, playlist.name, filter],
+            )
+            .expect("failed to insert playlist");
+        }
+        tx.commit().expect("failed to commit playlists");
+    }
+
+    // ==========================================================================
+    // Chapters
+    // ==========================================================================
+
+    pub fn replace_chapters(&self, episode_id: &str, chapters: &[Chapter]) {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .expect("failed to begin transaction");
+        tx.execute(
+            "DELETE FROM episode_chapter WHERE episode_id = ?1",
+            params![episode_id],
+        )
+        .expect("failed to delete chapters");
+        for (position, c) in chapters.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO episode_chapter (episode_id, position, start_time, end_time, title)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![episode_id, position as i64, c.start_time, c.end_time, c.title],
+            )
+            .expect("failed to insert chapter");
+        }
+        tx.commit().expect("failed to commit chapters");
+    }
+
+    pub fn list_chapters(&self, episode_id: &str) -> Vec<Chapter> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT start_time, end_time, title FROM episode_chapter
+                 WHERE episode_id = ?1
+                 ORDER BY position",
+            )
+            .expect("chapter query is valid static SQL");
+
+        stmt.query_map(params![episode_id], |row| {
+            Ok(Chapter {
+                start_time: row.get(0)?,
+                end_time: row.get(1)?,
+                title: row.get(2)?,
+                img: None,
+                url: None,
+            })
+        })
+        .expect("chapter query execution")
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
+    // ==========================================================================
+    // Transcripts
+    // ==========================================================================
+
+    pub fn replace_transcript(&self, episode_id: &str, segments: &[TranscriptSegment]) {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .expect("failed to begin transaction");
+        tx.execute(
+            "DELETE FROM episode_transcript_segment WHERE episode_id = ?1",
+            params![episode_id],
+        )
+        .expect("failed to delete transcript");
+        for (position, s) in segments.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO episode_transcript_segment (episode_id, position, start_time, end_time, speaker, body)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![episode_id, position as i64, s.start_time, s.end_time, s.speaker, s.body],
+            )
+            .expect("failed to insert transcript segment");
+        }
+        tx.commit().expect("failed to commit transcript");
+    }
+
+    pub fn list_transcript(&self, episode_id: &str) -> Vec<TranscriptSegment> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT start_time, end_time, speaker, body FROM episode_transcript_segment
+                 WHERE episode_id = ?1
+                 ORDER BY position",
+            )
+            .expect("transcript query is valid static SQL");
+
+        stmt.query_map(params![episode_id], |row| {
+            Ok(TranscriptSegment {
+                start_time: row.get(0)?,
+                end_time: row.get(1)?,
+                speaker: row.get(2)?,
+                body: row.get(3)?,
+            })
+        })
+        .expect("transcript query execution")
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
     // ==========================================================================
     // Progress
     // ==========================================================================
@@ -401,12 +1120,15 @@ impl LocalDb {
     // Downloads
     // ==========================================================================
 
-    pub fn insert_download(&self, episode_id: &str, file_path: &str) {
+    /// Start recording a download. `automatic` is set for downloads the
+    /// download policy started.
+    pub fn insert_download(&self, episode_id: &str, file_path: &str, automatic: bool) {
         let now = chrono::Utc::now().to_rfc3339();
         self.conn
             .execute(
-                "INSERT INTO episode_download (episode_id, file_path, status, started_at)
-                 VALUES (?1, ?2, 'pending', ?3)
+                "INSERT INTO episode_download
+                     (episode_id, file_path, status, started_at, automatic)
+                 VALUES (?1, ?2, 'pending', ?3, ?4)
                  ON CONFLICT(episode_id) DO UPDATE SET
                      file_path = excluded.file_path,
                      status = 'pending',
@@ -414,8 +1136,9 @@ impl LocalDb {
                      downloaded_bytes = 0,
                      error = NULL,
                      started_at = excluded.started_at,
-                     completed_at = NULL",
-                params![episode_id, file_path, now],
+                     completed_at = NULL,
+                     automatic = excluded.automatic",
+                params![episode_id, file_path, now, automatic],
             )
             .expect("failed to insert download");
     }
@@ -498,6 +1221,225 @@ impl LocalDb {
         }
     }
 
+    /// Every download record with its episode, most recently started first.
+    pub fn list_downloads(&self) -> Vec<DownloadRow> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT e.id, e.podcast_id, e.title, e.publication_date,
+                        e.audio_url, e.audio_duration,
+                        e.summary, e.content_encoded,
+                        COALESCE(ep.progress, 0), COALESCE(ep.done, 0),
+                        COALESCE(json_extract(ps.settings, '$.title_override'), p.title),
+                        ed.status,
+                        e.season, e.episode_number, e.episode_type,
+                        ed.file_path, ed.downloaded_bytes, ed.total_bytes, ed.error
+                 FROM episode_download ed
+                 JOIN episode e ON e.id = ed.episode_id
+                 JOIN podcast p ON p.id = e.podcast_id
+                 LEFT JOIN podcast_settings ps ON ps.podcast_id = e.podcast_id
+                 LEFT JOIN episode_progress ep ON ep.episode_id = e.id
+                 ORDER BY ed.started_at DESC",
+            )
+            .expect("downloads query is valid static SQL");
+
+        stmt.query_map([], |row| {
+            let status = DownloadStatus::from_str(&row.get::<_, String>(11)?);
+            Ok(DownloadRow {
+                episode: EpisodeRow {
+                    id: row.get(0)?,
+                    podcast_id: row.get(1)?,
+                    title: row.get(2)?,
+                    publication_date: row.get(3)?,
+                    audio_url: row.get(4)?,
+                    audio_duration: row.get(5)?,
+                    summary: row.get(6)?,
+                    content_encoded: row.get(7)?,
+                    progress: row.get(8)?,
+                    done: row.get::<_, i32>(9)? != 0,
+                    podcast_title: row.get(10)?,
+                    download_status: Some(status),
+                    season: row.get(12)?,
+                    episode_number: row.get(13)?,
+                    episode_type: row.get(14)?,
+                },
+                file_path: row.get(15)?,
+                status,
+                downloaded_bytes: row.get(16)?,
+                total_bytes: row.get(17)?,
+                error: row.get(18)?,
+            })
+        })
+        .expect("downloads query execution")
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
+    /// Where an episode is being downloaded to, whatever state the download
+    /// is in.
+    pub fn download_file_path(&self, episode_id: &str) -> Option<String> {
+        self.conn
+            .query_row(
+                "SELECT file_path FROM episode_download WHERE episode_id = ?1",
+                params![episode_id],
+                |row| row.get(0),
+            )
+            .ok()
+    }
+
+    /// Queue a failed download again. The bytes downloaded so far are kept,
+    /// since the partial file is resumed. Only the user retries downloads,
+    /// so it counts as started by hand from now on.
+    pub fn retry_download(&self, episode_id: &str) {
+        self.conn
+            .execute(
+                "UPDATE episode_download SET status = 'pending', error = NULL, automatic = 0
+                 WHERE episode_id = ?1",
+                params![episode_id],
+            )
+            .expect("failed to retry download");
+    }
+
+    /// Record that a downloaded episode is being played from its file.
+    pub fn mark_download_played(&self, episode_id: &str) {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.conn
+            .execute(
+                "UPDATE episode_download SET last_played_at = ?2 WHERE episode_id = ?1",
+                params![episode_id, now],
+            )
+            .expect("failed to mark download as played");
+    }
+
+    /// Mark a download whose file was deleted to free space as failed, so it
+    /// isn't downloaded again automatically but can be retried by hand.
+    pub fn evict_download(&self, episode_id: &str, reason: &str) {
+        self.conn
+            .execute(
+                "UPDATE episode_download
+                 SET status = 'failed', error = ?2, downloaded_bytes = 0, completed_at = NULL
+                 WHERE episode_id = ?1",
+                params![episode_id, reason],
+            )
+            .expect("failed to evict download");
+    }
+
+    /// Episodes to download automatically that have no download yet: the
+    /// newest unplayed ones of each active podcast, as many as its
+    /// `auto_download` setting asks for, and, if `queue` is set, everything
+    /// queued. Episodes published before `published_after` are left out of
+    /// the former.
+    pub fn list_auto_download_episodes(
+        &self,
+        queue: bool,
+        published_after: Option<&str>,
+    ) -> Vec<EpisodeRow> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "WITH ranked AS (
+                     SELECT e.id,
+                            ROW_NUMBER() OVER (
+                                PARTITION BY e.podcast_id ORDER BY e.publication_date DESC
+                            ) AS rank,
+                            COALESCE(json_extract(ps.settings, '$.auto_download'), 0) AS keep
+                     FROM episode e
+                     LEFT JOIN podcast_settings ps ON ps.podcast_id = e.podcast_id
+                     LEFT JOIN episode_progress ep ON ep.episode_id = e.id
+                     WHERE COALESCE(ep.done, 0) = 0
+                       AND COALESCE(json_extract(ps.settings, '$.state'), 'active') = 'active'
+                 )
+                 SELECT e.id, e.podcast_id, e.title, e.publication_date,
+                        e.audio_url, e.audio_duration,
+                        e.summary, e.content_encoded,
+                        COALESCE(ep.progress, 0), COALESCE(ep.done, 0),
+                        COALESCE(json_extract(ps.settings, '$.title_override'), p.title),
+                        e.season, e.episode_number, e.episode_type
+                 FROM episode e
+                 JOIN podcast p ON p.id = e.podcast_id
+                 LEFT JOIN podcast_settings ps ON ps.podcast_id = e.podcast_id
+                 LEFT JOIN episode_progress ep ON ep.episode_id = e.id
+                 WHERE e.id NOT IN (SELECT episode_id FROM episode_download)
+                   AND ((e.id IN (SELECT id FROM ranked WHERE rank <= keep)
+                         AND (?2 IS NULL OR e.publication_date >= ?2))
+                        OR (?1 AND e.id IN (SELECT episode_id FROM queue_item)))
+                 ORDER BY e.publication_date DESC",
+            )
+            .expect("auto-download query is valid static SQL");
+
+        stmt.query_map(params![queue, published_after], |row| {
+            Ok(EpisodeRow {
+                id: row.get(0)?,
+                podcast_id: row.get(1)?,
+                title: row.get(2)?,
+                publication_date: row.get(3)?,
+                audio_url: row.get(4)?,
+                audio_duration: row.get(5)?,
+                summary: row.get(6)?,
+                content_encoded: row.get(7)?,
+                progress: row.get(8)?,
+                done: row.get::<_, i32>(9)? != 0,
+                podcast_title: row.get(10)?,
+                download_status: None,
+                season: row.get(11)?,
+                episode_number: row.get(12)?,
+                episode_type: row.get(13)?,
+            })
+        })
+        .expect("auto-download query execution")
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
+    /// Completed downloads that are no longer wanted: of episodes marked
+    /// done if `done` is set, and automatic ones of episodes published
+    /// before `published_before` unless they are queued. Returns episode ids
+    /// and file paths.
+    pub fn list_expired_downloads(
+        &self,
+        done: bool,
+        published_before: Option<&str>,
+    ) -> Vec<(String, String)> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT ed.episode_id, ed.file_path
+                 FROM episode_download ed
+                 JOIN episode e ON e.id = ed.episode_id
+                 LEFT JOIN episode_progress ep ON ep.episode_id = ed.episode_id
+                 WHERE ed.status = 'complete'
+                   AND ((?1 AND COALESCE(ep.done, 0) = 1)
+                        OR (ed.automatic AND e.publication_date < ?2
+                            AND ed.episode_id NOT IN (SELECT episode_id FROM queue_item)))",
+            )
+            .expect("expired downloads query is valid static SQL");
+
+        stmt.query_map(params![done, published_before], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("expired downloads query execution")
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    /// Completed downloads with their size in bytes, least recently played
+    /// (or downloaded, if never played) first. Returns episode ids, file
+    /// paths and sizes.
+    pub fn list_downloads_by_last_use(&self) -> Vec<(String, String, i64)> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT episode_id, file_path, downloaded_bytes
+                 FROM episode_download
+                 WHERE status = 'complete'
+                 ORDER BY COALESCE(last_played_at, completed_at)",
+            )
+            .expect("downloads by last use query is valid static SQL");
+
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .expect("downloads by last use query execution")
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
     pub fn delete_download(&self, episode_id: &str) {
         self.conn
             .execute(
@@ -516,6 +1458,8 @@ fn parse_datetime(s: String) -> chrono::DateTime<chrono::Utc> {
 
 #[cfg(test)]
 mod tests {
+    use pod_model::{ProgressFilter, SubscriptionState};
+
     use super::*;
 
     #[test]
@@ -552,6 +1496,13 @@ mod tests {
             last_publication_date: Some(now),
             feed_etag: None,
             feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
         };
         db.upsert_podcast(&p);
         let list = db.list_podcasts();
@@ -559,6 +1510,47 @@ mod tests {
         assert_eq!(list[0].title, "Test Pod");
     }
 
+    #[test]
+    fn podcast_fetch_state_follows_the_server() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let now: chrono::DateTime<chrono::Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let p = PodcastWithEpisodeStats {
+            id: "p1".to_string(),
+            title: "Test Pod".to_string(),
+            description: String::new(),
+            image_link: String::new(),
+            feed_url: "https://feed.example".to_string(),
+            feed_type: "rss".to_string(),
+            created_at: now,
+            last_updated: now,
+            last_publication_date: None,
+            feed_etag: None,
+            feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 3,
+            last_error: Some("HTTP 503".to_string()),
+            next_fetch_at: Some(now),
+            podcast_guid: None,
+        };
+        db.upsert_podcast(&p);
+        let list = db.list_podcasts();
+        assert_eq!(list[0].consecutive_failures, 3);
+        assert_eq!(list[0].last_error.as_deref(), Some("HTTP 503"));
+        assert_eq!(list[0].next_fetch_at, Some(now));
+
+        // A successful fetch clears it.
+        db.upsert_podcast(&PodcastWithEpisodeStats {
+            consecutive_failures: 0,
+            last_error: None,
+            ..p
+        });
+        let list = db.list_podcasts();
+        assert_eq!(list[0].consecutive_failures, 0);
+        assert_eq!(list[0].last_error, None);
+    }
+
     #[test]
     fn episode_upsert_and_list() {
         let db = LocalDb::open(":memory:").unwrap();
@@ -577,6 +1569,13 @@ mod tests {
             last_publication_date: None,
             feed_etag: None,
             feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
         };
         db.upsert_podcast(&p);
 
@@ -595,6 +1594,12 @@ mod tests {
             thumbnail_url: None,
             created_at: now,
             last_updated: now,
+            deleted_at: None,
+            season: Some(2),
+            episode_number: Some(5),
+            episode_type: Some("full".to_string()),
+            chapters_url: None,
+            chapters_type: None,
         };
         db.upsert_episode(&e);
 
@@ -603,6 +1608,621 @@ mod tests {
         assert_eq!(episodes[0].title, "Episode 1");
         assert_eq!(episodes[0].progress, 0);
         assert!(!episodes[0].done);
+        assert_eq!(episodes[0].label().as_deref(), Some("S2E5"));
+
+        // Trailers are listed with the podcast but kept out of the inbox.
+        db.upsert_episode(&pod_model::Episode {
+            id: "e2".to_string(),
+            episode_type: Some("trailer".to_string()),
+            ..e
+        });
+        assert_eq!(db.list_episodes("p1").len(), 2);
+        let inbox = db.list_inbox_episodes(&PlaylistFilter::default(), 10, 0);
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].id, "e1");
+    }
+
+    #[test]
+    fn delete_podcast_removes_episodes_and_downloads() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let now = chrono::Utc::now();
+
+        for id in ["p1", "p2"] {
+            db.upsert_podcast(&PodcastWithEpisodeStats {
+                id: id.to_string(),
+                title: id.to_string(),
+                description: String::new(),
+                image_link: String::new(),
+                feed_url: format!("https://feed.example/{id}"),
+                feed_type: "rss".to_string(),
+                created_at: now,
+                last_updated: now,
+                last_publication_date: None,
+                feed_etag: None,
+                feed_last_modified: None,
+                feed_update_interval_secs: None,
+                last_fetch_at: None,
+                last_success_at: None,
+                consecutive_failures: 0,
+                last_error: None,
+                next_fetch_at: None,
+                podcast_guid: None,
+            });
+            db.upsert_episode(&pod_model::Episode {
+                id: format!("{id}-e1"),
+                podcast_id: id.to_string(),
+                title: "Ep".to_string(),
+                summary: String::new(),
+                summary_type: String::new(),
+                content_encoded: String::new(),
+                content_encoded_type: String::new(),
+                publication_date: now,
+                audio_url: format!("https://audio.example/{id}.mp3"),
+                audio_type: "audio/mpeg".to_string(),
+                audio_duration: 100,
+                thumbnail_url: None,
+                created_at: now,
+                last_updated: now,
+                deleted_at: None,
+                season: None,
+                episode_number: None,
+                episode_type: None,
+                chapters_url: None,
+                chapters_type: None,
+            });
+        }
+        db.upsert_progress("p1-e1", 10, false, false);
+        db.insert_download("p1-e1", "/tmp/p1-e1.mp3", false);
+
+        let paths = db.delete_podcast("p1");
+        assert_eq!(paths, vec!["/tmp/p1-e1.mp3".to_string()]);
+
+        let podcasts = db.list_podcasts();
+        assert_eq!(podcasts.len(), 1);
+        assert_eq!(podcasts[0].id, "p2");
+        assert!(db.list_episodes("p1").is_empty());
+        assert_eq!(db.list_episodes("p2").len(), 1);
+        assert_eq!(db.get_download_status("p1-e1"), None);
+    }
+
+    #[test]
+    fn delete_episode_removes_progress_and_download() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let now = chrono::Utc::now();
+
+        db.upsert_podcast(&PodcastWithEpisodeStats {
+            id: "p1".to_string(),
+            title: "Test".to_string(),
+            description: String::new(),
+            image_link: String::new(),
+            feed_url: String::new(),
+            feed_type: "rss".to_string(),
+            created_at: now,
+            last_updated: now,
+            last_publication_date: None,
+            feed_etag: None,
+            feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
+        });
+        for id in ["e1", "e2"] {
+            db.upsert_episode(&pod_model::Episode {
+                id: id.to_string(),
+                podcast_id: "p1".to_string(),
+                title: id.to_string(),
+                summary: String::new(),
+                summary_type: String::new(),
+                content_encoded: String::new(),
+                content_encoded_type: String::new(),
+                publication_date: now,
+                audio_url: format!("https://audio.example/{id}.mp3"),
+                audio_type: "audio/mpeg".to_string(),
+                audio_duration: 100,
+                thumbnail_url: None,
+                created_at: now,
+                last_updated: now,
+                deleted_at: None,
+                season: None,
+                episode_number: None,
+                episode_type: None,
+                chapters_url: None,
+                chapters_type: None,
+            });
+        }
+        db.upsert_progress("e1", 42, false, true);
+        db.insert_download("e1", "/tmp/e1.mp3", false);
+        let transcript = vec![pod_model::TranscriptSegment {
+            start_time: 1.5,
+            end_time: None,
+            speaker: Some("Alice".to_string()),
+            body: "Hello".to_string(),
+        }];
+        db.replace_transcript("e1", &transcript);
+        assert_eq!(db.list_transcript("e1"), transcript);
+
+        assert_eq!(db.delete_episode("e1"), Some("/tmp/e1.mp3".to_string()));
+        assert_eq!(db.delete_episode("e2"), None);
+        assert!(db.list_episodes("p1").is_empty());
+        assert!(db.list_dirty_progress().is_empty());
+        assert!(db.list_transcript("e1").is_empty());
+    }
+
+    #[test]
+    fn queue_order_and_dirty_flag() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let now = chrono::Utc::now();
+
+        db.upsert_podcast(&PodcastWithEpisodeStats {
+            id: "p1".to_string(),
+            title: "Test".to_string(),
+            description: String::new(),
+            image_link: String::new(),
+            feed_url: String::new(),
+            feed_type: "rss".to_string(),
+            created_at: now,
+            last_updated: now,
+            last_publication_date: None,
+            feed_etag: None,
+            feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
+        });
+        for id in ["e1", "e2"] {
+            db.upsert_episode(&pod_model::Episode {
+                id: id.to_string(),
+                podcast_id: "p1".to_string(),
+                title: id.to_string(),
+                summary: String::new(),
+                summary_type: String::new(),
+                content_encoded: String::new(),
+                content_encoded_type: String::new(),
+                publication_date: now,
+                audio_url: format!("https://audio.example/{id}.mp3"),
+                audio_type: "audio/mpeg".to_string(),
+                audio_duration: 100,
+                thumbnail_url: None,
+                created_at: now,
+                last_updated: now,
+                deleted_at: None,
+                season: None,
+                episode_number: None,
+                episode_type: None,
+                chapters_url: None,
+                chapters_type: None,
+            });
+        }
+
+        // "e3" hasn't synced yet: it stays queued but isn't listed.
+        let ids = ["e2", "e3", "e1"].map(String::from);
+        db.set_queue(&ids, true);
+        assert!(db.queue_dirty());
+        assert_eq!(db.queue_ids(), ids);
+        let listed: Vec<_> = db.list_queue_episodes().into_iter().map(|e| e.id).collect();
+        assert_eq!(listed, vec!["e2", "e1"]);
+        assert_eq!(db.list_queue_episodes()[0].podcast_title.as_deref(), Some("Test"));
+
+        db.delete_episode("e2");
+        assert_eq!(db.queue_ids(), vec!["e3", "e1"]);
+
+        db.set_queue(&["e1".to_string()], false);
+        assert!(!db.queue_dirty());
+        db.delete_podcast("p1");
+        assert!(db.queue_ids().is_empty());
+    }
+
+    #[test]
+    fn download_list_and_retry() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let now = chrono::Utc::now();
+
+        db.upsert_podcast(&PodcastWithEpisodeStats {
+            id: "p1".to_string(),
+            title: "Test".to_string(),
+            description: String::new(),
+            image_link: String::new(),
+            feed_url: String::new(),
+            feed_type: "rss".to_string(),
+            created_at: now,
+            last_updated: now,
+            last_publication_date: None,
+            feed_etag: None,
+            feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
+        });
+        for id in ["e1", "e2", "e3"] {
+            db.upsert_episode(&pod_model::Episode {
+                id: id.to_string(),
+                podcast_id: "p1".to_string(),
+                title: id.to_string(),
+                summary: String::new(),
+                summary_type: String::new(),
+                content_encoded: String::new(),
+                content_encoded_type: String::new(),
+                publication_date: now,
+                audio_url: format!("https://audio.example/{id}.mp3"),
+                audio_type: "audio/mpeg".to_string(),
+                audio_duration: 100,
+                thumbnail_url: None,
+                created_at: now,
+                last_updated: now,
+                deleted_at: None,
+                season: None,
+                episode_number: None,
+                episode_type: None,
+                chapters_url: None,
+                chapters_type: None,
+            });
+        }
+        db.insert_download("e1", "/tmp/e1.mp3", false);
+        db.update_download_progress("e1", 400, 1000);
+        db.fail_download("e1", "connection reset");
+        db.insert_download("e2", "/tmp/e2.mp3", false);
+        db.complete_download("e2");
+
+        let downloads = db.list_downloads();
+        assert_eq!(downloads.len(), 2);
+        let e1 = downloads.iter().find(|d| d.episode.id == "e1").unwrap();
+        assert_eq!(e1.status, DownloadStatus::Failed);
+        assert_eq!(e1.error.as_deref(), Some("connection reset"));
+        assert_eq!((e1.downloaded_bytes, e1.total_bytes), (400, 1000));
+        assert_eq!(e1.episode.podcast_title.as_deref(), Some("Test"));
+        assert_eq!(db.download_file_path("e1").as_deref(), Some("/tmp/e1.mp3"));
+        assert_eq!(db.download_file_path("e3"), None);
+
+        // Retrying keeps what was downloaded, so the file can be resumed.
+        db.retry_download("e1");
+        let e1 = db.list_downloads().into_iter().find(|d| d.episode.id == "e1").unwrap();
+        assert_eq!(e1.status, DownloadStatus::Pending);
+        assert_eq!(e1.error, None);
+        assert_eq!(e1.downloaded_bytes, 400);
+    }
+
+    #[test]
+    fn auto_download_and_expired_downloads() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let now = chrono::Utc::now();
+
+        db.upsert_podcast(&PodcastWithEpisodeStats {
+            id: "p1".to_string(),
+            title: "Test".to_string(),
+            description: String::new(),
+            image_link: String::new(),
+            feed_url: String::new(),
+            feed_type: "rss".to_string(),
+            created_at: now,
+            last_updated: now,
+            last_publication_date: None,
+            feed_etag: None,
+            feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
+        });
+        // e1 is the oldest, e4 the newest.
+        for (i, id) in ["e1", "e2", "e3", "e4"].into_iter().enumerate() {
+            db.upsert_episode(&pod_model::Episode {
+                id: id.to_string(),
+                podcast_id: "p1".to_string(),
+                title: id.to_string(),
+                summary: String::new(),
+                summary_type: String::new(),
+                content_encoded: String::new(),
+                content_encoded_type: String::new(),
+                publication_date: now - chrono::Duration::days(10 * (4 - i as i64)),
+                audio_url: format!("https://audio.example/{id}.mp3"),
+                audio_type: "audio/mpeg".to_string(),
+                audio_duration: 100,
+                thumbnail_url: None,
+                created_at: now,
+                last_updated: now,
+                deleted_at: None,
+                season: None,
+                episode_number: None,
+                episode_type: None,
+                chapters_url: None,
+                chapters_type: None,
+            });
+        }
+        let auto = |queue: bool, after: Option<&str>| -> Vec<String> {
+            db.list_auto_download_episodes(queue, after)
+                .into_iter()
+                .map(|e| e.id)
+                .collect()
+        };
+
+        // Nothing until the podcast asks for downloads.
+        db.set_queue(&["e1".to_string()], false);
+        assert!(auto(false, None).is_empty());
+        assert_eq!(auto(true, None), vec!["e1"]);
+
+        // The newest two unplayed episodes, skipping done ones and those
+        // already downloaded.
+        let settings = SubscriptionSettings {
+            auto_download: 2,
+            ..Default::default()
+        };
+        db.set_podcast_settings("p1", &settings);
+        db.upsert_progress("e4", 0, true, false);
+        assert_eq!(auto(false, None), vec!["e3", "e2"]);
+        let cutoff = (now - chrono::Duration::days(25)).to_rfc3339();
+        assert_eq!(auto(false, Some(&cutoff)), vec!["e3"]);
+        db.insert_download("e3", "/tmp/e3.mp3", false);
+        assert_eq!(auto(true, None), vec!["e2", "e1"]);
+        db.set_podcast_settings(
+            "p1",
+            &SubscriptionSettings {
+                state: pod_model::SubscriptionState::Paused,
+                ..settings
+            },
+        );
+        assert_eq!(auto(true, None), vec!["e1"]);
+
+        // Completed downloads of done episodes are expired, and automatic
+        // ones of old episodes; queued ones are kept however old.
+        for id in ["e1", "e2", "e3", "e4"] {
+            db.insert_download(id, &format!("/tmp/{id}.mp3"), true);
+            db.update_download_progress(id, 1000, 1000);
+            db.complete_download(id);
+        }
+        let expired = |done: bool, before: Option<&str>| -> Vec<String> {
+            let mut ids: Vec<_> = db
+                .list_expired_downloads(done, before)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert!(expired(false, None).is_empty());
+        assert_eq!(expired(true, None), vec!["e4"]);
+        assert_eq!(expired(true, Some(&cutoff)), vec!["e2", "e4"]);
+        // Downloads started by hand are kept however old.
+        db.retry_download("e2");
+        db.complete_download("e2");
+        assert_eq!(expired(true, Some(&cutoff)), vec!["e4"]);
+
+        // Least recently used first: played downloads move to the back.
+        db.mark_download_played("e1");
+        let by_use: Vec<_> =
+            db.list_downloads_by_last_use().into_iter().map(|(id, _, _)| id).collect();
+        assert_eq!(by_use.last().map(String::as_str), Some("e1"));
+        db.evict_download("e2", "Deleted to stay within the quota");
+        assert_eq!(db.list_downloads_by_last_use().len(), 3);
+        assert_eq!(db.get_download_status("e2"), Some(DownloadStatus::Failed));
+    }
+
+    #[test]
+    fn playlist_filters() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let now = chrono::Utc::now();
+
+        for id in ["p1", "p2"] {
+            db.upsert_podcast(&PodcastWithEpisodeStats {
+                id: id.to_string(),
+                title: id.to_string(),
+                description: String::new(),
+                image_link: String::new(),
+                feed_url: format!("https://feed.example/{id}"),
+                feed_type: "rss".to_string(),
+                created_at: now,
+                last_updated: now,
+                last_publication_date: None,
+                feed_etag: None,
+                feed_last_modified: None,
+                feed_update_interval_secs: None,
+                last_fetch_at: None,
+                last_success_at: None,
+                consecutive_failures: 0,
+                last_error: None,
+                next_fetch_at: None,
+                podcast_guid: None,
+            });
+        }
+        // (id, podcast, minutes long, days old)
+        for (id, podcast_id, minutes, age) in
+            [("short", "p1", 20, 1), ("long", "p1", 90, 2), ("old", "p2", 20, 30)]
+        {
+            db.upsert_episode(&pod_model::Episode {
+                id: id.to_string(),
+                podcast_id: podcast_id.to_string(),
+                title: id.to_string(),
+                summary: String::new(),
+                summary_type: String::new(),
+                content_encoded: String::new(),
+                content_encoded_type: String::new(),
+                publication_date: now - chrono::Duration::days(age),
+                audio_url: format!("https://audio.example/{id}.mp3"),
+                audio_type: "audio/mpeg".to_string(),
+                audio_duration: minutes * 60,
+                thumbnail_url: None,
+                created_at: now,
+                last_updated: now,
+                deleted_at: None,
+                season: None,
+                episode_number: None,
+                episode_type: None,
+                chapters_url: None,
+                chapters_type: None,
+            });
+        }
+        db.upsert_progress("long", 600, false, false);
+        db.upsert_progress("old", 1200, true, false);
+
+        let ids = |filter: PlaylistFilter| -> Vec<String> {
+            db.list_inbox_episodes(&filter, 10, 0).into_iter().map(|e| e.id).collect()
+        };
+        assert_eq!(ids(PlaylistFilter::default()), vec!["short", "long"]);
+        assert_eq!(
+            ids(PlaylistFilter {
+                progress: ProgressFilter::Unplayed,
+                max_duration_secs: Some(30 * 60),
+                ..Default::default()
+            }),
+            vec!["short"]
+        );
+        assert_eq!(
+            ids(PlaylistFilter { progress: ProgressFilter::InProgress, ..Default::default() }),
+            vec!["long"]
+        );
+        assert_eq!(
+            ids(PlaylistFilter {
+                podcast_ids: vec!["p2".to_string()],
+                progress: ProgressFilter::Any,
+                ..Default::default()
+            }),
+            vec!["old"]
+        );
+        assert_eq!(
+            ids(PlaylistFilter {
+                progress: ProgressFilter::Any,
+                published_within_days: Some(7),
+                ..Default::default()
+            }),
+            vec!["short", "long"]
+        );
+        // Windows reaching past the start of time include everything.
+        assert_eq!(
+            ids(PlaylistFilter {
+                progress: ProgressFilter::Any,
+                published_within_days: Some(i32::MAX),
+                ..Default::default()
+            }),
+            vec!["short", "long", "old"]
+        );
+
+        let playlists = vec![
+            Playlist {
+                id: "b".to_string(),
+                name: "Partially played".to_string(),
+                filter: PlaylistFilter {
+                    progress: ProgressFilter::InProgress,
+                    ..Default::default()
+                },
+            },
+            Playlist {
+                id: "a".to_string(),
+                name: "Everything".to_string(),
+                filter: PlaylistFilter::default(),
+            },
+        ];
+        db.set_playlists(&playlists);
+        assert_eq!(db.list_playlists(), playlists);
+
+        // Archived shows leave the inbox but stay in playlists naming them.
+        let settings = SubscriptionSettings {
+            title_override: Some("Renamed".to_string()),
+            state: SubscriptionState::Archived,
+            ..Default::default()
+        };
+        db.set_podcast_settings("p1", &settings);
+        assert_eq!(db.get_podcast_settings("p1"), settings);
+        assert_eq!(db.get_podcast_settings("p2"), SubscriptionSettings::default());
+        db.set_podcast_speed("p1", 1.5);
+        assert_eq!(db.get_podcast_settings("p1").playback_speed, 1.5);
+        assert_eq!(db.get_podcast_settings("p1").state, SubscriptionState::Archived);
+        assert!(ids(PlaylistFilter::default()).is_empty());
+        assert_eq!(
+            ids(PlaylistFilter {
+                podcast_ids: vec!["p1".to_string()],
+                ..Default::default()
+            }),
+            vec!["short", "long"]
+        );
+        let titles: Vec<String> = db.list_podcasts().into_iter().map(|p| p.title).collect();
+        assert!(titles.contains(&"Renamed".to_string()));
+    }
+
+    #[test]
+    fn search_titles_descriptions_and_podcasts() {
+        let db = LocalDb::open(":memory:").unwrap();
+        let now = chrono::Utc::now();
+
+        db.upsert_podcast(&PodcastWithEpisodeStats {
+            id: "p1".to_string(),
+            title: "Compiler Hour".to_string(),
+            description: String::new(),
+            image_link: String::new(),
+            feed_url: String::new(),
+            feed_type: "rss".to_string(),
+            created_at: now,
+            last_updated: now,
+            last_publication_date: None,
+            feed_etag: None,
+            feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
+        });
+        for (id, title, summary) in [
+            ("e1", "Borrow checking", "<p>All about <b>lifetimes</b> &amp; regions</p>"),
+            ("e2", "Gardening", "<p>Growing tomatoes</p>"),
+        ] {
+            db.upsert_episode(&pod_model::Episode {
+                id: id.to_string(),
+                podcast_id: "p1".to_string(),
+                title: title.to_string(),
+                summary: summary.to_string(),
+                summary_type: "text/html".to_string(),
+                content_encoded: String::new(),
+                content_encoded_type: String::new(),
+                publication_date: now,
+                audio_url: format!("https://audio.example/{id}.mp3"),
+                audio_type: "audio/mpeg".to_string(),
+                audio_duration: 100,
+                thumbnail_url: None,
+                created_at: now,
+                last_updated: now,
+                deleted_at: None,
+                season: None,
+                episode_number: None,
+                episode_type: None,
+                chapters_url: None,
+                chapters_type: None,
+            });
+        }
+
+        let ids = |q: &str| -> Vec<String> {
+            db.search_episodes(q, 10).into_iter().map(|h| h.episode.id).collect()
+        };
+        assert_eq!(ids("borrow"), vec!["e1"]);
+        // The last word matches as a prefix, while typing.
+        assert_eq!(ids("tomat"), vec!["e2"]);
+        assert_eq!(ids("compiler").len(), 2);
+        assert!(ids("\"").is_empty());
+
+        let hits = db.search_episodes("lifetimes", 10);
+        assert_eq!(
+            hits[0].snippet,
+            format!("All about {}lifetimes{} & regions", MATCH_START, MATCH_END)
+        );
+        assert_eq!(hits[0].episode.podcast_title.as_deref(), Some("Compiler Hour"));
+
+        db.delete_episode("e1");
+        assert!(ids("borrow").is_empty());
     }
 
     #[test]
@@ -622,6 +2242,13 @@ mod tests {
             last_publication_date: None,
             feed_etag: None,
             feed_last_modified: None,
+            feed_update_interval_secs: None,
+            last_fetch_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            last_error: None,
+            next_fetch_at: None,
+            podcast_guid: None,
         };
         db.upsert_podcast(&p);
 
@@ -640,6 +2267,12 @@ mod tests {
             thumbnail_url: None,
             created_at: now,
             last_updated: now,
+            deleted_at: None,
+            season: None,
+            episode_number: None,
+            episode_type: None,
+            chapters_url: None,
+            chapters_type: None,
         };
         db.upsert_episode(&e);
 
@@ -13,9 +13,12 @@ use ratatui::prelude::*;
 use tokio::sync::Mutex;
 
 use pod_tui::app::{Action, App, View};
+use pod_tui::cli::{self, Command};
+use pod_tui::daemon::{self, DaemonClient};
 use pod_tui::event::{self, PlayerHandle};
 use pod_tui::local_db::LocalDb;
 use pod_tui::mpris;
+use pod_tui::player::Backend;
 use pod_tui::ui;
 
 fn main() -> Result<()> {
@@ -29,6 +32,10 @@ fn main() -> Result<()> {
 }
 
 async fn run() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = Command::parse(&args)
+        .map_err(|e| anyhow::anyhow!("{}\n\n{}", e, cli::USAGE))?;
+
     // Determine the data directory: ~/.local/share/pod/
     let data_dir = dirs::data_dir()
         .expect("could not determine data directory")
@@ -43,35 +50,47 @@ async fn run() -> Result<()> {
     let db_path = data_dir.join("pod.db");
 
     let db = LocalDb::open(db_path.to_str().expect("invalid db path"))?;
+    let backend = match std::env::var("POD_PLAYER") {
+        Ok(name) => Backend::from_name(&name).unwrap_or_else(|| {
+            eprintln!("warning: unknown POD_PLAYER {:?}, picking a player automatically", name);
+            Backend::detect()
+        }),
+        Err(_) => Backend::detect(),
+    };
+
+    let socket = daemon::socket_path(&data_dir);
+    if command == Command::Daemon {
+        let listener = daemon::bind(&socket).await?;
+        let mut app = App::new(db);
+        app.backend = backend;
+        let mpris_player = start_mpris(&app).await;
+        return daemon::serve(app, listener, mpris_player).await;
+    }
+    if command != Command::Tui {
+        return cli::run(command, db, backend, &socket, &mut io::stdout().lock()).await;
+    }
+
     let mut app = App::new(db);
+    app.backend = backend;
+    // Leave playback, downloads, pushing progress and MPRIS to the daemon if
+    // one is running.
+    app.daemon = DaemonClient::connect(&socket).await;
+    app.manage_downloads = app.daemon.is_none();
 
-    // Shared mpv player handle.
+    // Shared player handle.
     let player: PlayerHandle = Arc::new(Mutex::new(None));
 
-    // Spawn background task that polls mpv for playback progress.
-    event::spawn_playback_poller(Arc::clone(&player), app.action_tx.clone());
-
-    // Spawn background task that pushes dirty progress to the server
-    // every 30 seconds.
-    event::spawn_progress_pusher(app.action_tx.clone());
-
-    // Set up MPRIS D-Bus service for system media keys and GNOME tray.
-    let mpris_player: Option<Rc<mpris_server::Player>> =
-        match mpris::create_mpris_player(app.action_tx.clone()).await {
-            Ok(p) => {
-                // The MPRIS event loop must run on the local task set
-                // since Player is !Send.
-                let p_clone = Rc::clone(&p);
-                tokio::task::spawn_local(async move {
-                    p_clone.run().await;
-                });
-                Some(p)
-            }
-            Err(e) => {
-                eprintln!("warning: MPRIS setup failed (media keys won't work): {}", e);
-                None
-            }
-        };
+    let mut mpris_player = None;
+    if app.daemon.is_none() {
+        // Pick up downloads interrupted by the last quit.
+        event::resume_downloads(&app);
+
+        // Spawn background task that pushes dirty progress to the server
+        // every 30 seconds.
+        event::spawn_progress_pusher(app.action_tx.clone());
+
+        mpris_player = start_mpris(&app).await;
+    }
 
     // If we already have a token, kick off a sync immediately.
     if matches!(app.view, View::Inbox(_) | View::PodcastList(_)) {
@@ -99,35 +118,21 @@ async fn run() -> Result<()> {
             maybe_event = events.next() => {
                 if let Some(Ok(crossterm::event::Event::Key(key))) = maybe_event {
                     if let Some(action) = event::map_key(&app, key) {
-                        event::handle_async_action(&action, &mut app, &player);
-                        app.update(action);
+                        event::dispatch(action, &mut app, &player);
                     }
                 }
             }
             Some(action) = app.action_rx.recv() => {
-                event::handle_async_action(&action, &mut app, &player);
-                app.update(action);
+                event::dispatch(action, &mut app, &player);
             }
             _ = tick.tick() => {
-                app.update(Action::Tick);
+                event::dispatch(Action::Tick, &mut app, &player);
             }
         }
 
         // Keep MPRIS state in sync with the TUI.
         if let Some(ref mpris_p) = mpris_player {
-            if let Some(ref np) = app.now_playing {
-                mpris::update_mpris_state(
-                    mpris_p,
-                    &np.episode_title,
-                    np.state.position_secs,
-                    np.state.duration_secs,
-                    np.state.paused,
-                    true,
-                )
-                .await;
-            } else {
-                mpris::update_mpris_state(mpris_p, "", 0, 0, false, false).await;
-            }
+            mpris::update_from_app(mpris_p, &app).await;
         }
 
         if app.should_quit {
@@ -154,3 +159,22 @@ async fn run() -> Result<()> {
 
     Ok(())
 }
+
+/// Set up the MPRIS D-Bus service for system media keys and GNOME tray.
+async fn start_mpris(app: &App) -> Option<Rc<mpris_server::Player>> {
+    match mpris::create_mpris_player(app.action_tx.clone()).await {
+        Ok(p) => {
+            // The MPRIS event loop must run on the local task set
+            // since Player is !Send.
+            let p_clone = Rc::clone(&p);
+            tokio::task::spawn_local(async move {
+                p_clone.run().await;
+            });
+            Some(p)
+        }
+        Err(e) => {
+            eprintln!("warning: MPRIS setup failed (media keys won't work): {}", e);
+            None
+        }
+    }
+}
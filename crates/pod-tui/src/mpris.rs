@@ -9,7 +9,7 @@ use std::rc::Rc;
 use mpris_server::{Metadata, PlaybackStatus, Player, Time};
 use tokio::sync::mpsc;
 
-use crate::app::Action;
+use crate::app::{Action, App};
 
 /// Create the MPRIS player and wire its callbacks to send Actions back to
 /// the TUI main loop. Returns the Player handle (for updating metadata and
@@ -21,6 +21,8 @@ pub async fn create_mpris_player(
         .can_play(true)
         .can_pause(true)
         .can_seek(true)
+        .can_go_next(true)
+        .can_go_previous(true)
         .can_control(true)
         .identity("Pod")
         .build()
@@ -66,9 +68,41 @@ pub async fn create_mpris_player(
         });
     }
 
+    // Next/previous track step through the episode's chapters.
+    {
+        let tx = tx.clone();
+        player.connect_next(move |_| {
+            let _ = tx.send(Action::NextChapter);
+        });
+    }
+    {
+        let tx = tx.clone();
+        player.connect_previous(move |_| {
+            let _ = tx.send(Action::PreviousChapter);
+        });
+    }
+
     Ok(player)
 }
 
+/// Update the MPRIS player state to match whatever `app` is playing.
+pub async fn update_from_app(player: &Player, app: &App) {
+    match app.now_playing {
+        Some(ref np) => {
+            update_mpris_state(
+                player,
+                &np.episode_title,
+                np.state.position_secs,
+                np.state.duration_secs,
+                np.state.paused,
+                true,
+            )
+            .await
+        }
+        None => update_mpris_state(player, "", 0, 0, false, false).await,
+    }
+}
+
 /// Update the MPRIS player state to match the current TUI playback state.
 /// Call this whenever playback state changes.
 pub async fn update_mpris_state(
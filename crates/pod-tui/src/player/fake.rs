@@ -0,0 +1,102 @@
+//! A player that plays nothing, for tests. It keeps its state in memory and
+//! reports changes like a real backend, so playback flows can be driven
+//! without mpv or an audio device. Time doesn't pass: the position only
+//! moves when seeking, and seeking to the end finishes the episode.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use super::{PlaybackBackend, PlaybackState};
+use crate::app::Action;
+
+pub struct FakePlayer {
+    state: Mutex<PlaybackState>,
+    ended: AtomicBool,
+    events: mpsc::UnboundedSender<Action>,
+}
+
+impl FakePlayer {
+    pub fn new(
+        start_position: i32,
+        duration_secs: i32,
+        events: mpsc::UnboundedSender<Action>,
+    ) -> Self {
+        Self {
+            state: Mutex::new(PlaybackState {
+                position_secs: start_position,
+                duration_secs,
+                paused: false,
+            }),
+            ended: AtomicBool::new(false),
+            events,
+        }
+    }
+
+    /// Apply `change` to the state and report the result.
+    fn update(&self, change: impl FnOnce(&mut PlaybackState)) {
+        let mut state = self.state.lock().expect("fake player lock poisoned");
+        change(&mut state);
+        let _ = self.events.send(Action::PlaybackUpdate(state.clone()));
+    }
+}
+
+#[async_trait]
+impl PlaybackBackend for FakePlayer {
+    async fn toggle_pause(&self) -> anyhow::Result<()> {
+        self.update(|state| state.paused = !state.paused);
+        Ok(())
+    }
+
+    async fn pause(&self) -> anyhow::Result<()> {
+        self.update(|state| state.paused = true);
+        Ok(())
+    }
+
+    async fn seek(&self, offset_secs: i32) -> anyhow::Result<()> {
+        let position = self.position().await.unwrap_or(0) + offset_secs;
+        self.seek_to(position as f64).await
+    }
+
+    async fn seek_to(&self, position_secs: f64) -> anyhow::Result<()> {
+        if self.has_ended() {
+            anyhow::bail!("playback has ended");
+        }
+        let mut state = self.state.lock().expect("fake player lock poisoned");
+        state.position_secs = (position_secs as i32).clamp(0, state.duration_secs);
+        if state.position_secs == state.duration_secs {
+            self.ended.store(true, Ordering::SeqCst);
+            let _ = self.events.send(Action::PlaybackFinished);
+        } else {
+            let _ = self.events.send(Action::PlaybackUpdate(state.clone()));
+        }
+        Ok(())
+    }
+
+    async fn set_speed(&self, _speed: f64) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn set_volume(&self, _volume: i32) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn set_skip_silence(&self, _enabled: bool) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn position(&self) -> Option<i32> {
+        Some(self.state.lock().expect("fake player lock poisoned").position_secs)
+    }
+
+    fn has_ended(&self) -> bool {
+        self.ended.load(Ordering::SeqCst)
+    }
+
+    async fn stop(&mut self) -> anyhow::Result<()> {
+        self.ended.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
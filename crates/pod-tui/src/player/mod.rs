@@ -0,0 +1,160 @@
+//! Audio playback. Episodes are played by a [`PlaybackBackend`], started
+//! through [`Backend`]: mpv by default, rodio in-process when built with the
+//! `native-audio` feature, or a fake player in tests.
+
+mod fake;
+mod mpv;
+#[cfg(feature = "native-audio")]
+mod native;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use pod_model::{Chapter, SubscriptionSettings};
+
+use crate::app::Action;
+
+pub use fake::FakePlayer;
+pub use mpv::MpvPlayer;
+#[cfg(feature = "native-audio")]
+pub use native::NativePlayer;
+
+/// Playback state pushed to the TUI whenever it changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlaybackState {
+    pub position_secs: i32,
+    pub duration_secs: i32,
+    pub paused: bool,
+}
+
+/// Playback speeds the player can be stepped between.
+pub const MIN_SPEED: f64 = 0.5;
+pub const MAX_SPEED: f64 = 3.0;
+pub const SPEED_STEP: f64 = 0.1;
+pub const VOLUME_STEP: i32 = 5;
+
+/// A playing episode. Backends report state changes and how playback ended
+/// by sending `PlaybackUpdate`, `PlaybackFinished`, `PlaybackError` or
+/// `StopPlayback` to the channel they were started with.
+#[async_trait]
+pub trait PlaybackBackend: Send + Sync {
+    async fn toggle_pause(&self) -> anyhow::Result<()>;
+
+    async fn pause(&self) -> anyhow::Result<()>;
+
+    /// Seek relative to the current position.
+    async fn seek(&self, offset_secs: i32) -> anyhow::Result<()>;
+
+    /// Seek to an absolute position.
+    async fn seek_to(&self, position_secs: f64) -> anyhow::Result<()>;
+
+    async fn set_speed(&self, speed: f64) -> anyhow::Result<()>;
+
+    /// Set the volume as a percentage of the file's own level.
+    async fn set_volume(&self, volume: i32) -> anyhow::Result<()>;
+
+    /// Turn skipping silence on or off.
+    async fn set_skip_silence(&self, enabled: bool) -> anyhow::Result<()>;
+
+    /// The current playback position, asked of the backend rather than taken
+    /// from the last update.
+    async fn position(&self) -> Option<i32>;
+
+    /// Chapters embedded in the file itself. Empty until the file has been
+    /// loaded, or if the backend can't read them.
+    async fn chapter_list(&self) -> Vec<Chapter> {
+        Vec::new()
+    }
+
+    /// Whether playback has ended and this player can only be stopped.
+    fn has_ended(&self) -> bool;
+
+    async fn stop(&mut self) -> anyhow::Result<()>;
+}
+
+/// Which [`PlaybackBackend`] episodes are played with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// An mpv subprocess controlled over its JSON IPC socket.
+    Mpv,
+    /// rodio in-process, for systems without mpv.
+    #[cfg(feature = "native-audio")]
+    Native,
+    /// A [`FakePlayer`] for an episode `duration_secs` long.
+    Fake { duration_secs: i32 },
+}
+
+impl Backend {
+    /// The backend called `name` ("mpv" or "native"), if it was built in.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mpv" => Some(Backend::Mpv),
+            #[cfg(feature = "native-audio")]
+            "native" => Some(Backend::Native),
+            _ => None,
+        }
+    }
+
+    /// mpv if it is installed, otherwise native playback when built in.
+    pub fn detect() -> Self {
+        #[cfg(feature = "native-audio")]
+        if !mpv::is_installed() {
+            return Backend::Native;
+        }
+        Backend::Mpv
+    }
+
+    /// Start playing `audio_url` (a URL or a local path) at the show's
+    /// playback speed, skipping its intro and outro. State changes and the
+    /// end of playback are sent to `events`.
+    pub async fn start(
+        self,
+        audio_url: &str,
+        start_position: i32,
+        settings: &SubscriptionSettings,
+        events: mpsc::UnboundedSender<Action>,
+    ) -> anyhow::Result<Box<dyn PlaybackBackend>> {
+        Ok(match self {
+            Backend::Mpv => {
+                Box::new(MpvPlayer::start(audio_url, start_position, settings, events).await?)
+            }
+            #[cfg(feature = "native-audio")]
+            Backend::Native => {
+                Box::new(NativePlayer::start(audio_url, start_position, settings, events).await?)
+            }
+            Backend::Fake { duration_secs } => Box::new(FakePlayer::new(
+                start_offset(start_position, settings),
+                duration_secs,
+                events,
+            )),
+        })
+    }
+}
+
+/// Where playback of an episode starts: the saved position, but never inside
+/// the show's intro.
+pub fn start_offset(start_position: i32, settings: &SubscriptionSettings) -> i32 {
+    start_position.max(settings.skip_intro_secs)
+}
+
+/// `speed` moved by `steps` speed steps, kept within the supported range.
+/// Rounded to one decimal so repeated steps don't drift.
+pub fn step_speed(speed: f64, steps: i32) -> f64 {
+    let stepped = speed + steps as f64 * SPEED_STEP;
+    (stepped.clamp(MIN_SPEED, MAX_SPEED) * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_steps_stay_in_range() {
+        assert_eq!(step_speed(1.0, 5), 1.5);
+        assert_eq!(step_speed(1.5, -1), 1.4);
+        assert_eq!(step_speed(0.6, -3), MIN_SPEED);
+        assert_eq!(step_speed(2.95, 1), MAX_SPEED);
+        // Speeds synced from the server may be off the step grid.
+        assert_eq!(step_speed(1.25, 1), 1.4);
+    }
+}
@@ -0,0 +1,512 @@
+//! Playback via mpv's JSON IPC protocol.
+//!
+//! Spawns an mpv subprocess with `--input-ipc-server` pointing at a Unix
+//! socket and keeps one connection to it open. Commands are matched to
+//! their replies by request id, and playback state arrives as
+//! `observe_property` events that are forwarded to the app as actions.
+//! This is Linux-specific for now.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+use pod_model::{Chapter, SubscriptionSettings};
+
+use super::{start_offset, PlaybackBackend, PlaybackState};
+use crate::app::Action;
+
+/// Label of the skip-silence audio filter, so it can be removed again.
+const SKIP_SILENCE_LABEL: &str = "skipsilence";
+/// Drops every stretch of half a second or more below -40dB.
+const SKIP_SILENCE_FILTER: &str =
+    "silenceremove=stop_periods=-1:stop_duration=0.5:stop_threshold=-40dB";
+
+/// Properties observed for `PlaybackState`, by observer id.
+const OBSERVED_PROPERTIES: [&str; 3] = ["time-pos", "duration", "pause"];
+
+/// How long to wait for mpv to answer a command.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether an `mpv` executable is on the `PATH`.
+#[cfg(feature = "native-audio")]
+pub(super) fn is_installed() -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join("mpv").is_file()))
+}
+
+pub struct MpvPlayer {
+    process: Child,
+    socket_path: PathBuf,
+    ipc: Arc<Ipc>,
+    reader: JoinHandle<()>,
+}
+
+/// The write half of the connection to mpv plus what the reader task needs
+/// to route replies and report how playback ended.
+struct Ipc {
+    writer: Mutex<OwnedWriteHalf>,
+    pending: std::sync::Mutex<HashMap<u64, oneshot::Sender<serde_json::Value>>>,
+    next_request_id: AtomicU64,
+    /// Set once the file has ended or the connection closed.
+    ended: AtomicBool,
+    /// Set when we told mpv to quit, so that isn't reported as an ending.
+    stopping: AtomicBool,
+}
+
+/// Why mpv stopped playing, from its `end-file` event.
+#[derive(Debug, PartialEq, Eq)]
+enum PlaybackEnd {
+    /// Reached the end of the file (or the skipped outro).
+    Eof,
+    /// mpv was quit, e.g. by closing it from outside the TUI.
+    Quit,
+    Error(String),
+}
+
+impl MpvPlayer {
+    /// Launch mpv playing the given audio URL at the show's playback speed,
+    /// skipping its intro and outro. The player starts in the background and
+    /// can be controlled via IPC. State changes and the end of playback are
+    /// sent to `events`.
+    pub async fn start(
+        audio_url: &str,
+        start_position: i32,
+        settings: &SubscriptionSettings,
+        events: mpsc::UnboundedSender<Action>,
+    ) -> anyhow::Result<Self> {
+        let socket_path = std::env::temp_dir().join(format!("pod-mpv-{}.sock", std::process::id()));
+
+        // Remove stale socket if it exists.
+        let _ = std::fs::remove_file(&socket_path);
+
+        let mut command = Command::new("mpv");
+        command
+            .arg("--no-video")
+            .arg("--no-terminal")
+            .arg(format!("--input-ipc-server={}", socket_path.display()))
+            .arg(format!("--start=+{}", start_offset(start_position, settings)))
+            .arg(format!("--speed={}", settings.playback_speed));
+        if settings.skip_outro_secs > 0 {
+            // A negative end time counts back from the end of the file.
+            command.arg(format!("--end=-{}", settings.skip_outro_secs));
+        }
+        let mut process = command
+            .arg("--")
+            .arg(audio_url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("failed to launch mpv (is it installed?): {}", e))?;
+
+        // Wait briefly for mpv to create the socket.
+        for _ in 0..20 {
+            if socket_path.exists() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let stream = match UnixStream::connect(&socket_path).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = process.kill().await;
+                anyhow::bail!(
+                    "could not connect to mpv IPC socket at {}: {}",
+                    socket_path.display(),
+                    e
+                );
+            }
+        };
+        let (read_half, write_half) = stream.into_split();
+        let ipc = Arc::new(Ipc {
+            writer: Mutex::new(write_half),
+            pending: Default::default(),
+            next_request_id: AtomicU64::new(1),
+            ended: AtomicBool::new(false),
+            stopping: AtomicBool::new(false),
+        });
+        let reader = tokio::spawn(read_events(Arc::clone(&ipc), read_half, events));
+
+        let player = Self {
+            process,
+            socket_path,
+            ipc,
+            reader,
+        };
+        for (id, name) in OBSERVED_PROPERTIES.iter().enumerate() {
+            player
+                .ipc_command(&[
+                    serde_json::json!("observe_property"),
+                    serde_json::json!(id),
+                    serde_json::json!(name),
+                ])
+                .await
+                .with_context(|| format!("observe {}", name))?;
+        }
+        Ok(player)
+    }
+
+    /// Send a JSON IPC command and wait for mpv's reply to it.
+    async fn ipc_command(&self, command: &[serde_json::Value]) -> anyhow::Result<serde_json::Value> {
+        if self.has_ended() {
+            anyhow::bail!("mpv is no longer playing");
+        }
+        let request_id = self.ipc.next_request_id.fetch_add(1, Ordering::SeqCst);
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.ipc
+            .pending
+            .lock()
+            .expect("pending requests lock poisoned")
+            .insert(request_id, reply_tx);
+
+        let msg = serde_json::json!({ "command": command, "request_id": request_id });
+        let mut bytes = serde_json::to_vec(&msg).context("serialize IPC command")?;
+        bytes.push(b'\n');
+        let written = self.ipc.writer.lock().await.write_all(&bytes).await;
+        if let Err(e) = written {
+            self.ipc.forget(request_id);
+            return Err(e).context("write to mpv IPC");
+        }
+
+        match tokio::time::timeout(COMMAND_TIMEOUT, reply_rx).await {
+            Ok(Ok(resp)) => Ok(resp),
+            Ok(Err(_)) => anyhow::bail!("mpv closed the IPC connection"),
+            Err(_) => {
+                self.ipc.forget(request_id);
+                anyhow::bail!("mpv did not answer within {:?}", COMMAND_TIMEOUT)
+            }
+        }
+    }
+
+    /// Get a property from mpv. Returns None if the property doesn't exist
+    /// or mpv has exited.
+    async fn get_property(&self, name: &str) -> Option<serde_json::Value> {
+        let resp = self
+            .ipc_command(&[
+                serde_json::json!("get_property"),
+                serde_json::json!(name),
+            ])
+            .await
+            .ok()?;
+
+        if resp.get("error")?.as_str()? == "success" {
+            resp.get("data").cloned()
+        } else {
+            None
+        }
+    }
+
+    async fn set_property(&self, name: &str, value: serde_json::Value) -> anyhow::Result<()> {
+        let resp = self
+            .ipc_command(&[serde_json::json!("set_property"), serde_json::json!(name), value])
+            .await?;
+        match resp.get("error").and_then(|e| e.as_str()) {
+            Some("success") => Ok(()),
+            error => anyhow::bail!("mpv refused {}: {}", name, error.unwrap_or("no response")),
+        }
+    }
+}
+
+#[async_trait]
+impl PlaybackBackend for MpvPlayer {
+    async fn position(&self) -> Option<i32> {
+        let position = self.get_property("time-pos").await?.as_f64()?;
+        Some(position as i32)
+    }
+
+    async fn toggle_pause(&self) -> anyhow::Result<()> {
+        self.ipc_command(&[
+            serde_json::json!("cycle"),
+            serde_json::json!("pause"),
+        ])
+        .await
+        .context("toggle pause")?;
+        Ok(())
+    }
+
+    async fn pause(&self) -> anyhow::Result<()> {
+        self.set_property("pause", serde_json::json!(true))
+            .await
+            .context("pause")
+    }
+
+    async fn seek(&self, offset_secs: i32) -> anyhow::Result<()> {
+        self.ipc_command(&[
+            serde_json::json!("seek"),
+            serde_json::json!(offset_secs),
+            serde_json::json!("relative"),
+        ])
+        .await
+        .context("seek")?;
+        Ok(())
+    }
+
+    async fn seek_to(&self, position_secs: f64) -> anyhow::Result<()> {
+        self.ipc_command(&[
+            serde_json::json!("seek"),
+            serde_json::json!(position_secs),
+            serde_json::json!("absolute"),
+        ])
+        .await
+        .context("seek")?;
+        Ok(())
+    }
+
+    async fn set_speed(&self, speed: f64) -> anyhow::Result<()> {
+        self.set_property("speed", serde_json::json!(speed))
+            .await
+            .context("set speed")
+    }
+
+    async fn set_volume(&self, volume: i32) -> anyhow::Result<()> {
+        self.set_property("volume", serde_json::json!(volume))
+            .await
+            .context("set volume")
+    }
+
+    /// Turn the silence-skipping audio filter on or off.
+    async fn set_skip_silence(&self, enabled: bool) -> anyhow::Result<()> {
+        let command = if enabled {
+            [
+                serde_json::json!("af"),
+                serde_json::json!("add"),
+                serde_json::json!(format!("@{SKIP_SILENCE_LABEL}:lavfi=[{SKIP_SILENCE_FILTER}]")),
+            ]
+        } else {
+            [
+                serde_json::json!("af"),
+                serde_json::json!("remove"),
+                serde_json::json!(format!("@{SKIP_SILENCE_LABEL}")),
+            ]
+        };
+        self.ipc_command(&command).await.context("set skip silence")?;
+        Ok(())
+    }
+
+    /// Chapters mpv found in the file itself (e.g. ID3 `CHAP` frames or MP4
+    /// chapter atoms). Empty until the file has been loaded.
+    async fn chapter_list(&self) -> Vec<Chapter> {
+        let Some(list) = self.get_property("chapter-list").await else {
+            return Vec::new();
+        };
+        list.as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, entry)| {
+                        Some(Chapter {
+                            start_time: entry.get("time")?.as_f64()?,
+                            end_time: None,
+                            title: entry
+                                .get("title")
+                                .and_then(|t| t.as_str())
+                                .map(|t| t.to_string())
+                                .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+                            img: None,
+                            url: None,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    async fn stop(&mut self) -> anyhow::Result<()> {
+        self.ipc.stopping.store(true, Ordering::SeqCst);
+        let _ = self.ipc_command(&[serde_json::json!("quit")]).await;
+        let _ = self.process.wait().await;
+        self.reader.abort();
+        let _ = std::fs::remove_file(&self.socket_path);
+        Ok(())
+    }
+
+    fn has_ended(&self) -> bool {
+        self.ipc.ended.load(Ordering::SeqCst)
+    }
+}
+
+impl Ipc {
+    /// Drop a request that will never be answered.
+    fn forget(&self, request_id: u64) {
+        self.pending
+            .lock()
+            .expect("pending requests lock poisoned")
+            .remove(&request_id);
+    }
+}
+
+/// Read everything mpv sends until the file ends or the connection closes:
+/// hand replies to the commands waiting for them, push state changes as
+/// `PlaybackUpdate`s and finally report how playback ended.
+async fn read_events(
+    ipc: Arc<Ipc>,
+    read_half: OwnedReadHalf,
+    events: mpsc::UnboundedSender<Action>,
+) {
+    let mut lines = BufReader::new(read_half).lines();
+    let mut state = PlaybackState::default();
+    let mut end = None;
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        match msg.get("event").and_then(|e| e.as_str()) {
+            None => {
+                let Some(request_id) = msg.get("request_id").and_then(|id| id.as_u64()) else {
+                    continue;
+                };
+                let reply = ipc
+                    .pending
+                    .lock()
+                    .expect("pending requests lock poisoned")
+                    .remove(&request_id);
+                if let Some(reply) = reply {
+                    let _ = reply.send(msg);
+                }
+            }
+            Some("property-change") => {
+                if apply_property(&mut state, &msg) {
+                    let _ = events.send(Action::PlaybackUpdate(state.clone()));
+                }
+            }
+            Some("end-file") => {
+                end = Some(playback_end(&msg));
+                break;
+            }
+            Some(_) => {}
+        }
+    }
+
+    ipc.ended.store(true, Ordering::SeqCst);
+    // Wake up anything still waiting for a reply.
+    ipc.pending
+        .lock()
+        .expect("pending requests lock poisoned")
+        .clear();
+    if ipc.stopping.load(Ordering::SeqCst) {
+        return;
+    }
+    let action = match end {
+        Some(PlaybackEnd::Eof) => Action::PlaybackFinished,
+        Some(PlaybackEnd::Quit) => Action::StopPlayback,
+        Some(PlaybackEnd::Error(e)) => Action::PlaybackError(e),
+        None => Action::PlaybackError("mpv exited unexpectedly".to_string()),
+    };
+    let _ = events.send(action);
+}
+
+/// Update `state` from a `property-change` event. Returns whether anything
+/// the TUI shows changed; sub-second position changes don't count. Values
+/// mpv doesn't have yet (e.g. while the file loads) keep the last known one.
+fn apply_property(state: &mut PlaybackState, msg: &serde_json::Value) -> bool {
+    let Some(data) = msg.get("data") else {
+        return false;
+    };
+    let previous = state.clone();
+    match msg.get("name").and_then(|n| n.as_str()) {
+        Some("time-pos") => {
+            if let Some(position) = data.as_f64() {
+                state.position_secs = position as i32;
+            }
+        }
+        Some("duration") => {
+            if let Some(duration) = data.as_f64() {
+                state.duration_secs = duration as i32;
+            }
+        }
+        Some("pause") => {
+            if let Some(paused) = data.as_bool() {
+                state.paused = paused;
+            }
+        }
+        _ => {}
+    }
+    *state != previous
+}
+
+fn playback_end(msg: &serde_json::Value) -> PlaybackEnd {
+    match msg.get("reason").and_then(|r| r.as_str()) {
+        Some("eof") => PlaybackEnd::Eof,
+        Some("error") => {
+            let error = msg
+                .get("file_error")
+                .and_then(|e| e.as_str())
+                .unwrap_or("unknown error");
+            PlaybackEnd::Error(format!("mpv could not play the episode: {}", error))
+        }
+        _ => PlaybackEnd::Quit,
+    }
+}
+
+impl Drop for MpvPlayer {
+    fn drop(&mut self) {
+        // Best-effort cleanup: kill mpv if still running.
+        self.reader.abort();
+        let _ = self.process.start_kill();
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn property_changes_update_state() {
+        let mut state = PlaybackState::default();
+        let change = |name: &str, data: serde_json::Value| {
+            serde_json::json!({ "event": "property-change", "id": 0, "name": name, "data": data })
+        };
+        assert!(apply_property(&mut state, &change("duration", serde_json::json!(1800.4))));
+        assert!(apply_property(&mut state, &change("time-pos", serde_json::json!(12.2))));
+        assert!(!apply_property(&mut state, &change("time-pos", serde_json::json!(12.9))));
+        assert!(apply_property(&mut state, &change("pause", serde_json::json!(true))));
+        // Unavailable while a file loads.
+        assert!(!apply_property(&mut state, &change("time-pos", serde_json::Value::Null)));
+        assert_eq!(
+            state,
+            PlaybackState {
+                position_secs: 12,
+                duration_secs: 1800,
+                paused: true,
+            }
+        );
+    }
+
+    #[test]
+    fn end_file_reasons() {
+        let end = |msg: serde_json::Value| playback_end(&msg);
+        assert_eq!(
+            end(serde_json::json!({ "event": "end-file", "reason": "eof" })),
+            PlaybackEnd::Eof
+        );
+        assert_eq!(
+            end(serde_json::json!({ "event": "end-file", "reason": "quit" })),
+            PlaybackEnd::Quit
+        );
+        assert_eq!(
+            end(serde_json::json!({
+                "event": "end-file",
+                "reason": "error",
+                "file_error": "loading failed"
+            })),
+            PlaybackEnd::Error("mpv could not play the episode: loading failed".to_string())
+        );
+    }
+}
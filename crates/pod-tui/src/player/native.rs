@@ -0,0 +1,539 @@
+//! In-process playback with rodio, decoding with symphonia, for systems
+//! without mpv. Local downloads are read directly; HTTP streams are
+//! downloaded to a temporary file while they play. Changing the speed also
+//! changes the pitch, and skipping silence isn't supported.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use rodio::{Decoder, OutputStream, Sink, Source};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use pod_model::SubscriptionSettings;
+
+use super::{start_offset, PlaybackBackend, PlaybackState};
+use crate::app::Action;
+
+/// How often the sink is checked for state changes and the end of playback.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct NativePlayer {
+    sink: Arc<Sink>,
+    clock: Arc<Mutex<Clock>>,
+    ended: Arc<AtomicBool>,
+    stopping: Arc<AtomicBool>,
+    watcher: JoinHandle<()>,
+    /// The output thread holds the audio device until this is dropped.
+    _output: std::sync::mpsc::Sender<()>,
+    stream: Option<Stream>,
+}
+
+impl NativePlayer {
+    /// Start playing `audio_url`, a local path or an HTTP(S) URL.
+    pub async fn start(
+        audio_url: &str,
+        start_position: i32,
+        settings: &SubscriptionSettings,
+        events: mpsc::UnboundedSender<Action>,
+    ) -> anyhow::Result<Self> {
+        let (sink, output) = open_output().await?;
+
+        let stream = if audio_url.starts_with("http://") || audio_url.starts_with("https://") {
+            Some(Stream::start(audio_url).await?)
+        } else {
+            None
+        };
+        let start = start_offset(start_position, settings) as f64;
+        let speed = settings.playback_speed as f64;
+        let appended = {
+            let sink = Arc::clone(&sink);
+            let reader = match &stream {
+                Some(stream) => Reader::Stream(stream.reader()?),
+                None => Reader::File(BufReader::new(
+                    File::open(audio_url).with_context(|| format!("open {}", audio_url))?,
+                )),
+            };
+            tokio::task::spawn_blocking(move || {
+                let duration = match reader {
+                    Reader::File(file) => append(&sink, file)?,
+                    Reader::Stream(stream) => append(&sink, stream)?,
+                };
+                sink.set_speed(speed as f32);
+                if start > 0.0 {
+                    sink.try_seek(Duration::from_secs_f64(start))
+                        .map_err(|e| anyhow::anyhow!("seek to start: {}", e))?;
+                }
+                anyhow::Ok(duration)
+            })
+            .await?
+        };
+        let duration = match appended {
+            Ok(duration) => duration,
+            // A failed download shows up as undecodable audio; report why.
+            Err(e) => match stream.as_ref().and_then(Stream::error) {
+                Some(download_error) => anyhow::bail!("download failed: {}", download_error),
+                None => return Err(e),
+            },
+        };
+
+        // Stop before the outro, when we know where the file ends.
+        let end = duration
+            .map(|d| d - settings.skip_outro_secs as f64)
+            .filter(|_| settings.skip_outro_secs > 0);
+        let clock = Arc::new(Mutex::new(Clock {
+            media: start,
+            sink: start,
+            speed,
+        }));
+        let ended = Arc::new(AtomicBool::new(false));
+        let stopping = Arc::new(AtomicBool::new(false));
+        let watcher = tokio::spawn(watch(Watch {
+            sink: Arc::clone(&sink),
+            clock: Arc::clone(&clock),
+            duration: duration.unwrap_or(0.0),
+            end,
+            ended: Arc::clone(&ended),
+            stopping: Arc::clone(&stopping),
+            download: stream.as_ref().map(|s| Arc::clone(&s.progress)),
+            events,
+        }));
+
+        Ok(Self {
+            sink,
+            clock,
+            ended,
+            stopping,
+            watcher,
+            _output: output,
+            stream,
+        })
+    }
+
+    fn media_position(&self) -> f64 {
+        let clock = self.clock.lock().expect("clock lock poisoned");
+        clock.position(self.sink.get_pos().as_secs_f64())
+    }
+}
+
+#[async_trait]
+impl PlaybackBackend for NativePlayer {
+    async fn toggle_pause(&self) -> anyhow::Result<()> {
+        if self.sink.is_paused() {
+            self.sink.play();
+        } else {
+            self.sink.pause();
+        }
+        Ok(())
+    }
+
+    async fn pause(&self) -> anyhow::Result<()> {
+        self.sink.pause();
+        Ok(())
+    }
+
+    async fn seek(&self, offset_secs: i32) -> anyhow::Result<()> {
+        let position = (self.media_position() + offset_secs as f64).max(0.0);
+        self.seek_to(position).await
+    }
+
+    async fn seek_to(&self, position_secs: f64) -> anyhow::Result<()> {
+        let sink = Arc::clone(&self.sink);
+        // Seeking waits for the audio thread to carry it out.
+        tokio::task::spawn_blocking(move || sink.try_seek(Duration::from_secs_f64(position_secs)))
+            .await?
+            .map_err(|e| anyhow::anyhow!("seek: {}", e))?;
+        let mut clock = self.clock.lock().expect("clock lock poisoned");
+        // After a seek the sink counts from the new position.
+        clock.media = position_secs;
+        clock.sink = position_secs;
+        Ok(())
+    }
+
+    async fn set_speed(&self, speed: f64) -> anyhow::Result<()> {
+        let mut clock = self.clock.lock().expect("clock lock poisoned");
+        let sink_pos = self.sink.get_pos().as_secs_f64();
+        *clock = Clock {
+            media: clock.position(sink_pos),
+            sink: sink_pos,
+            speed,
+        };
+        self.sink.set_speed(speed as f32);
+        Ok(())
+    }
+
+    async fn set_volume(&self, volume: i32) -> anyhow::Result<()> {
+        self.sink.set_volume(volume as f32 / 100.0);
+        Ok(())
+    }
+
+    async fn set_skip_silence(&self, enabled: bool) -> anyhow::Result<()> {
+        if enabled {
+            anyhow::bail!("skipping silence needs the mpv backend");
+        }
+        Ok(())
+    }
+
+    async fn position(&self) -> Option<i32> {
+        Some(self.media_position() as i32)
+    }
+
+    fn has_ended(&self) -> bool {
+        self.ended.load(Ordering::SeqCst)
+    }
+
+    async fn stop(&mut self) -> anyhow::Result<()> {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.watcher.abort();
+        self.sink.stop();
+        self.stream.take();
+        Ok(())
+    }
+}
+
+impl Drop for NativePlayer {
+    fn drop(&mut self) {
+        self.watcher.abort();
+        self.sink.stop();
+    }
+}
+
+/// Maps the sink's position to the position in the episode. rodio counts
+/// played time rather than media time once the speed changes, so the media
+/// position is worked out from the last seek or speed change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Clock {
+    /// Media position at the last seek or speed change...
+    media: f64,
+    /// ...and the sink's position at that moment.
+    sink: f64,
+    speed: f64,
+}
+
+impl Clock {
+    fn position(&self, sink_pos: f64) -> f64 {
+        self.media + (sink_pos - self.sink).max(0.0) * self.speed
+    }
+}
+
+/// Open the default audio device. `OutputStream` can't leave the thread it
+/// was created on, so a thread holds it until the returned sender is dropped.
+async fn open_output() -> anyhow::Result<(Arc<Sink>, std::sync::mpsc::Sender<()>)> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let (keep_tx, keep_rx) = std::sync::mpsc::channel::<()>();
+    std::thread::spawn(move || {
+        let (_stream, handle) = match OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                let _ = ready_tx.send(Err(anyhow::anyhow!("open audio device: {}", e)));
+                return;
+            }
+        };
+        let sink = Sink::try_new(&handle)
+            .map(Arc::new)
+            .map_err(|e| anyhow::anyhow!("open audio device: {}", e));
+        if ready_tx.send(sink).is_ok() {
+            // Blocks until the player is dropped.
+            let _ = keep_rx.recv();
+        }
+    });
+    let sink = ready_rx.await.context("audio output thread exited")??;
+    Ok((sink, keep_tx))
+}
+
+/// Decode `reader` onto the end of `sink`. Returns the duration in seconds
+/// if the file says what it is.
+fn append<R>(sink: &Sink, reader: R) -> anyhow::Result<Option<f64>>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    let decoder = Decoder::new(reader).context("decode audio")?;
+    let duration = decoder.total_duration().map(|d| d.as_secs_f64());
+    sink.append(decoder);
+    Ok(duration)
+}
+
+enum Reader {
+    File(BufReader<File>),
+    Stream(StreamReader),
+}
+
+struct Watch {
+    sink: Arc<Sink>,
+    clock: Arc<Mutex<Clock>>,
+    duration: f64,
+    end: Option<f64>,
+    ended: Arc<AtomicBool>,
+    stopping: Arc<AtomicBool>,
+    download: Option<Arc<Progress>>,
+    events: mpsc::UnboundedSender<Action>,
+}
+
+/// Report state changes until the sink runs dry or reaches the outro, then
+/// report how playback ended. rodio has no events, so this polls the sink.
+async fn watch(w: Watch) {
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+    let mut last = None;
+    loop {
+        interval.tick().await;
+        let position = w
+            .clock
+            .lock()
+            .expect("clock lock poisoned")
+            .position(w.sink.get_pos().as_secs_f64());
+        let state = PlaybackState {
+            position_secs: position as i32,
+            duration_secs: w.duration as i32,
+            paused: w.sink.is_paused(),
+        };
+        if last.as_ref() != Some(&state) {
+            let _ = w.events.send(Action::PlaybackUpdate(state.clone()));
+            last = Some(state);
+        }
+        if w.sink.empty() || w.end.is_some_and(|end| position >= end) {
+            break;
+        }
+    }
+
+    w.sink.stop();
+    w.ended.store(true, Ordering::SeqCst);
+    if w.stopping.load(Ordering::SeqCst) {
+        return;
+    }
+    // The decoder stops at the first read error, so a broken download
+    // looks like the end of the file.
+    let action = match w.download.as_deref().and_then(Progress::error) {
+        Some(e) => Action::PlaybackError(format!("download failed: {}", e)),
+        None => Action::PlaybackFinished,
+    };
+    let _ = w.events.send(action);
+}
+
+/// An HTTP download written to a temporary file as it arrives, so it can be
+/// played (and seeked in) before it is complete.
+struct Stream {
+    path: PathBuf,
+    progress: Arc<Progress>,
+    task: JoinHandle<()>,
+}
+
+/// How much of a download has arrived, shared between the download task and
+/// the readers waiting on it.
+#[derive(Default)]
+struct Progress {
+    state: Mutex<Downloaded>,
+    arrived: Condvar,
+}
+
+#[derive(Default)]
+struct Downloaded {
+    len: u64,
+    total: Option<u64>,
+    done: bool,
+    error: Option<String>,
+}
+
+impl Stream {
+    async fn start(url: &str) -> anyhow::Result<Self> {
+        let response = reqwest::get(url)
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("fetch {}", url))?;
+        let path = std::env::temp_dir().join(format!("pod-stream-{}.part", std::process::id()));
+        let file = tokio::fs::File::create(&path)
+            .await
+            .with_context(|| format!("create {}", path.display()))?;
+        let progress = Arc::new(Progress::default());
+        progress.lock().total = response.content_length();
+        let task = tokio::spawn(download(response, file, Arc::clone(&progress)));
+        Ok(Self {
+            path,
+            progress,
+            task,
+        })
+    }
+
+    fn reader(&self) -> anyhow::Result<StreamReader> {
+        Ok(StreamReader {
+            file: File::open(&self.path).with_context(|| format!("open {}", self.path.display()))?,
+            pos: 0,
+            progress: Arc::clone(&self.progress),
+        })
+    }
+
+    fn error(&self) -> Option<String> {
+        self.progress.error()
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        self.task.abort();
+        // Wake up any reader still waiting for data.
+        self.progress.finish(None);
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl Progress {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Downloaded> {
+        self.state.lock().expect("download progress lock poisoned")
+    }
+
+    fn finish(&self, error: Option<String>) {
+        let mut state = self.lock();
+        state.done = true;
+        state.error = state.error.take().or(error);
+        self.arrived.notify_all();
+    }
+
+    fn error(&self) -> Option<String> {
+        self.lock().error.clone()
+    }
+}
+
+async fn download(
+    mut response: reqwest::Response,
+    mut file: tokio::fs::File,
+    progress: Arc<Progress>,
+) {
+    let result = async {
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            // Readers open the file separately, so it must reach the OS
+            // before they are told about it.
+            file.flush().await?;
+            let mut state = progress.lock();
+            state.len += chunk.len() as u64;
+            progress.arrived.notify_all();
+        }
+        anyhow::Ok(())
+    }
+    .await;
+    progress.finish(result.err().map(|e| e.to_string()));
+}
+
+/// Reads a [`Stream`]'s file, waiting for data that hasn't arrived yet.
+struct StreamReader {
+    file: File,
+    pos: u64,
+    progress: Arc<Progress>,
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.progress.lock();
+        while state.len <= self.pos && !state.done {
+            state = self
+                .progress
+                .arrived
+                .wait(state)
+                .expect("download progress lock poisoned");
+        }
+        let available = state.len.saturating_sub(self.pos);
+        drop(state);
+
+        let len = buf.len().min(available.try_into().unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        self.file.seek(SeekFrom::Start(self.pos))?;
+        let read = self.file.read(&mut buf[..len])?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for StreamReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+            SeekFrom::End(offset) => {
+                // Without a Content-Length the end is only known once the
+                // whole file has arrived.
+                let mut state = self.progress.lock();
+                while state.total.is_none() && !state.done {
+                    state = self
+                        .progress
+                        .arrived
+                        .wait(state)
+                        .expect("download progress lock poisoned");
+                }
+                state.total.unwrap_or(state.len).checked_add_signed(offset)
+            }
+        };
+        self.pos = pos.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before start of stream")
+        })?;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_tracks_media_time_across_speed_changes() {
+        let mut clock = Clock {
+            media: 100.0,
+            sink: 100.0,
+            speed: 1.0,
+        };
+        assert_eq!(clock.position(110.0), 110.0);
+
+        // At 1.5x, ten seconds of listening cover fifteen of the episode.
+        clock = Clock {
+            media: clock.position(110.0),
+            sink: 110.0,
+            speed: 1.5,
+        };
+        assert_eq!(clock.position(120.0), 125.0);
+        // The sink may not have caught up with a seek yet.
+        assert_eq!(clock.position(90.0), 110.0);
+    }
+
+    #[test]
+    fn stream_reader_waits_for_data() {
+        let path = std::env::temp_dir().join(format!("pod-stream-test-{}", std::process::id()));
+        std::fs::write(&path, b"").unwrap();
+        let progress = Arc::new(Progress::default());
+        let mut reader = StreamReader {
+            file: File::open(&path).unwrap(),
+            pos: 0,
+            progress: Arc::clone(&progress),
+        };
+
+        let writer = {
+            let (path, progress) = (path.clone(), Arc::clone(&progress));
+            std::thread::spawn(move || {
+                for chunk in [&b"hello "[..], b"world"] {
+                    std::thread::sleep(Duration::from_millis(20));
+                    let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+                    std::io::Write::write_all(&mut file, chunk).unwrap();
+                    progress.lock().len += chunk.len() as u64;
+                    progress.arrived.notify_all();
+                }
+                progress.finish(None);
+            })
+        };
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).unwrap();
+        writer.join().unwrap();
+        assert_eq!(contents, "hello world");
+
+        assert_eq!(reader.seek(SeekFrom::End(-5)).unwrap(), 6);
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "world");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
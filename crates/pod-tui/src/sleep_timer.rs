@@ -0,0 +1,201 @@
+//! Sleep timer for the now-playing episode: pauses playback after a fixed
+//! time or at the end of the current chapter or episode, fading the volume
+//! out first.
+
+use std::time::{Duration, Instant};
+
+use crate::app::NowPlaying;
+
+/// The volume fades out over this many seconds before the timer fires.
+pub const FADE_SECS: f64 = 30.0;
+
+/// Fixed durations offered, in minutes, in the order they are cycled.
+const DURATIONS_MINS: [u64; 4] = [15, 30, 45, 60];
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SleepTimer {
+    /// Fires `minutes` after it was set, at `ends_at`.
+    After {
+        minutes: u64,
+        #[serde(with = "secs_from_now")]
+        ends_at: Instant,
+    },
+    /// Fires when playback reaches `end`, the start of the chapter after the
+    /// one that was playing when the timer was set.
+    EndOfChapter { end: f64 },
+    /// Lets the episode finish and stops before the queue moves on.
+    EndOfEpisode,
+}
+
+impl SleepTimer {
+    /// The timer that follows `current` when cycling through the options:
+    /// the fixed durations, end of chapter (unless `np` is in its last
+    /// chapter or has none), end of episode, then off.
+    pub fn cycle(current: Option<&SleepTimer>, np: &NowPlaying, now: Instant) -> Option<Self> {
+        let after = |minutes| SleepTimer::After {
+            minutes,
+            ends_at: now + Duration::from_secs(minutes * 60),
+        };
+        match current {
+            None => Some(after(DURATIONS_MINS[0])),
+            Some(SleepTimer::After { minutes, .. }) => {
+                if let Some(next) = DURATIONS_MINS.iter().find(|m| *m > minutes) {
+                    return Some(after(*next));
+                }
+                let position = np.state.position_secs as f64;
+                match crate::chapters::next_start(&np.chapters, position) {
+                    Some(end) => Some(SleepTimer::EndOfChapter { end }),
+                    None => Some(SleepTimer::EndOfEpisode),
+                }
+            }
+            Some(SleepTimer::EndOfChapter { .. }) => Some(SleepTimer::EndOfEpisode),
+            Some(SleepTimer::EndOfEpisode) => None,
+        }
+    }
+
+    /// Seconds of listening left before the timer fires, or `None` while
+    /// that isn't known yet (e.g. before the player reports the duration).
+    pub fn remaining(&self, np: &NowPlaying, now: Instant) -> Option<f64> {
+        let end = match self {
+            SleepTimer::After { ends_at, .. } => {
+                return Some(ends_at.saturating_duration_since(now).as_secs_f64());
+            }
+            SleepTimer::EndOfChapter { end } => *end,
+            SleepTimer::EndOfEpisode if np.state.duration_secs > 0 => np.state.duration_secs as f64,
+            SleepTimer::EndOfEpisode => return None,
+        };
+        Some(((end - np.state.position_secs as f64) / np.speed).max(0.0))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SleepTimer::After { minutes, .. } => format!("{} min", minutes),
+            SleepTimer::EndOfChapter { .. } => "end of chapter".to_string(),
+            SleepTimer::EndOfEpisode => "end of episode".to_string(),
+        }
+    }
+}
+
+/// An `Instant` only means something inside one process, so it is sent to
+/// the daemon's clients as the seconds until it.
+mod secs_from_now {
+    use std::time::{Duration, Instant};
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(at: &Instant, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_f64(at.saturating_duration_since(Instant::now()).as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Instant, D::Error> {
+        let secs = f64::deserialize(d)?.max(0.0);
+        Ok(Instant::now() + Duration::from_secs_f64(secs))
+    }
+}
+
+/// The volume to play at with `remaining` seconds left on the timer.
+pub fn fade_volume(volume: i32, remaining: f64) -> i32 {
+    if remaining >= FADE_SECS {
+        volume
+    } else {
+        (volume as f64 * remaining / FADE_SECS).round() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pod_model::Chapter;
+
+    use super::*;
+    use crate::player::PlaybackState;
+
+    fn now_playing(position_secs: i32, duration_secs: i32, speed: f64) -> NowPlaying {
+        NowPlaying {
+            episode_id: "e".to_string(),
+            podcast_id: "p".to_string(),
+            episode_title: "Episode".to_string(),
+            state: PlaybackState {
+                position_secs,
+                duration_secs,
+                paused: false,
+            },
+            speed,
+            chapters: [0.0, 600.0]
+                .into_iter()
+                .map(|start_time| Chapter {
+                    start_time,
+                    end_time: None,
+                    title: String::new(),
+                    img: None,
+                    url: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn cycles_through_modes() {
+        let now = Instant::now();
+        let np = now_playing(300, 1200, 1.0);
+        let mut timer = None;
+        let mut labels = Vec::new();
+        for _ in 0..7 {
+            timer = SleepTimer::cycle(timer.as_ref(), &np, now);
+            labels.push(timer.map(|t| t.label()));
+        }
+        assert_eq!(
+            labels,
+            [
+                Some("15 min"),
+                Some("30 min"),
+                Some("45 min"),
+                Some("60 min"),
+                Some("end of chapter"),
+                Some("end of episode"),
+                None,
+            ]
+            .map(|l| l.map(str::to_string))
+        );
+
+        let longest = SleepTimer::After {
+            minutes: 60,
+            ends_at: now,
+        };
+        assert_eq!(
+            SleepTimer::cycle(Some(&longest), &np, now),
+            Some(SleepTimer::EndOfChapter { end: 600.0 })
+        );
+        // In the last chapter the longest duration is followed by end of
+        // episode.
+        assert_eq!(
+            SleepTimer::cycle(Some(&longest), &now_playing(900, 1200, 1.0), now),
+            Some(SleepTimer::EndOfEpisode)
+        );
+    }
+
+    #[test]
+    fn remaining_time() {
+        let now = Instant::now();
+        let timer = SleepTimer::After {
+            minutes: 15,
+            ends_at: now + Duration::from_secs(90),
+        };
+        assert_eq!(timer.remaining(&now_playing(0, 0, 1.0), now), Some(90.0));
+
+        // Position-based timers count listening time, so speed shortens them.
+        let np = now_playing(300, 1200, 1.5);
+        let end_of_chapter = SleepTimer::EndOfChapter { end: 600.0 };
+        assert_eq!(end_of_chapter.remaining(&np, now), Some(200.0));
+        assert_eq!(SleepTimer::EndOfEpisode.remaining(&np, now), Some(600.0));
+        // Once the chapter is over the timer stays run out.
+        assert_eq!(end_of_chapter.remaining(&now_playing(601, 1200, 1.0), now), Some(0.0));
+        assert_eq!(SleepTimer::EndOfEpisode.remaining(&now_playing(10, 0, 1.0), now), None);
+    }
+
+    #[test]
+    fn fades_over_the_last_seconds() {
+        assert_eq!(fade_volume(80, 120.0), 80);
+        assert_eq!(fade_volume(80, 15.0), 40);
+        assert_eq!(fade_volume(80, 0.0), 0);
+    }
+}
@@ -1,12 +1,15 @@
 use anyhow::Context;
 use tokio::sync::mpsc;
 
-use crate::api_client::ApiClient;
+use pod_model::Episode;
+
+use crate::api_client::{ApiClient, CursorExpired, QueueRejected};
 use crate::app::Action;
 use crate::local_db::LocalDb;
 
-/// Run a full sync cycle: pull podcast list, pull episode changes, pull
-/// progress changes, and push dirty local progress.
+/// Run a full sync cycle: pull podcast list, push local queue edits, pull
+/// episode, subscription, queue and playlist changes, pull progress changes,
+/// and push dirty local progress.
 ///
 /// Opens its own SQLite connection from the given path so the future is
 /// `Send` (rusqlite::Connection is not Sync).
@@ -25,26 +28,90 @@ pub async fn run_sync(db_path: &str, tx: mpsc::UnboundedSender<Action>) -> anyho
     let _ = tx.send(Action::SyncProgress("Fetching podcast list…".to_string()));
     let podcasts = client.list_podcasts().await.context("fetch podcast list")?;
     let total = podcasts.len();
+    // Podcasts we had before this sync, whose episodes are already stored.
+    let mut known_podcasts: Vec<String> =
+        db.list_podcasts().into_iter().map(|p| p.id).collect();
     for p in &podcasts {
         db.upsert_podcast(p);
     }
 
-    // ---- 2. Pull episode changes (delta sync) ----
-    let episode_cursor = db.get_sync_state("episode_cursor");
-    if let Some(cursor) = &episode_cursor {
+    // ---- 2. Push local queue edits ----
+    push_queue(db_path, &client).await?;
+
+    // ---- 3. Pull episode and queue changes (delta sync) ----
+    let mut episode_cursor = db.get_sync_state("episode_cursor");
+    if let Some(cursor) = episode_cursor.clone() {
         // Incremental: use /sync/changes.
         let _ = tx.send(Action::SyncProgress("Syncing episode changes…".to_string()));
-        let mut since = cursor.clone();
+        let mut since = cursor;
         loop {
-            let resp = client
-                .sync_changes(&since, 500)
-                .await
-                .context("fetch sync changes")?;
+            let resp = match client.sync_changes(&since, 500).await {
+                Err(e) if e.is::<CursorExpired>() => {
+                    // The server compacted its change log past our cursor;
+                    // drop it and fall through to a full re-bootstrap.
+                    tracing::info!("sync cursor expired, re-bootstrapping");
+                    db.delete_sync_state("episode_cursor");
+                    episode_cursor = None;
+                    break;
+                }
+                resp => resp.context("fetch sync changes")?,
+            };
             for change in &resp.changes {
-                if change.op == "upsert" {
-                    db.upsert_episode(&change.episode);
+                match (change.change_type.as_str(), change.op.as_str()) {
+                    ("episode", "upsert") => {
+                        if let Some(episode) = &change.episode {
+                            db.upsert_episode(episode);
+                        }
+                    }
+                    ("subscription", "upsert") => {
+                        // Episodes published before the subscription are
+                        // behind our cursor, so backfill them from the
+                        // episode list. Skip podcasts that are no longer in
+                        // the list; a later delete change covers those.
+                        // Podcasts we already have only changed settings.
+                        if let Some(p) = podcasts.iter().find(|p| p.id == change.podcast_id) {
+                            if !known_podcasts.contains(&p.id) {
+                                let episodes = fetch_podcast_episodes(&client, &p.id).await?;
+                                db.upsert_podcast(p);
+                                store_podcast_episodes(&db, &episodes);
+                                known_podcasts.push(p.id.clone());
+                            }
+                            if let Some(settings) = &change.settings {
+                                db.set_podcast_settings(&p.id, settings);
+                            }
+                        }
+                    }
+                    ("subscription", "delete") => {
+                        for path in db.delete_podcast(&change.podcast_id) {
+                            let _ = std::fs::remove_file(path);
+                        }
+                        // A later resubscribe has to fetch the episodes
+                        // again.
+                        known_podcasts.retain(|id| id != &change.podcast_id);
+                    }
+                    ("episode", "delete") => {
+                        if let Some(tombstone) = &change.episode_tombstone {
+                            if let Some(path) = db.delete_episode(&tombstone.id) {
+                                let _ = std::fs::remove_file(path);
+                            }
+                        }
+                    }
+                    ("queue", _) => {
+                        // Edits made since the push above win; they go out
+                        // on the next cycle.
+                        if let Some(queue) = &change.queue {
+                            if !db.queue_dirty() {
+                                db.set_queue(queue, false);
+                            }
+                        }
+                    }
+                    ("playlists", _) => {
+                        if let Some(playlists) = &change.playlists {
+                            db.set_playlists(playlists);
+                        }
+                    }
+                    _ => {}
                 }
-                // TODO: handle "delete" ops.
             }
             since = resp.next_since.clone();
             if !resp.has_more {
@@ -54,40 +121,59 @@ pub async fn run_sync(db_path: &str, tx: mpsc::UnboundedSender<Action>) -> anyho
                 break;
             }
         }
-    } else {
-        // Initial bootstrap: get head cursor, then fetch all episodes via
-        // the paginated episode list API.
+    }
+    if episode_cursor.is_none() {
+        // Initial bootstrap (or re-bootstrap after an expired cursor): get
+        // head cursor, then fetch all episodes via the paginated episode
+        // list API.
         let head = client.sync_head().await.context("fetch sync head")?;
 
+        // On re-bootstrap we may hold podcasts and episodes whose delete
+        // changes were compacted away; drop anything the server no longer
+        // lists.
+        for local in db.list_podcasts() {
+            if !podcasts.iter().any(|p| p.id == local.id) {
+                for path in db.delete_podcast(&local.id) {
+                    let _ = std::fs::remove_file(path);
+                }
+            }
+        }
+
         for (i, p) in podcasts.iter().enumerate() {
             let _ = tx.send(Action::SyncProgress(format!(
                 "Syncing episodes ({}/{total}): {}",
                 i + 1,
                 p.title
             )));
-            let mut page_token: Option<String> = None;
-            loop {
-                let (episodes, next_token) = client
-                    .list_episodes(&p.id, 100, page_token.as_deref())
-                    .await
-                    .with_context(|| format!("fetch episodes for podcast {}", p.id))?;
-                for (episode, progress, done) in &episodes {
-                    db.upsert_episode(episode);
-                    if let Some(progress) = progress {
-                        db.upsert_progress(&episode.id, *progress, *done, false);
+            let episodes = fetch_podcast_episodes(&client, &p.id).await?;
+            for local in db.list_episodes(&p.id) {
+                if !episodes.iter().any(|(e, _, _)| e.id == local.id) {
+                    if let Some(path) = db.delete_episode(&local.id) {
+                        let _ = std::fs::remove_file(path);
                     }
                 }
-                if next_token.is_none() || episodes.is_empty() {
-                    break;
-                }
-                page_token = next_token;
             }
+            store_podcast_episodes(&db, &episodes);
+            let settings = client
+                .get_podcast_settings(&p.id)
+                .await
+                .with_context(|| format!("fetch settings for podcast {}", p.id))?;
+            db.set_podcast_settings(&p.id, &settings);
+        }
+
+        if !db.queue_dirty() {
+            let queue = client.get_queue().await.context("fetch queue")?;
+            let ids: Vec<String> = queue.into_iter().map(|e| e.episode.id).collect();
+            db.set_queue(&ids, false);
         }
 
+        let playlists = client.list_playlists().await.context("fetch playlists")?;
+        db.set_playlists(&playlists);
+
         db.set_sync_state("episode_cursor", &head);
     }
 
-    // ---- 3. Pull progress changes ----
+    // ---- 4. Pull progress changes ----
     let _ = tx.send(Action::SyncProgress("Syncing playback progress…".to_string()));
     let progress_since = db.get_sync_state("progress_since");
     let progress_resp = client
@@ -104,7 +190,7 @@ pub async fn run_sync(db_path: &str, tx: mpsc::UnboundedSender<Action>) -> anyho
         &progress_resp.server_time.to_rfc3339(),
     );
 
-    // ---- 4. Push dirty local progress ----
+    // ---- 5. Push dirty local progress ----
     let dirty = db.list_dirty_progress();
     for (episode_id, progress, done) in &dirty {
         match client.report_progress(episode_id, *progress, *done).await {
@@ -118,3 +204,179 @@ pub async fn run_sync(db_path: &str, tx: mpsc::UnboundedSender<Action>) -> anyho
 
     Ok(())
 }
+
+/// Push local queue edits to the server, which replaces its copy with them.
+/// The queue the server stored is kept unless the queue was edited again in
+/// the meantime. A queue the server rejects is dropped in favour of the
+/// server's.
+pub async fn push_queue(db_path: &str, client: &ApiClient) -> anyhow::Result<()> {
+    let db = LocalDb::open(db_path).context("open local database")?;
+    if !db.queue_dirty() {
+        return Ok(());
+    }
+    let ids = db.queue_ids();
+    let queue = match client.replace_queue(&ids).await {
+        Err(e) if e.is::<QueueRejected>() => {
+            tracing::warn!("server rejected local queue, using the server's");
+            client.get_queue().await.context("fetch queue")?
+        }
+        queue => queue.context("push queue")?,
+    };
+    if db.queue_ids() == ids {
+        let ids: Vec<String> = queue.into_iter().map(|e| e.episode.id).collect();
+        db.set_queue(&ids, false);
+    }
+    Ok(())
+}
+
+/// Page through a podcast's full episode list, returning every episode
+/// along with any progress the server has for it.
+async fn fetch_podcast_episodes(
+    client: &ApiClient,
+    podcast_id: &str,
+) -> anyhow::Result<Vec<(Episode, Option<i32>, bool)>> {
+    let mut all = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let (episodes, next_token) = client
+            .list_episodes(podcast_id, 100, page_token.as_deref())
+            .await
+            .with_context(|| format!("fetch episodes for podcast {}", podcast_id))?;
+        let done = next_token.is_none() || episodes.is_empty();
+        all.extend(episodes);
+        if done {
+            break;
+        }
+        page_token = next_token;
+    }
+    Ok(all)
+}
+
+/// Store episodes fetched via `fetch_podcast_episodes`. Progress is stored
+/// clean since it came from the server.
+fn store_podcast_episodes(db: &LocalDb, episodes: &[(Episode, Option<i32>, bool)]) {
+    for (episode, progress, done) in episodes {
+        db.upsert_episode(episode);
+        if let Some(progress) = progress {
+            db.upsert_progress(&episode.id, *progress, *done, false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn podcast() -> serde_json::Value {
+        serde_json::json!({
+            "id": "p1",
+            "title": "Show",
+            "description": "",
+            "image_link": "",
+            "feed_url": "https://feed.example",
+            "feed_type": "rss",
+            "created_at": "2024-01-01T00:00:00Z",
+            "last_updated": "2024-01-01T00:00:00Z",
+            "last_publication_date": null,
+        })
+    }
+
+    fn episode() -> serde_json::Value {
+        serde_json::json!({
+            "id": "e1",
+            "podcast_id": "p1",
+            "title": "Episode 1",
+            "summary": "",
+            "summary_type": "text/plain",
+            "content_encoded": "",
+            "content_encoded_type": "text/html",
+            "publication_date": "2024-01-01T00:00:00Z",
+            "audio_url": "https://audio.example/e1.mp3",
+            "audio_type": "audio/mpeg",
+            "audio_duration": 100,
+            "thumbnail_url": null,
+            "created_at": "2024-01-01T00:00:00Z",
+            "last_updated": "2024-01-01T00:00:00Z",
+        })
+    }
+
+    /// Answer every request with the body `route` gives its path, until the
+    /// test ends. Returns the server's URL.
+    async fn serve(route: fn(&str) -> serde_json::Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    let mut byte = [0];
+                    socket.read_exact(&mut byte).await.unwrap();
+                    request.push(byte[0]);
+                }
+                let request = String::from_utf8(request).unwrap();
+                let target = request.split(' ').nth(1).unwrap();
+                let path = target.split('?').next().unwrap();
+                let body = route(path).to_string();
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n",
+                    body.len()
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(body.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn resubscribing_between_syncs_refetches_episodes() {
+        let url = serve(|path| {
+            let now = chrono::Utc::now();
+            match path {
+                "/api/v1/podcasts" => serde_json::json!([podcast()]),
+                "/api/v1/podcasts/p1/episodes" => serde_json::json!({
+                    "items": [
+                        { "episode": episode(), "progress": null, "done": false },
+                    ],
+                    "next_page_token": null,
+                }),
+                // Unsubscribed, then subscribed again.
+                "/api/v1/sync/changes" => serde_json::json!({
+                    "server_time": now,
+                    "next_since": "c2",
+                    "has_more": false,
+                    "changes": [
+                        { "seq": 1, "type": "subscription", "op": "delete", "podcast_id": "p1" },
+                        { "seq": 2, "type": "subscription", "op": "upsert", "podcast_id": "p1" },
+                    ],
+                }),
+                "/api/v1/sync/progress" => serde_json::json!({ "server_time": now, "changes": [] }),
+                other => panic!("unexpected request for {}", other),
+            }
+        })
+        .await;
+
+        let path = std::env::temp_dir().join(format!("pod-sync-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let path = path.to_str().unwrap();
+        let db = LocalDb::open(path).unwrap();
+        db.set_config("server_url", &url);
+        db.set_config("auth_token", "token");
+        db.set_sync_state("episode_cursor", "c1");
+        db.upsert_podcast(&serde_json::from_value(podcast()).unwrap());
+        db.upsert_episode(&serde_json::from_value(episode()).unwrap());
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        run_sync(path, tx).await.unwrap();
+
+        assert_eq!(db.list_podcasts().len(), 1);
+        let episodes: Vec<_> = db.list_episodes("p1").into_iter().map(|e| e.id).collect();
+        assert_eq!(episodes, vec!["e1"]);
+        assert_eq!(db.get_sync_state("episode_cursor").as_deref(), Some("c2"));
+    }
+}
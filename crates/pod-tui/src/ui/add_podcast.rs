@@ -0,0 +1,29 @@
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+
+use crate::app::AddPodcastState;
+
+pub fn render(frame: &mut Frame, state: &AddPodcastState, area: Rect) {
+    let [input_area, message_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    let cursor = if state.loading { "" } else { "▏" };
+    let input = Paragraph::new(format!("{}{}", state.feed_url, cursor)).block(
+        Block::bordered()
+            .title(" Add podcast — feed URL ")
+            .border_style(Style::default().fg(Color::Yellow)),
+    );
+    frame.render_widget(input, input_area);
+
+    let message = if state.loading {
+        Line::styled("Subscribing...", Style::default().fg(Color::Yellow))
+    } else if let Some(ref error) = state.error {
+        Line::styled(error.as_str(), Style::default().fg(Color::Red))
+    } else {
+        Line::from("Paste the RSS feed URL of the show and press Enter.")
+    };
+    frame.render_widget(
+        Paragraph::new(message).wrap(Wrap { trim: false }),
+        message_area.inner(Margin::new(1, 0)),
+    );
+}
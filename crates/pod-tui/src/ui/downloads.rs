@@ -0,0 +1,132 @@
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+
+use super::text;
+use crate::app::{DownloadRow, DownloadsState};
+use crate::local_db::DownloadStatus;
+
+pub fn render(frame: &mut Frame, state: &DownloadsState, area: Rect) {
+    let block = Block::bordered().title(format!(" Downloads ({}) ", state.downloads.len()));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if state.downloads.is_empty() {
+        let msg = "Nothing downloaded yet. Press 'D' on an episode to download it.";
+        frame.render_widget(Paragraph::new(msg), inner);
+        return;
+    }
+
+    // Layout: icon (2) + podcast (variable) + " │ " (3) + title (variable)
+    // + right column (bar + percentage + size ≈ 34) + highlight (2).
+    let highlight_width = 2;
+    let prefix_width = 2; // "↓ "
+    let right_width = 34;
+    let separator_width = 3; // " │ "
+    let available = (inner.width as usize)
+        .saturating_sub(highlight_width + prefix_width + right_width + separator_width);
+    // Split available space: ~1/3 for podcast name, ~2/3 for episode title.
+    let podcast_width = available / 3;
+    let title_width = available.saturating_sub(podcast_width);
+
+    let items: Vec<ListItem> = state
+        .downloads
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            let selected = i == state.selected;
+
+            let title_style = if selected {
+                Style::default().fg(Color::Yellow).bold()
+            } else {
+                Style::default()
+            };
+
+            let podcast_style = if selected {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::Blue)
+            };
+
+            let dim_style = if selected {
+                Style::default().fg(Color::Gray)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+
+            let ep = &d.episode;
+            let podcast_name = ep.podcast_title.as_deref().unwrap_or("?");
+
+            let (icon, color) = match d.status {
+                DownloadStatus::Pending => ("·", Color::DarkGray),
+                DownloadStatus::Downloading => ("↓", Color::Yellow),
+                DownloadStatus::Complete => ("●", Color::Magenta),
+                DownloadStatus::Failed => ("!", Color::Red),
+            };
+
+            let mut spans = vec![
+                Span::styled(format!("{} ", icon), Style::default().fg(color)),
+                Span::styled(text::pad(podcast_name, podcast_width), podcast_style),
+                Span::styled(" │ ", dim_style),
+                Span::styled(text::pad(&ep.title, title_width), title_style),
+            ];
+            spans.extend(status_spans(d, right_width, dim_style));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_symbol("▸ ");
+
+    let mut list_state = ListState::default().with_selected(Some(state.selected));
+    frame.render_stateful_widget(list, inner, &mut list_state);
+}
+
+/// The right-hand column: a progress bar while downloading, the size once
+/// complete, or why it failed.
+fn status_spans(d: &DownloadRow, width: usize, dim_style: Style) -> Vec<Span<'static>> {
+    match d.status {
+        DownloadStatus::Complete => vec![Span::styled(
+            format!("{:>width$}", format_size(d.total_bytes.max(d.downloaded_bytes))),
+            dim_style,
+        )],
+        DownloadStatus::Failed => {
+            let error = d.error.as_deref().unwrap_or("failed");
+            vec![Span::styled(
+                format!("  {}", text::pad(error, width - 2)),
+                Style::default().fg(Color::Red),
+            )]
+        }
+        DownloadStatus::Pending | DownloadStatus::Downloading => {
+            let pct = (d.downloaded_bytes * 100)
+                .checked_div(d.total_bytes)
+                .unwrap_or(0)
+                .clamp(0, 100) as u16;
+            let size = if d.total_bytes > 0 {
+                format!("{}/{}", format_size_value(d.downloaded_bytes), format_size(d.total_bytes))
+            } else {
+                format_size(d.downloaded_bytes)
+            };
+            vec![
+                Span::styled(format!("  {}", format_progress_bar(pct, 10)), Style::default().fg(Color::Cyan)),
+                Span::styled(format!(" {:>3}% ", pct), dim_style),
+                Span::styled(format!("{:>14}", size), dim_style),
+            ]
+        }
+    }
+}
+
+/// `bytes` in megabytes, e.g. "27.0 MB".
+fn format_size(bytes: i64) -> String {
+    format!("{} MB", format_size_value(bytes))
+}
+
+fn format_size_value(bytes: i64) -> String {
+    format!("{:.1}", bytes as f64 / 1_000_000.0)
+}
+
+fn format_progress_bar(pct: u16, width: usize) -> String {
+    let filled = (pct as usize * width / 100).min(width);
+    let empty = width - filled;
+    format!("[{}{}]", "█".repeat(filled), "░".repeat(empty))
+}
@@ -1,9 +1,20 @@
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 
-use crate::app::EpisodeDetailState;
+use super::text;
+use crate::app::{EpisodeDetailState, NowPlaying};
+
+pub fn render(
+    frame: &mut Frame,
+    state: &EpisodeDetailState,
+    now_playing: Option<&NowPlaying>,
+    area: Rect,
+) {
+    if state.show_transcript {
+        render_transcript(frame, state, now_playing, area);
+        return;
+    }
 
-pub fn render(frame: &mut Frame, state: &EpisodeDetailState, area: Rect) {
     let block = Block::bordered().title(format!(" {} ", state.episode.title));
     let inner = block.inner(area);
     frame.render_widget(block, area);
@@ -30,6 +41,12 @@ pub fn render(frame: &mut Frame, state: &EpisodeDetailState, area: Rect) {
         Span::styled("Duration: ", Style::default().fg(Color::DarkGray)),
         Span::raw(duration_str),
     ]));
+    if let Some(label) = state.episode.label() {
+        lines.push(Line::from(vec![
+            Span::styled("Episode: ", Style::default().fg(Color::DarkGray)),
+            Span::raw(label),
+        ]));
+    }
 
     lines.push(Line::raw(""));
     lines.push(Line::styled(
@@ -56,6 +73,64 @@ pub fn render(frame: &mut Frame, state: &EpisodeDetailState, area: Rect) {
     frame.render_widget(paragraph, inner);
 }
 
+/// The transcript from `transcript_offset` on, one paragraph per segment,
+/// with the segment being played highlighted.
+fn render_transcript(
+    frame: &mut Frame,
+    state: &EpisodeDetailState,
+    now_playing: Option<&NowPlaying>,
+    area: Rect,
+) {
+    let block = Block::bordered().title(format!(" {} — Transcript ", state.episode.title));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let segments = match state.transcript {
+        None => {
+            frame.render_widget(Paragraph::new("Loading transcript…"), inner);
+            return;
+        }
+        Some(ref segments) if segments.is_empty() => {
+            frame.render_widget(Paragraph::new("No transcript available"), inner);
+            return;
+        }
+        Some(ref segments) => segments,
+    };
+
+    let playing = state.playing_segment(now_playing);
+    let mut lines = Vec::new();
+    let mut last_speaker = None;
+    // Every segment takes at least one line, so this is all that can show.
+    for (i, segment) in segments
+        .iter()
+        .enumerate()
+        .skip(state.transcript_offset)
+        .take(inner.height as usize)
+    {
+        let body_style = if Some(i) == playing {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let mut spans = vec![Span::styled(
+            format!("{:>7} ", text::format_time(segment.start_time as i32)),
+            Style::default().fg(Color::DarkGray),
+        )];
+        // Only name the speaker when it changes.
+        if segment.speaker.is_some() && segment.speaker != last_speaker {
+            spans.push(Span::styled(
+                format!("{}: ", segment.speaker.as_deref().unwrap_or_default()),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        last_speaker = segment.speaker.clone();
+        spans.push(Span::styled(segment.body.clone(), body_style));
+        lines.push(Line::from(spans));
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
 /// Simple HTML tag stripping. For an MVP this is sufficient; a proper
 /// implementation would use the `html2text` crate.
 fn strip_html(html: &str) -> String {
@@ -69,6 +69,10 @@ pub fn render(frame: &mut Frame, state: &EpisodeListState, area: Rect) {
             };
 
             // Auto-scroll long titles on the selected row.
+            // Episode number or type goes in front of the title and takes
+            // its width from the title column.
+            let label = ep.label().map(|l| format!("{} ", l)).unwrap_or_default();
+            let title_width = title_width.saturating_sub(label.len());
             let title_display = if selected {
                 text::scroll(&ep.title, title_width, state.scroll_tick)
             } else {
@@ -87,6 +91,7 @@ pub fn render(frame: &mut Frame, state: &EpisodeListState, area: Rect) {
                     if ep.done { Color::Green } else { Color::DarkGray }
                 )),
                 Span::styled(format!("{} ", dl_icon), Style::default().fg(dl_color)),
+                Span::styled(label, dim_style),
                 Span::styled(title_display, style),
                 Span::styled(format!("  {}  ", date), dim_style),
                 Span::styled(format!("{:>6} ", duration), dim_style),
@@ -11,13 +11,19 @@ pub fn render(
     sync_status: Option<&str>,
     area: Rect,
 ) {
-    let block = Block::bordered().title(" Inbox ");
+    let title = match &state.playlist {
+        Some(playlist) => format!(" {} ", playlist.name),
+        None => " Inbox ".to_string(),
+    };
+    let block = Block::bordered().title(title);
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
     if state.episodes.is_empty() {
         let msg = if let Some(status) = sync_status {
             status.to_string()
+        } else if state.playlist.is_some() {
+            "No episodes match this playlist.".to_string()
         } else {
             "No new episodes. Press 'r' to sync.".to_string()
         };
@@ -75,6 +81,10 @@ pub fn render(
             let date = &ep.publication_date[..10.min(ep.publication_date.len())];
             let podcast_name = ep.podcast_title.as_deref().unwrap_or("?");
 
+            // Episode number or type goes in front of the title and takes
+            // its width from the title column.
+            let label = ep.label().map(|l| format!("{} ", l)).unwrap_or_default();
+            let title_width = title_width.saturating_sub(label.len());
             let title_display = if selected {
                 text::scroll(&ep.title, title_width, state.scroll_tick)
             } else {
@@ -95,6 +105,7 @@ pub fn render(
                 Span::styled(format!("{} ", dl_icon), Style::default().fg(dl_color)),
                 Span::styled(text::pad(podcast_name, podcast_width), podcast_style),
                 Span::styled(" │ ", dim_style),
+                Span::styled(label, dim_style),
                 Span::styled(title_display, title_style),
                 Span::styled(format!("  {}  ", date), dim_style),
                 Span::styled(format!("{:>6} ", duration), dim_style),
@@ -1,10 +1,15 @@
+mod add_podcast;
+mod downloads;
 mod episode_detail;
 mod episode_list;
 mod inbox;
 mod login;
 mod podcast_list;
+mod podcast_settings;
+mod queue;
+mod search;
 mod status_bar;
-mod text;
+pub(crate) mod text;
 
 use ratatui::Frame;
 
@@ -31,7 +36,14 @@ pub fn render(frame: &mut Frame, app: &App) {
         }
         View::PodcastList(state) => podcast_list::render(frame, state, content_area),
         View::EpisodeList(state) => episode_list::render(frame, state, content_area),
-        View::EpisodeDetail(state) => episode_detail::render(frame, state, content_area),
+        View::EpisodeDetail(state) => {
+            episode_detail::render(frame, state, app.now_playing.as_ref(), content_area)
+        }
+        View::Search(state) => search::render(frame, state, content_area),
+        View::Queue(state) => queue::render(frame, state, content_area),
+        View::Downloads(state) => downloads::render(frame, state, content_area),
+        View::AddPodcast(state) => add_podcast::render(frame, state, content_area),
+        View::PodcastSettings(state) => podcast_settings::render(frame, state, content_area),
     }
 
     status_bar::render(frame, app, status_area);
@@ -19,7 +19,7 @@ pub fn render(frame: &mut Frame, state: &PodcastListState, area: Rect) {
 
     if state.podcasts.is_empty() {
         frame.render_widget(
-            Paragraph::new("No podcasts. Press 'r' to sync."),
+            Paragraph::new("No podcasts. Press 'a' to add one or 'r' to sync."),
             inner,
         );
         return;
@@ -41,10 +41,19 @@ pub fn render(frame: &mut Frame, state: &PodcastListState, area: Rect) {
                 Style::default()
             };
 
-            ListItem::new(Line::from(vec![
+            let mut spans = vec![
                 Span::styled(text::pad(&p.title, 50), style),
                 Span::styled(date, Style::default().fg(Color::DarkGray)),
-            ]))
+            ];
+            // The server keeps retrying failing feeds; say why they fail.
+            if p.consecutive_failures > 0 {
+                let error = p.last_error.as_deref().unwrap_or("fetch failed");
+                spans.push(Span::styled(
+                    format!("  ! {} ({}×)", error, p.consecutive_failures),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
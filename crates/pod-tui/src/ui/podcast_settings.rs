@@ -0,0 +1,79 @@
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+
+use crate::app::{PodcastSettingsState, SettingField};
+
+pub fn render(frame: &mut Frame, state: &PodcastSettingsState, area: Rect) {
+    let title = if state.saving {
+        format!(" {} — Settings (saving...) ", state.podcast_title)
+    } else {
+        format!(" {} — Settings ", state.podcast_title)
+    };
+    let block = Block::bordered().title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let label_width = 20;
+    let items: Vec<ListItem> = SettingField::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let selected = i == state.selected;
+            let label_style = if selected {
+                Style::default().fg(Color::Yellow).bold()
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            let value_style = if selected && state.editing {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<label_width$}", label(*field)), label_style),
+                Span::styled(value(state, *field), value_style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_symbol("▸ ");
+
+    let mut list_state = ListState::default().with_selected(Some(state.selected));
+    frame.render_stateful_widget(list, inner, &mut list_state);
+}
+
+fn label(field: SettingField) -> &'static str {
+    match field {
+        SettingField::PlaybackSpeed => "Playback speed",
+        SettingField::SkipIntro => "Skip intro",
+        SettingField::SkipOutro => "Skip outro",
+        SettingField::AutoDownload => "Auto-download",
+        SettingField::HideFromInbox => "Hide from inbox",
+        SettingField::Notifications => "Notifications",
+        SettingField::TitleOverride => "Title override",
+        SettingField::State => "State",
+    }
+}
+
+fn value(state: &PodcastSettingsState, field: SettingField) -> String {
+    let s = &state.settings;
+    let on_off = |b: bool| if b { "on" } else { "off" }.to_string();
+    match field {
+        SettingField::PlaybackSpeed => format!("{:.2}x", s.playback_speed),
+        SettingField::SkipIntro => format!("{}s", s.skip_intro_secs),
+        SettingField::SkipOutro => format!("{}s", s.skip_outro_secs),
+        SettingField::AutoDownload if s.auto_download == 0 => "off".to_string(),
+        SettingField::AutoDownload => format!("newest {}", s.auto_download),
+        SettingField::HideFromInbox => on_off(s.hide_from_inbox),
+        SettingField::Notifications => on_off(s.notifications),
+        SettingField::TitleOverride if state.editing => {
+            format!("{}▏", s.title_override.as_deref().unwrap_or(""))
+        }
+        SettingField::TitleOverride => {
+            s.title_override.clone().unwrap_or_else(|| "(feed title)".to_string())
+        }
+        SettingField::State => s.state.as_str().to_string(),
+    }
+}
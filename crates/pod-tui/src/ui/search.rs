@@ -0,0 +1,108 @@
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+
+use super::text;
+use crate::app::SearchState;
+use crate::local_db::{MATCH_END, MATCH_START};
+
+pub fn render(frame: &mut Frame, state: &SearchState, area: Rect) {
+    let [input_area, results_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    // Query input, with a cursor while typing.
+    let input_style = if state.editing {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    let cursor = if state.editing { "▏" } else { "" };
+    let input = Paragraph::new(format!("{}{}", state.query, cursor))
+        .block(Block::bordered().title(" Search ").border_style(input_style));
+    frame.render_widget(input, input_area);
+
+    let block = Block::bordered().title(format!(" {} results ", state.results.len()));
+    let inner = block.inner(results_area);
+    frame.render_widget(block, results_area);
+
+    if state.results.is_empty() {
+        let msg = if state.query.trim().is_empty() {
+            "Type to search episode titles, descriptions and podcast names."
+        } else {
+            "No matching episodes."
+        };
+        frame.render_widget(Paragraph::new(msg), inner);
+        return;
+    }
+
+    // Layout: "[✓] " (4) + podcast (1/3) + " │ " (3) + title + date (12)
+    // + highlight (2).
+    let available = (inner.width as usize).saturating_sub(4 + 3 + 12 + 2);
+    let podcast_width = available / 3;
+    let title_width = available.saturating_sub(podcast_width);
+
+    let items: Vec<ListItem> = state
+        .results
+        .iter()
+        .enumerate()
+        .map(|(i, hit)| {
+            let ep = &hit.episode;
+            let selected = i == state.selected && !state.editing;
+            let title_style = if selected {
+                Style::default().fg(Color::Yellow).bold()
+            } else {
+                Style::default()
+            };
+            let dim_style = Style::default().fg(Color::DarkGray);
+
+            let label = ep.label().map(|l| format!("{} ", l)).unwrap_or_default();
+            let date = &ep.publication_date[..10.min(ep.publication_date.len())];
+            let header = Line::from(vec![
+                Span::styled(
+                    format!("[{}] ", if ep.done { "✓" } else { " " }),
+                    Style::default().fg(if ep.done { Color::Green } else { Color::DarkGray }),
+                ),
+                Span::styled(
+                    text::pad(ep.podcast_title.as_deref().unwrap_or("?"), podcast_width),
+                    Style::default().fg(Color::Blue),
+                ),
+                Span::styled(" │ ", dim_style),
+                Span::styled(label.clone(), dim_style),
+                Span::styled(
+                    text::pad(&ep.title, title_width.saturating_sub(label.len())),
+                    title_style,
+                ),
+                Span::styled(format!("  {}", date), dim_style),
+            ]);
+
+            let mut snippet = vec![Span::raw("    ")];
+            snippet.extend(snippet_spans(&hit.snippet, dim_style));
+            ListItem::new(vec![header, Line::from(snippet)])
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_symbol("▸ ");
+
+    let selected = (!state.editing).then_some(state.selected);
+    let mut list_state = ListState::default().with_selected(selected);
+    frame.render_stateful_widget(list, inner, &mut list_state);
+}
+
+/// Split a search snippet into spans, highlighting the matched terms.
+fn snippet_spans(snippet: &str, style: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut rest = snippet;
+    while let Some(start) = rest.find(MATCH_START) {
+        spans.push(Span::styled(rest[..start].to_string(), style));
+        rest = &rest[start + MATCH_START.len_utf8()..];
+        let end = rest.find(MATCH_END).unwrap_or(rest.len());
+        spans.push(Span::styled(
+            rest[..end].to_string(),
+            Style::default().fg(Color::Yellow),
+        ));
+        rest = rest.get(end + MATCH_END.len_utf8()..).unwrap_or("");
+    }
+    spans.push(Span::styled(rest.to_string(), style));
+    spans
+}
@@ -9,8 +9,8 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     // for view info. Otherwise squeeze into one line.
     if let Some(ref np) = app.now_playing {
         let pause_icon = if np.state.paused { "⏸" } else { "▶" };
-        let pos = format_time(np.state.position_secs);
-        let dur = format_time(np.state.duration_secs);
+        let pos = text::format_time(np.state.position_secs);
+        let dur = text::format_time(np.state.duration_secs);
         let pct = if np.state.duration_secs > 0 {
             (np.state.position_secs as f64 / np.state.duration_secs as f64 * 100.0) as u16
         } else {
@@ -20,14 +20,32 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
 
         let sync_indicator = if app.syncing { " [syncing...]" } else { "" };
 
+        let (chapter, chapter_help) = match np.current_chapter() {
+            Some(c) => (format!(" › {}", text::truncate(&c.title, 25)), " │ [/]: chapter"),
+            None => (String::new(), ""),
+        };
+
+        let silence = if app.skip_silence { " skip silence" } else { "" };
+        let sleep = app
+            .sleep_timer
+            .and_then(|timer| timer.remaining(np, std::time::Instant::now()))
+            .map(|secs| format!(" sleep {}", text::format_time(secs.ceil() as i32)))
+            .unwrap_or_default();
+
         let playback_line = format!(
-            " {} {} {}/{} {}{} │ Space: pause │ ←/→: seek │ s: stop",
+            " {} {}{} {}/{} {} {:.1}x vol {}%{}{}{} │ Space: pause │ ←/→: seek{} │ +/-: speed │ 9/0: volume │ S: silence │ z: sleep │ s: stop",
             pause_icon,
             text::truncate(&np.episode_title, 30),
+            chapter,
             pos,
             dur,
             bar,
+            np.speed,
+            app.volume,
+            silence,
+            sleep,
             sync_indicator,
+            chapter_help,
         );
 
         let playback_bar = Paragraph::new(playback_line)
@@ -40,14 +58,27 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
             View::PodcastList(_) => "Podcasts",
             View::EpisodeList(_) => "Episodes",
             View::EpisodeDetail(_) => "Detail",
+            View::Search(_) => "Search",
+            View::Queue(_) => "Queue",
+            View::Downloads(_) => "Downloads",
+            View::AddPodcast(_) => "Add podcast",
+            View::PodcastSettings(_) => "Settings",
         };
 
         let help = match &app.view {
             View::Login(_) => "Tab: next field | Enter: submit | Esc: quit",
-            View::Inbox(_) => "j/k: navigate | Enter: detail | p: play | d: done | D: download | l: podcasts | r: sync",
-            View::PodcastList(_) => "j/k: navigate | Enter: select | i: inbox | r: sync | q: quit",
-            View::EpisodeList(_) => "j/k: navigate | Enter: detail | p: play | d: done | D: download | Esc: back",
-            View::EpisodeDetail(_) => "j/k: scroll | Enter: play | D: download | Esc: back | q: quit",
+            View::Inbox(_) => "j/k: navigate | Enter: detail | p: play | d: done | D: download | a: queue | u: up next | o: downloads | Tab: playlists | l: podcasts | /: search | r: sync",
+            View::PodcastList(_) => "j/k: navigate | Enter: select | a: add | e: settings | X: unsubscribe | i: inbox | u: up next | o: downloads | /: search | r: sync | q: quit",
+            View::EpisodeList(_) => "j/k: navigate | Enter: detail | p: play | d: done | D: download | a: queue | u: up next | o: downloads | /: search | Esc: back",
+            View::EpisodeDetail(s) if s.show_transcript => "j/k: scroll | f: follow | t: description | Enter: play | Esc: back",
+            View::EpisodeDetail(_) => "j/k: scroll | Enter: play | D: download | a: queue | t: transcript | Esc: back | q: quit",
+            View::Search(s) if s.editing => "Type to search | Enter: results | Esc: back",
+            View::Search(_) => "j/k: navigate | Enter: detail | p: play | d: done | D: download | a: queue | /: edit | Esc: back",
+            View::Queue(_) => "j/k: navigate | J/K: move | Enter: detail | p: play | x: remove | C: clear | d: done | o: downloads | Esc: back",
+            View::Downloads(_) => "j/k: navigate | Enter: detail | p: play | c: cancel | r: retry | x: delete | Esc: back",
+            View::AddPodcast(_) => "Type the feed URL | Enter: subscribe | Esc: back",
+            View::PodcastSettings(s) if s.editing => "Type the title | Enter: done",
+            View::PodcastSettings(_) => "j/k: navigate | h/l: change | Enter: edit title | w: save | Esc: back",
         };
 
         let sync_indicator = if app.syncing { " [syncing...]" } else { "" };
@@ -64,18 +95,6 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     }
 }
 
-fn format_time(secs: i32) -> String {
-    let h = secs / 3600;
-    let m = (secs % 3600) / 60;
-    let s = secs % 60;
-    if h > 0 {
-        format!("{}:{:02}:{:02}", h, m, s)
-    } else {
-        format!("{}:{:02}", m, s)
-    }
-}
-
-
 fn progress_bar(pct: u16, width: usize) -> String {
     let filled = (pct as usize * width / 100).min(width);
     let empty = width - filled;
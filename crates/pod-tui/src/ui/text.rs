@@ -58,7 +58,7 @@ pub fn scroll(s: &str, max_width: usize, tick: usize) -> String {
     let speed = 2;
     let pause = 3;
     let overflow = width - max_width;
-    let steps = (overflow + speed - 1) / speed; // ceil division
+    let steps = overflow.div_ceil(speed);
     let cycle_len = pause + steps + pause;
     let pos_in_cycle = tick % cycle_len;
 
@@ -102,6 +102,18 @@ pub fn scroll(s: &str, max_width: usize, tick: usize) -> String {
     result
 }
 
+/// Format seconds as `m:ss`, or `h:mm:ss` from an hour on.
+pub fn format_time(secs: i32) -> String {
+    let h = secs / 3600;
+    let m = (secs % 3600) / 60;
+    let s = secs % 60;
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;